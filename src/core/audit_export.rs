@@ -0,0 +1,96 @@
+// src/core/audit_export.rs
+// 入站连接记录的导出：CSV 中的身份码等字段由对方提供，导出时避免被表格软件当作公式执行
+use std::borrow::Cow;
+use serde::Serialize;
+use crate::core::db::ConnectionEvent;
+use crate::core::error::{NearbyError, Result};
+
+pub struct AuditExport;
+
+#[derive(Serialize)]
+struct CsvRow<'a> {
+    occurred_at: Cow<'a, str>,
+    source_address: Cow<'a, str>,
+    claimed_identity: Cow<'a, str>,
+    authenticated_identity: Cow<'a, str>,
+    outcome: &'static str,
+    bytes: u64,
+}
+
+impl AuditExport {
+    /// 将入站连接记录转换为 CSV 文本
+    pub fn to_csv(events: &[ConnectionEvent]) -> Result<String> {
+        let csv_error = |e: csv::Error| NearbyError::validation(format!("生成 CSV 失败: {}", e));
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        for event in events {
+            writer
+                .serialize(CsvRow {
+                    occurred_at: Self::neutralize_formula(&event.occurred_at),
+                    source_address: Self::neutralize_formula(&event.source_address),
+                    claimed_identity: Self::neutralize_formula(&event.claimed_identity),
+                    authenticated_identity: Self::neutralize_formula(&event.authenticated_identity),
+                    outcome: event.outcome.as_str(),
+                    bytes: event.bytes,
+                })
+                .map_err(csv_error)?;
+        }
+        let bytes = writer
+            .into_inner()
+            .map_err(|e| NearbyError::validation(format!("生成 CSV 失败: {}", e)))?;
+        String::from_utf8(bytes).map_err(|e| NearbyError::validation(format!("生成 CSV 失败: {}", e)))
+    }
+    
+    // 以 = + - @ 或制表符、回车开头的字段会被表格软件当作公式，前面加单引号按文本显示
+    pub fn neutralize_formula(field: &str) -> Cow<'_, str> {
+        if field.starts_with(['=', '+', '-', '@', '\t', '\r']) {
+            Cow::Owned(format!("'{}", field))
+        } else {
+            Cow::Borrowed(field)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::db::ConnectionOutcome;
+    
+    #[test]
+    fn neutralizes_formula_prefixes() {
+        for field in ["=1+1", "+1", "-1", "@SUM(A1)", "\tcmd", "\rcmd"] {
+            assert_eq!(AuditExport::neutralize_formula(field), format!("'{}", field));
+        }
+        for field in ["", "2001:db8::1", "abc=1", "'quoted"] {
+            assert!(matches!(AuditExport::neutralize_formula(field), Cow::Borrowed(f) if f == field));
+        }
+    }
+    
+    #[test]
+    fn csv_quotes_and_neutralizes_fields() {
+        let event = ConnectionEvent {
+            id: 1,
+            occurred_at: "2024-01-01 00:00:00".to_string(),
+            source_address: "[2001:db8::1]:6789".to_string(),
+            claimed_identity: "=HYPERLINK(\"http://x\",\"a,b\")".to_string(),
+            authenticated_identity: "@cmd".to_string(),
+            outcome: ConnectionOutcome::RejectedBlocked,
+            bytes: 42,
+        };
+        let csv = AuditExport::to_csv(&[event]).unwrap();
+        let mut lines = csv.lines();
+        assert_eq!(
+            lines.next(),
+            Some("occurred_at,source_address,claimed_identity,authenticated_identity,outcome,bytes"),
+        );
+        assert_eq!(
+            lines.next(),
+            Some("2024-01-01 00:00:00,[2001:db8::1]:6789,\"'=HYPERLINK(\"\"http://x\"\",\"\"a,b\"\")\",'@cmd,rejected_blocked,42"),
+        );
+        assert_eq!(lines.next(), None);
+    }
+    
+    #[test]
+    fn csv_without_events_is_empty() {
+        assert_eq!(AuditExport::to_csv(&[]).unwrap(), "");
+    }
+}
@@ -0,0 +1,204 @@
+// src/core/backup.rs
+// 完整档案（数据库中的身份密钥、联系人、白名单、接收记录和设置）的加密备份与恢复
+//
+// 备份文件格式：
+//   "NEARBYBK" | 格式版本 (u16, 大端) | 盐 (16 字节) | 加密后的内容
+// 文件头（前三项）作为加密的附加数据，被修改时无法解密
+// 格式版本 1 的内容（解密后）：
+//   清单长度 (u32, 大端) | 清单 (JSON) | 数据库文件
+use std::path::Path;
+use chrono::Utc;
+use log::{error, info};
+use serde::{Deserialize, Serialize};
+use crate::core::db::{AddressBook, DB_TIME_FORMAT};
+use crate::core::error::{NearbyError, Result};
+use crate::core::filereceiver::{FileReceiver, ReceiveStatus};
+use crate::core::key_vault::{KeyVault, SALT_LEN};
+use crate::core::migrations;
+use crate::core::settings::Settings;
+
+const MAGIC: &[u8; 8] = b"NEARBYBK";
+// 当前写入的格式版本，读取时支持所有不高于它的版本
+const FORMAT_VERSION: u16 = 1;
+const HEADER_LEN: usize = MAGIC.len() + 2 + SALT_LEN;
+
+// 备份文件的扩展名
+pub const BACKUP_EXTENSION: &str = "nbk";
+
+pub struct ProfileBackup;
+
+// 备份清单，恢复时用于检查和显示
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BackupManifest {
+    pub schema_version: i64, // 数据库的 user_version，恢复后按迁移升级
+    pub created_at: String,  // 备份时间（UTC）
+    pub identity: String,    // 备份时的身份码
+}
+
+impl ProfileBackup {
+    // 生成备份文件内容
+    pub fn export(passphrase: &str) -> Result<Vec<u8>> {
+        let snapshot = AddressBook::get_db_path().with_extension("db.export");
+        std::fs::remove_file(&snapshot).ok();
+        AddressBook::snapshot_database(&snapshot)?;
+        let database = std::fs::read(&snapshot);
+        std::fs::remove_file(&snapshot).ok();
+        let database = database?;
+        
+        let manifest = BackupManifest {
+            schema_version: migrations::latest_version(),
+            created_at: Utc::now().format(DB_TIME_FORMAT).to_string(),
+            identity: AddressBook::get_my_identity()?,
+        };
+        let archive = Self::encode(&manifest, &database, passphrase)?;
+        info!("已生成档案备份（数据库 {} 字节）", database.len());
+        Ok(archive)
+    }
+    
+    // 加密清单和数据库文件，文件头作为附加数据一起认证，修改格式版本或盐都会导致解密失败
+    fn encode(manifest: &BackupManifest, database: &[u8], passphrase: &str) -> Result<Vec<u8>> {
+        let manifest_json = serde_json::to_vec(manifest)
+            .map_err(|e| NearbyError::validation(format!("生成备份清单失败: {}", e)))?;
+        
+        let mut payload = (manifest_json.len() as u32).to_be_bytes().to_vec();
+        payload.extend_from_slice(&manifest_json);
+        payload.extend_from_slice(database);
+        
+        let salt = KeyVault::new_salt();
+        let key = KeyVault::derive_key(passphrase, &salt)?;
+        let mut archive = MAGIC.to_vec();
+        archive.extend_from_slice(&FORMAT_VERSION.to_be_bytes());
+        archive.extend_from_slice(&salt);
+        let sealed = KeyVault::seal_with_aad(&key, &payload, &archive);
+        archive.extend_from_slice(&sealed);
+        Ok(archive)
+    }
+    
+    // 从备份文件恢复档案，当前数据库保留为 address_book.db.before-restore
+    pub async fn restore(path: &Path, passphrase: &str) -> Result<BackupManifest> {
+        let archive = tokio::fs::read(path).await?;
+        let passphrase = passphrase.to_string();
+        
+        let manifest = AddressBook::call(move || {
+            let (manifest, database) = Self::decode(&archive, &passphrase)?;
+            let staging = AddressBook::get_db_path().with_extension("db.restore");
+            std::fs::write(&staging, &database)?;
+            let result = AddressBook::replace_database(&staging);
+            std::fs::remove_file(&staging).ok();
+            result?;
+            
+            // 恢复的私钥可能设置了口令，之前解锁的密钥不再适用
+            KeyVault::set_unlocked_key(None);
+            Settings::load()?;
+            Ok(manifest)
+        }).await?;
+        
+        // 恢复后身份私钥和端口都可能变化，握手需要使用新的静态密钥；
+        // 恢复的私钥设置了口令时先关闭接收，解锁后再按设置开启
+        if AddressBook::call(AddressBook::identity_key_locked).await? {
+            FileReceiver::set_receive_status(ReceiveStatus::Closed).await?;
+        } else if let Err(e) = FileReceiver::restart_server().await {
+            error!("重新启动接收服务器失败: {}", e);
+        }
+        info!("已从备份恢复档案，备份时间 {}", manifest.created_at);
+        Ok(manifest)
+    }
+    
+    // 解密并解析备份文件，返回清单和数据库文件内容
+    fn decode(archive: &[u8], passphrase: &str) -> Result<(BackupManifest, Vec<u8>)> {
+        if archive.len() < HEADER_LEN || &archive[..MAGIC.len()] != MAGIC {
+            return Err(NearbyError::validation("不是 Nearby 备份文件"));
+        }
+        let version = u16::from_be_bytes([archive[MAGIC.len()], archive[MAGIC.len() + 1]]);
+        if version == 0 {
+            return Err(NearbyError::validation("不是 Nearby 备份文件"));
+        }
+        if version > FORMAT_VERSION {
+            return Err(NearbyError::validation(format!(
+                "备份文件格式版本 {} 高于程序支持的版本 {}，请升级程序",
+                version, FORMAT_VERSION
+            )));
+        }
+        
+        let salt = &archive[MAGIC.len() + 2..HEADER_LEN];
+        let key = KeyVault::derive_key(passphrase, salt)?;
+        let payload = KeyVault::open_with_aad(&key, &archive[HEADER_LEN..], &archive[..HEADER_LEN])
+            .ok_or_else(|| NearbyError::validation("口令错误或备份文件已损坏"))?;
+        
+        // 以后的格式版本在这里按版本分别解析
+        Self::decode_v1(&payload)
+    }
+    
+    fn decode_v1(payload: &[u8]) -> Result<(BackupManifest, Vec<u8>)> {
+        let invalid = || NearbyError::validation("备份文件内容不完整");
+        let length_bytes: [u8; 4] = payload.get(..4).ok_or_else(invalid)?.try_into().map_err(|_| invalid())?;
+        let manifest_end = 4 + u32::from_be_bytes(length_bytes) as usize;
+        let manifest_json = payload.get(4..manifest_end).ok_or_else(invalid)?;
+        let manifest: BackupManifest = serde_json::from_slice(manifest_json).map_err(|_| invalid())?;
+        
+        let latest = migrations::latest_version();
+        if manifest.schema_version > latest {
+            return Err(NearbyError::DatabaseVersion { found: manifest.schema_version, supported: latest });
+        }
+        Ok((manifest, payload[manifest_end..].to_vec()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn manifest(schema_version: i64) -> BackupManifest {
+        BackupManifest {
+            schema_version,
+            created_at: "2024-01-01 00:00:00".to_string(),
+            identity: "ab".repeat(32),
+        }
+    }
+    
+    #[test]
+    fn round_trip() {
+        let database = b"SQLite format 3\0 test".to_vec();
+        let archive = ProfileBackup::encode(&manifest(migrations::latest_version()), &database, "口令").unwrap();
+        let (decoded, restored) = ProfileBackup::decode(&archive, "口令").unwrap();
+        assert_eq!(decoded, manifest(migrations::latest_version()));
+        assert_eq!(restored, database);
+    }
+    
+    #[test]
+    fn wrong_passphrase() {
+        let archive = ProfileBackup::encode(&manifest(1), b"db", "correct").unwrap();
+        assert!(ProfileBackup::decode(&archive, "wrong").is_err());
+    }
+    
+    #[test]
+    fn tampered_header() {
+        let mut archive = ProfileBackup::encode(&manifest(1), b"db", "correct").unwrap();
+        archive[HEADER_LEN - 1] ^= 1;
+        assert!(ProfileBackup::decode(&archive, "correct").is_err());
+    }
+    
+    #[test]
+    fn newer_format_version() {
+        let mut archive = ProfileBackup::encode(&manifest(1), b"db", "correct").unwrap();
+        archive[MAGIC.len()..MAGIC.len() + 2].copy_from_slice(&(FORMAT_VERSION + 1).to_be_bytes());
+        let error = ProfileBackup::decode(&archive, "correct").unwrap_err();
+        assert!(error.to_string().contains("请升级程序"));
+    }
+    
+    #[test]
+    fn newer_schema_version() {
+        let latest = migrations::latest_version();
+        let archive = ProfileBackup::encode(&manifest(latest + 1), b"db", "correct").unwrap();
+        assert!(matches!(
+            ProfileBackup::decode(&archive, "correct"),
+            Err(NearbyError::DatabaseVersion { found, supported }) if found == latest + 1 && supported == latest
+        ));
+    }
+    
+    #[test]
+    fn not_a_backup() {
+        assert!(ProfileBackup::decode(b"not a backup file at all", "correct").is_err());
+        assert!(ProfileBackup::decode(b"NEARBYBK", "correct").is_err());
+    }
+}
@@ -0,0 +1,685 @@
+// src/core/contact_exchange.rs
+// 通讯录的导入导出：JSON、CSV，以及本机名片 (vCard) 和联系人链接 (nearby://)
+use std::path::Path;
+use percent_encoding::{percent_decode_str, utf8_percent_encode, NON_ALPHANUMERIC};
+use qrcode::render::svg;
+use qrcode::QrCode;
+use serde::{Deserialize, Serialize};
+use log::info;
+use crate::core::db::{AddressBook, Contact, ContactGroup};
+use crate::core::error::{NearbyError, Result};
+use crate::core::filereceiver::FileReceiver;
+use crate::core::settings::Settings;
+
+// JSON 导出文件的格式标识和版本
+const JSON_FORMAT: &str = "nearby-contacts";
+const JSON_VERSION: u32 = 1;
+
+// 未设置显示名称时名片使用的名称
+const DEFAULT_CARD_NAME: &str = "Nearby";
+
+// 联系人链接的前缀，例如 nearby://contact?name=...&key=...&addr=...&port=6789
+const URI_PREFIX: &str = "nearby://contact?";
+
+pub struct ContactExchange;
+
+// 导入导出文件的格式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExchangeFormat {
+    Json,  // 完整导出，包含分组
+    Csv,   // 便于用表格软件编辑
+    VCard, // 名片，可包含多张
+}
+
+impl ExchangeFormat {
+    pub const ALL: [ExchangeFormat; 3] = [ExchangeFormat::Json, ExchangeFormat::Csv, ExchangeFormat::VCard];
+    
+    /// 文件扩展名
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExchangeFormat::Json => "json",
+            ExchangeFormat::Csv => "csv",
+            ExchangeFormat::VCard => "vcf",
+        }
+    }
+    
+    /// 按文件扩展名判断格式，不区分大小写
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_lowercase();
+        Self::ALL.into_iter().find(|format| format.extension() == extension)
+    }
+    
+    /// 界面上显示的名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            ExchangeFormat::Json => "JSON",
+            ExchangeFormat::Csv => "CSV",
+            ExchangeFormat::VCard => "名片 (vCard)",
+        }
+    }
+}
+
+// 导出文件中的一条联系人记录
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExportedContact {
+    pub name: String,
+    pub identity: String,
+    pub addresses: Vec<String>,
+    pub port: Option<u16>,
+    pub trusted: bool,
+    pub notes: String,
+    pub groups: Vec<String>, // 所属分组的名称
+}
+
+#[derive(Serialize, Deserialize)]
+struct ContactsFile {
+    format: String,
+    version: u32,
+    contacts: Vec<ExportedContact>,
+}
+
+// CSV 中的一行，地址以空格分隔，分组以分号分隔
+#[derive(Serialize, Deserialize)]
+struct CsvRow {
+    name: String,
+    #[serde(default)]
+    identity: String,
+    #[serde(default)]
+    addresses: String,
+    #[serde(default)]
+    port: Option<u16>,
+    #[serde(default)]
+    trusted: bool,
+    #[serde(default)]
+    notes: String,
+    #[serde(default)]
+    groups: String,
+}
+
+// 导入结果
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ImportReport {
+    pub added: usize,           // 新增的联系人数
+    pub merged: usize,          // 合并到已有联系人的记录数
+    pub unchanged: usize,       // 与已有联系人相同的记录数
+    pub conflicts: Vec<String>, // 未能自动处理的冲突，保留已有数据
+}
+
+impl ImportReport {
+    /// 一行摘要
+    pub fn summary(&self) -> String {
+        format!(
+            "新增 {} 个，合并 {} 个，无变化 {} 个，冲突 {} 处",
+            self.added,
+            self.merged,
+            self.unchanged,
+            self.conflicts.len()
+        )
+    }
+}
+
+impl ContactExchange {
+    // ===== 导出 =====
+    
+    /// 导出全部联系人，JSON 和 CSV 中包含所属分组
+    pub fn export(format: ExchangeFormat) -> Result<String> {
+        let contacts = AddressBook::get_all_contacts()?;
+        let groups = AddressBook::get_all_groups()?;
+        let records: Vec<ExportedContact> = contacts.iter().map(|c| Self::to_exported(c, &groups)).collect();
+        
+        let text = match format {
+            ExchangeFormat::Json => Self::to_json(records)?,
+            ExchangeFormat::Csv => Self::to_csv(&records)?,
+            ExchangeFormat::VCard => records.iter().map(Self::to_vcard).collect(),
+        };
+        info!("已导出 {} 个联系人 ({})", contacts.len(), format.label());
+        Ok(text)
+    }
+    
+    /// 本机名片：显示名称、身份码、当前 IPv6 地址和端口，对方导入后即可发送
+    pub fn my_card() -> Result<String> {
+        Ok(Self::to_vcard(&Self::my_contact()?))
+    }
+    
+    /// 本机的联系人链接，内容与名片相同，用于生成二维码
+    pub fn my_uri() -> Result<String> {
+        Ok(Self::to_uri(&Self::my_contact()?))
+    }
+    
+    fn my_contact() -> Result<ExportedContact> {
+        let settings = Settings::current();
+        let name = if settings.display_name.is_empty() {
+            DEFAULT_CARD_NAME.to_string()
+        } else {
+            settings.display_name
+        };
+        Ok(ExportedContact {
+            name,
+            identity: AddressBook::get_my_identity()?,
+            addresses: FileReceiver::get_ipv6_addr().iter().map(|a| a.to_string()).collect(),
+            port: Some(settings.port),
+            ..ExportedContact::default()
+        })
+    }
+    
+    fn to_exported(contact: &Contact, groups: &[ContactGroup]) -> ExportedContact {
+        ExportedContact {
+            name: contact.name.clone(),
+            identity: contact.identity.clone(),
+            addresses: contact.addresses.clone(),
+            port: contact.port,
+            trusted: contact.trusted,
+            notes: contact.notes.clone(),
+            groups: groups
+                .iter()
+                .filter(|g| g.member_ids.contains(&contact.id))
+                .map(|g| g.name.clone())
+                .collect(),
+        }
+    }
+    
+    fn to_json(contacts: Vec<ExportedContact>) -> Result<String> {
+        let file = ContactsFile {
+            format: JSON_FORMAT.to_string(),
+            version: JSON_VERSION,
+            contacts,
+        };
+        serde_json::to_string_pretty(&file).map_err(|e| NearbyError::validation(format!("生成 JSON 失败: {}", e)))
+    }
+    
+    fn to_csv(contacts: &[ExportedContact]) -> Result<String> {
+        let csv_error = |e: csv::Error| NearbyError::validation(format!("生成 CSV 失败: {}", e));
+        let mut writer = csv::Writer::from_writer(Vec::new());
+        for contact in contacts {
+            writer
+                .serialize(CsvRow {
+                    name: contact.name.clone(),
+                    identity: contact.identity.clone(),
+                    addresses: contact.addresses.join(" "),
+                    port: contact.port,
+                    trusted: contact.trusted,
+                    notes: contact.notes.clone(),
+                    groups: contact.groups.join(";"),
+                })
+                .map_err(csv_error)?;
+        }
+        let bytes = writer
+            .into_inner()
+            .map_err(|e| NearbyError::validation(format!("生成 CSV 失败: {}", e)))?;
+        String::from_utf8(bytes).map_err(|e| NearbyError::validation(format!("生成 CSV 失败: {}", e)))
+    }
+    
+    // 身份码和地址放在 X-NEARBY-* 扩展属性中，其他通讯录程序会忽略它们
+    fn to_vcard(contact: &ExportedContact) -> String {
+        let mut card = String::from("BEGIN:VCARD\r\nVERSION:4.0\r\n");
+        card.push_str(&format!("FN:{}\r\n", escape_vcard(&contact.name)));
+        if !contact.identity.is_empty() {
+            card.push_str(&format!("X-NEARBY-IDENTITY:{}\r\n", escape_vcard(&contact.identity)));
+        }
+        for address in &contact.addresses {
+            card.push_str(&format!("X-NEARBY-ADDRESS:{}\r\n", escape_vcard(address)));
+        }
+        if let Some(port) = contact.port {
+            card.push_str(&format!("X-NEARBY-PORT:{}\r\n", port));
+        }
+        if !contact.notes.is_empty() {
+            card.push_str(&format!("NOTE:{}\r\n", escape_vcard(&contact.notes)));
+        }
+        card.push_str("END:VCARD\r\n");
+        card
+    }
+    
+    // 身份码可能包含任意可打印字符，所有参数都做百分号编码
+    fn to_uri(contact: &ExportedContact) -> String {
+        let encode = |value: &str| utf8_percent_encode(value, NON_ALPHANUMERIC).to_string();
+        let mut params = vec![format!("name={}", encode(&contact.name))];
+        if !contact.identity.is_empty() {
+            params.push(format!("key={}", encode(&contact.identity)));
+        }
+        params.extend(contact.addresses.iter().map(|a| format!("addr={}", encode(a))));
+        if let Some(port) = contact.port {
+            params.push(format!("port={}", port));
+        }
+        format!("{}{}", URI_PREFIX, params.join("&"))
+    }
+    
+    /// 把联系人链接编码为二维码（SVG）
+    pub fn qr_code_svg(uri: &str) -> Result<String> {
+        let code = QrCode::new(uri.as_bytes())
+            .map_err(|e| NearbyError::validation(format!("生成二维码失败: {}", e)))?;
+        Ok(code
+            .render::<svg::Color>()
+            .max_dimensions(200, 200)
+            .quiet_zone(true)
+            .build())
+    }
+    
+    // ===== 导入 =====
+    
+    /// 解析联系人链接，未知的参数会被忽略
+    pub fn parse_uri(uri: &str) -> Result<ExportedContact> {
+        let uri = uri.trim();
+        let query = uri
+            .get(..URI_PREFIX.len())
+            .filter(|prefix| prefix.eq_ignore_ascii_case(URI_PREFIX))
+            .map(|_| &uri[URI_PREFIX.len()..])
+            .ok_or_else(|| NearbyError::validation("不是联系人链接（应以 nearby://contact? 开头）"))?;
+        
+        let mut contact = ExportedContact::default();
+        for param in query.split('&').filter(|p| !p.is_empty()) {
+            let (key, value) = param.split_once('=').unwrap_or((param, ""));
+            let value = percent_decode_str(value)
+                .decode_utf8()
+                .map_err(|_| NearbyError::validation("联系人链接的编码无效"))?
+                .to_string();
+            match key {
+                "name" => contact.name = value,
+                "key" => contact.identity = value,
+                "addr" => contact.addresses.push(value),
+                "port" => {
+                    let port = value.parse().map_err(|_| NearbyError::validation(format!("联系人链接中的端口无效: {}", value)))?;
+                    contact.port = Some(port);
+                }
+                _ => {}
+            }
+        }
+        
+        if contact.identity.is_empty() && contact.addresses.is_empty() {
+            return Err(NearbyError::validation("联系人链接中没有身份码或地址"));
+        }
+        Ok(contact)
+    }
+    
+    /// 识别图片中的二维码，返回其中的文本
+    pub fn read_qr_image(path: &Path) -> Result<String> {
+        let image = image::open(path)
+            .map_err(|e| NearbyError::validation(format!("无法读取图片: {}", e)))?
+            .to_luma8();
+        let mut prepared = rqrr::PreparedImage::prepare_from_greyscale(
+            image.width() as usize,
+            image.height() as usize,
+            |x, y| image.get_pixel(x as u32, y as u32).0[0],
+        );
+        
+        // 图片中可能有多个二维码，取第一个能解码的
+        prepared
+            .detect_grids()
+            .iter()
+            .find_map(|grid| grid.decode().ok().map(|(_, content)| content))
+            .ok_or_else(|| NearbyError::validation("图片中没有找到可识别的二维码"))
+    }
+    
+    
+    /// 解析导入文件的内容
+    pub fn parse(text: &str, format: ExchangeFormat) -> Result<Vec<ExportedContact>> {
+        // 去掉部分编辑器保存时添加的 BOM
+        let text = text.trim_start_matches('\u{feff}');
+        match format {
+            ExchangeFormat::Json => Self::parse_json(text),
+            ExchangeFormat::Csv => Self::parse_csv(text),
+            ExchangeFormat::VCard => Self::parse_vcards(text),
+        }
+    }
+    
+    fn parse_json(text: &str) -> Result<Vec<ExportedContact>> {
+        let file: ContactsFile = serde_json::from_str(text)
+            .map_err(|e| NearbyError::validation(format!("无法解析 JSON 文件: {}", e)))?;
+        if file.format != JSON_FORMAT {
+            return Err(NearbyError::validation("不是 Nearby 导出的联系人文件"));
+        }
+        if file.version > JSON_VERSION {
+            return Err(NearbyError::validation(format!(
+                "联系人文件版本 {} 高于程序支持的版本 {}",
+                file.version, JSON_VERSION
+            )));
+        }
+        Ok(file.contacts)
+    }
+    
+    fn parse_csv(text: &str) -> Result<Vec<ExportedContact>> {
+        let mut reader = csv::Reader::from_reader(text.as_bytes());
+        let mut contacts = Vec::new();
+        for (index, row) in reader.deserialize::<CsvRow>().enumerate() {
+            // 第 1 行是表头
+            let row = row.map_err(|e| NearbyError::validation(format!("CSV 第 {} 行无效: {}", index + 2, e)))?;
+            contacts.push(ExportedContact {
+                name: row.name,
+                identity: row.identity,
+                addresses: row.addresses.split_whitespace().map(|a| a.to_string()).collect(),
+                port: row.port,
+                trusted: row.trusted,
+                notes: row.notes,
+                groups: row
+                    .groups
+                    .split(';')
+                    .map(|g| g.trim().to_string())
+                    .filter(|g| !g.is_empty())
+                    .collect(),
+            });
+        }
+        Ok(contacts)
+    }
+    
+    fn parse_vcards(text: &str) -> Result<Vec<ExportedContact>> {
+        // 以空格或制表符开头的行是上一行的续行
+        let mut lines: Vec<String> = Vec::new();
+        for line in text.lines() {
+            match (line.strip_prefix(' ').or_else(|| line.strip_prefix('\t')), lines.last_mut()) {
+                (Some(rest), Some(last)) => last.push_str(rest),
+                _ => lines.push(line.to_string()),
+            }
+        }
+        
+        let mut contacts = Vec::new();
+        let mut current: Option<ExportedContact> = None;
+        for line in &lines {
+            let Some((key, value)) = line.split_once(':') else { continue };
+            // 忽略 TYPE 等参数
+            let key = key.split(';').next().unwrap_or_default().to_uppercase();
+            let value = unescape_vcard(value);
+            match (key.as_str(), current.as_mut()) {
+                ("BEGIN", _) if value.eq_ignore_ascii_case("VCARD") => current = Some(ExportedContact::default()),
+                ("END", Some(_)) if value.eq_ignore_ascii_case("VCARD") => contacts.extend(current.take()),
+                ("FN", Some(card)) => card.name = value,
+                ("X-NEARBY-IDENTITY", Some(card)) => card.identity = value,
+                ("X-NEARBY-ADDRESS", Some(card)) => card.addresses.push(value),
+                ("X-NEARBY-PORT", Some(card)) => {
+                    let port = value.trim().parse().map_err(|_| NearbyError::validation(format!("名片中的端口无效: {}", value)))?;
+                    card.port = Some(port);
+                }
+                ("NOTE", Some(card)) => card.notes = value,
+                _ => {}
+            }
+        }
+        
+        if contacts.is_empty() {
+            return Err(NearbyError::validation("文件中没有名片"));
+        }
+        Ok(contacts)
+    }
+    
+    /// 把记录合并到通讯录：身份码相同或地址相同的视为同一联系人，
+    /// 补充已有联系人缺少的字段，无法自动合并的情况记入冲突并保留已有数据
+    pub fn import(records: Vec<ExportedContact>) -> Result<ImportReport> {
+        let mut contacts = AddressBook::get_all_contacts()?;
+        let mut groups = AddressBook::get_all_groups()?;
+        let mut report = ImportReport::default();
+        
+        for record in records {
+            let label = if record.name.trim().is_empty() { record.identity.clone() } else { record.name.trim().to_string() };
+            let mut incoming = Contact {
+                id: 0,
+                name: record.name.clone(),
+                identity: record.identity.trim().to_string(),
+                addresses: record.addresses.iter().map(|a| a.trim().to_string()).filter(|a| !a.is_empty()).collect(),
+                port: record.port,
+                last_successful: None,
+                trusted: record.trusted,
+                trust_expires_at: None,
+                trust_uses_left: None,
+                verified: false,
+                notes: record.notes.clone(),
+            };
+            
+            let by_identity = if incoming.identity.is_empty() {
+                None
+            } else {
+                contacts.iter().position(|c| c.identity == incoming.identity)
+            };
+            let by_address: Vec<usize> = contacts
+                .iter()
+                .enumerate()
+                .filter(|(_, c)| c.addresses.iter().any(|a| incoming.addresses.contains(a)))
+                .map(|(index, _)| index)
+                .collect();
+            
+            let target = match by_identity {
+                Some(index) => {
+                    // 已属于其他联系人的地址不合并
+                    for other in by_address.iter().filter(|i| **i != index) {
+                        let other = &contacts[*other];
+                        incoming.addresses.retain(|a| {
+                            let taken = other.addresses.contains(a);
+                            if taken {
+                                report.conflicts.push(format!("{}: 地址 {} 已属于联系人 {}，未合并", label, a, other.name));
+                            }
+                            !taken
+                        });
+                    }
+                    Some(index)
+                }
+                None => {
+                    match by_address.as_slice() {
+                        [] => None,
+                        [index] => {
+                            let existing = &contacts[*index];
+                            if !existing.identity.is_empty() && !incoming.identity.is_empty() {
+                                report.conflicts.push(format!(
+                                    "{}: 地址与联系人 {} 相同但身份码不同，已跳过",
+                                    label, existing.name
+                                ));
+                                continue;
+                            }
+                            Some(*index)
+                        }
+                        _ => {
+                            report.conflicts.push(format!("{}: 地址分属多个联系人，已跳过", label));
+                            continue;
+                        }
+                    }
+                }
+            };
+            
+            let contact_id = match target {
+                None => match AddressBook::add_contact(&incoming) {
+                    Ok(id) => {
+                        report.added += 1;
+                        incoming.id = id;
+                        contacts.push(incoming);
+                        id
+                    }
+                    Err(NearbyError::Validation(e)) => {
+                        report.conflicts.push(format!("{}: {}，已跳过", label, e));
+                        continue;
+                    }
+                    Err(e) => return Err(e),
+                },
+                Some(index) => {
+                    let existing = &contacts[index];
+                    let merged = Self::merge(existing, &incoming, &label, &mut report.conflicts);
+                    if merged == *existing {
+                        report.unchanged += 1;
+                    } else {
+                        match AddressBook::update_contact(&merged) {
+                            Ok(()) => report.merged += 1,
+                            Err(NearbyError::Validation(e)) => {
+                                report.conflicts.push(format!("{}: {}，未合并", label, e));
+                                continue;
+                            }
+                            Err(e) => return Err(e),
+                        }
+                        contacts[index] = merged;
+                    }
+                    contacts[index].id
+                }
+            };
+            
+            Self::join_groups(contact_id, &record.groups, &mut groups)?;
+        }
+        
+        info!("联系人导入完成: {}", report.summary());
+        Ok(report)
+    }
+    
+    // 用导入的记录补充已有联系人：名称和端口以已有的为准，地址取并集
+    fn merge(existing: &Contact, incoming: &Contact, label: &str, conflicts: &mut Vec<String>) -> Contact {
+        let mut merged = existing.clone();
+        
+        let name = incoming.name.trim();
+        if !name.is_empty() && name != existing.name {
+            conflicts.push(format!("{}: 名称与已有联系人 {} 不同，保留已有名称", label, existing.name));
+        }
+        if existing.identity.is_empty() {
+            merged.identity = incoming.identity.clone();
+        }
+        for address in &incoming.addresses {
+            if !merged.addresses.contains(address) {
+                merged.addresses.push(address.clone());
+            }
+        }
+        match (existing.port, incoming.port) {
+            (None, port) => merged.port = port,
+            (Some(old), Some(new)) if old != new => {
+                conflicts.push(format!("{}: 端口 {} 与已有的 {} 不同，保留已有端口", label, new, old));
+            }
+            _ => {}
+        }
+        merged.trusted = existing.trusted || incoming.trusted;
+        if existing.notes.is_empty() {
+            merged.notes = incoming.notes.trim().to_string();
+        }
+        merged
+    }
+    
+    // 把联系人加入指定名称的分组，分组不存在时新建
+    fn join_groups(contact_id: i64, names: &[String], groups: &mut Vec<ContactGroup>) -> Result<()> {
+        for name in names.iter().map(|n| n.trim()).filter(|n| !n.is_empty()) {
+            match groups.iter_mut().find(|g| g.name == name) {
+                Some(group) if group.member_ids.contains(&contact_id) => {}
+                Some(group) => {
+                    group.member_ids.push(contact_id);
+                    AddressBook::update_group(group)?;
+                }
+                None => {
+                    let mut group = ContactGroup {
+                        id: 0,
+                        name: name.to_string(),
+                        member_ids: vec![contact_id],
+                    };
+                    group.id = AddressBook::add_group(&group)?;
+                    groups.push(group);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+// vCard 文本值的转义
+fn escape_vcard(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(',', "\\,")
+        .replace(';', "\\;")
+        .replace('\n', "\\n")
+}
+
+fn unescape_vcard(value: &str) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => result.push('\n'),
+            Some(other) => result.push(other),
+            None => result.push('\\'),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn sample() -> ExportedContact {
+        ExportedContact {
+            name: "张三, \"小张\"".to_string(),
+            identity: "ab".repeat(32),
+            addresses: vec!["2001:db8::1".to_string(), "fe80::1%eth0".to_string()],
+            port: Some(6789),
+            trusted: true,
+            notes: "第一行\n第二行; 含分号".to_string(),
+            groups: vec!["同事".to_string(), "家人".to_string()],
+        }
+    }
+    
+    #[test]
+    fn json_round_trip() {
+        let text = ContactExchange::to_json(vec![sample()]).unwrap();
+        assert_eq!(ContactExchange::parse(&text, ExchangeFormat::Json).unwrap(), vec![sample()]);
+    }
+    
+    #[test]
+    fn json_rejects_other_files() {
+        let other = r#"{"format":"other","version":1,"contacts":[]}"#;
+        assert!(ContactExchange::parse(other, ExchangeFormat::Json).is_err());
+        let newer = format!(r#"{{"format":"{}","version":{},"contacts":[]}}"#, JSON_FORMAT, JSON_VERSION + 1);
+        assert!(ContactExchange::parse(&newer, ExchangeFormat::Json).is_err());
+        assert!(ContactExchange::parse("not json", ExchangeFormat::Json).is_err());
+    }
+    
+    #[test]
+    fn csv_round_trip() {
+        let text = ContactExchange::to_csv(&[sample()]).unwrap();
+        let with_bom = format!("\u{feff}{}", text);
+        assert_eq!(ContactExchange::parse(&with_bom, ExchangeFormat::Csv).unwrap(), vec![sample()]);
+    }
+    
+    #[test]
+    fn csv_fills_missing_columns() {
+        let contacts = ContactExchange::parse("name,addresses\n李四,2001:db8::2  2001:db8::3\n", ExchangeFormat::Csv).unwrap();
+        assert_eq!(contacts, vec![ExportedContact {
+            name: "李四".to_string(),
+            addresses: vec!["2001:db8::2".to_string(), "2001:db8::3".to_string()],
+            ..ExportedContact::default()
+        }]);
+        assert!(ContactExchange::parse("name,port\n李四,abc\n", ExchangeFormat::Csv).is_err());
+    }
+    
+    #[test]
+    fn vcard_round_trip() {
+        // vCard 不保存信任状态和分组
+        let text = ContactExchange::to_vcard(&sample());
+        let expected = ExportedContact { trusted: false, groups: Vec::new(), ..sample() };
+        assert_eq!(ContactExchange::parse(&text, ExchangeFormat::VCard).unwrap(), vec![expected]);
+    }
+    
+    #[test]
+    fn vcard_unfolds_lines_and_ignores_parameters() {
+        let text = "BEGIN:VCARD\r\nVERSION:3.0\r\nFN;CHARSET=UTF-8:王\r\n 五\r\nX-NEARBY-ADDRESS;TYPE=home:2001:db8::5\r\nTEL:123\r\nEND:VCARD\r\n\
+                    BEGIN:VCARD\r\nFN:赵六\r\nEND:VCARD\r\n";
+        let contacts = ContactExchange::parse(text, ExchangeFormat::VCard).unwrap();
+        assert_eq!(contacts.len(), 2);
+        assert_eq!(contacts[0].name, "王五");
+        assert_eq!(contacts[0].addresses, vec!["2001:db8::5".to_string()]);
+        assert_eq!(contacts[1].name, "赵六");
+        
+        assert!(ContactExchange::parse("FN:孤立的属性\r\n", ExchangeFormat::VCard).is_err());
+        assert!(ContactExchange::parse("BEGIN:VCARD\r\nX-NEARBY-PORT:99999\r\nEND:VCARD\r\n", ExchangeFormat::VCard).is_err());
+    }
+    
+    #[test]
+    fn uri_round_trip() {
+        let uri = ContactExchange::to_uri(&sample());
+        let expected = ExportedContact { trusted: false, notes: String::new(), groups: Vec::new(), ..sample() };
+        assert_eq!(ContactExchange::parse_uri(&uri).unwrap(), expected);
+    }
+    
+    #[test]
+    fn uri_parsing() {
+        let contact = ContactExchange::parse_uri("  NEARBY://Contact?name=%E5%BC%A0%E4%B8%89&addr=2001:db8::1&extra=1&  ").unwrap();
+        assert_eq!(contact.name, "张三");
+        assert_eq!(contact.addresses, vec!["2001:db8::1".to_string()]);
+        assert_eq!(contact.port, None);
+        
+        assert!(ContactExchange::parse_uri("https://example.com/?key=ab").is_err());
+        assert!(ContactExchange::parse_uri("nearby://contact?name=张三").is_err());
+        assert!(ContactExchange::parse_uri("nearby://contact?key=ab&port=70000").is_err());
+        assert!(ContactExchange::parse_uri("nearby://contact?key=%FF").is_err());
+    }
+}
@@ -1,24 +1,201 @@
 // src/core/db.rs
-use rusqlite::{Connection, Result};
-use std::path::PathBuf;
-use dirs::data_dir;
-use crate::core::create_identity::CreateIdentity;
-use log::{info, debug}; // 添加日志功能
+use rusqlite::{Connection, OptionalExtension};
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv6Addr, SocketAddrV6};
+use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+use chrono::{NaiveDateTime, Utc};
+use crate::core::identity_key::{IdentityKey, RotationProof, MAX_ROTATION_PROOFS};
+use crate::core::key_vault::KeyVault;
+use crate::core::error::{NearbyError, Result};
+use crate::core::migrations;
+use crate::core::profile::Profiles;
+use crate::core::protocol::ConflictResolution;
+use crate::core::receive_code::ReceiveCode;
+use crate::core::session_ledger::SessionLedger;
+use log::{info, debug, warn}; // 添加日志功能
 
 pub struct AddressBook;
 
+// 全程序共享的数据库连接（WAL 模式，带忙等待超时），首次使用时打开
+static CONNECTION: Mutex<Option<Connection>> = Mutex::new(None);
+
+// 启动时早期身份码被更换后记录旧身份码，界面据此提示用户
+static LEGACY_REPLACEMENT: Mutex<Option<String>> = Mutex::new(None);
+
+// 屏蔽的地址段，接受连接时只在内存中比较，不等待数据库连接；打开数据库和修改屏蔽列表时重新加载
+static BLOCKED_PREFIXES: Mutex<Vec<(Ipv6Addr, u8)>> = Mutex::new(Vec::new());
+
+// 共享连接的独占使用权，离开作用域时归还
+pub struct DbConnection(MutexGuard<'static, Option<Connection>>);
+
+impl Deref for DbConnection {
+    type Target = Connection;
+    
+    fn deref(&self) -> &Connection {
+        self.0.as_ref().expect("数据库连接未打开")
+    }
+}
+
+impl DerefMut for DbConnection {
+    fn deref_mut(&mut self) -> &mut Connection {
+        self.0.as_mut().expect("数据库连接未打开")
+    }
+}
+
+// 联系人：同一个人的名称、身份码和地址放在一起
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Contact {
+    pub id: i64,                // 主键ID，新建时为 0
+    pub name: String,           // 显示名称
+    pub identity: String,       // 身份码 (64个字符)，未知时为空
+    pub addresses: Vec<String>, // IPv6地址，按优先顺序排列
+    pub port: Option<u16>,      // 对方监听的端口，为空时使用设置中的端口
+    pub last_successful: Option<String>, // 上次发送成功的地址
+    pub trusted: bool,          // 是否在白名单中
+    pub trust_expires_at: Option<String>, // 白名单到期时间（UTC），为空表示长期有效
+    pub trust_uses_left: Option<u32>,     // 白名单剩余可接收次数，为空表示不限
+    pub verified: bool,         // 是否已与对方核对安全码
+    pub notes: String,          // 备注
+}
+
+impl Contact {
+    // 发送时尝试地址的顺序：上次成功的地址优先，其余按列表顺序
+    pub fn dial_order(&self) -> Vec<String> {
+        let mut order: Vec<String> = self.last_successful.iter().cloned().collect();
+        order.extend(self.addresses.iter().filter(|a| Some(*a) != self.last_successful.as_ref()).cloned());
+        order
+    }
+    
+    // 去除首尾空白和重复地址，并检查各字段是否合法
+    fn normalized(&self) -> Result<Contact> {
+        let name = self.name.trim().to_string();
+        let identity = self.identity.trim().to_string();
+        if name.is_empty() {
+            return Err(NearbyError::validation("联系人名称不能为空"));
+        }
+        if !identity.is_empty() && identity.len() != 64 {
+            return Err(NearbyError::validation("身份码必须为64字符"));
+        }
+        
+        let mut addresses: Vec<String> = Vec::new();
+        for address in self.addresses.iter().map(|a| a.trim()).filter(|a| !a.is_empty()) {
+            // 与发送时的解析方式保持一致
+            if format!("[{}]:0", address).parse::<SocketAddrV6>().is_err() {
+                return Err(NearbyError::validation(format!("无效的 IPv6 地址: {}", address)));
+            }
+            if !addresses.iter().any(|a| a == address) {
+                addresses.push(address.to_string());
+            }
+        }
+        
+        if identity.is_empty() && addresses.is_empty() {
+            return Err(NearbyError::validation("身份码和地址至少需要填写一项"));
+        }
+        if self.port == Some(0) {
+            return Err(NearbyError::validation("端口不能为 0"));
+        }
+        if self.trusted && identity.is_empty() {
+            return Err(NearbyError::validation("加入白名单需要填写身份码"));
+        }
+        if self.verified && identity.is_empty() {
+            return Err(NearbyError::validation("标记为已验证需要填写身份码"));
+        }
+        // 不在白名单中时有效期和次数没有意义
+        let (trust_expires_at, trust_uses_left) = if self.trusted {
+            (self.trust_expires_at.clone(), self.trust_uses_left)
+        } else {
+            (None, None)
+        };
+        if let Some(expires_at) = &trust_expires_at {
+            if NaiveDateTime::parse_from_str(expires_at, DB_TIME_FORMAT).is_err() {
+                return Err(NearbyError::validation("白名单到期时间格式不正确"));
+            }
+        }
+        
+        Ok(Contact {
+            id: self.id,
+            name,
+            identity,
+            addresses,
+            port: self.port,
+            last_successful: self.last_successful.clone(),
+            trusted: self.trusted,
+            trust_expires_at,
+            trust_uses_left,
+            verified: self.verified,
+            notes: self.notes.trim().to_string(),
+        })
+    }
+    
+    // 白名单是否已到期或次数已用完
+    pub fn trust_exhausted(&self) -> bool {
+        let expired = self.trust_expires_at.as_deref().is_some_and(|expires_at| {
+            expires_at <= Utc::now().format(DB_TIME_FORMAT).to_string().as_str()
+        });
+        expired || self.trust_uses_left == Some(0)
+    }
+}
+
+// 邀请令牌：持有令牌的发送方在收藏模式下也会被接收，可用次数和有效期至少设置一项
 #[derive(Debug, Clone, PartialEq)]
-pub struct IdentityEntry {
-    pub id: i64,           // 主键ID
-    pub identity: String,  // 身份标识 (64个字符)
-    pub alias: String,     // 备注
+pub struct Invitation {
+    pub id: i64,                    // 主键ID
+    pub token: String,              // 令牌，例如 ABCD-EFGH-JKMN
+    pub note: String,               // 备注
+    pub uses_left: Option<u32>,     // 剩余可接收次数，为空表示不限
+    pub expires_at: Option<String>, // 到期时间（UTC），为空表示不限
+    pub add_to_whitelist: bool,     // 使用令牌后把发送方长期加入白名单
+    pub created_at: String,         // 创建时间
 }
 
+impl Invitation {
+    // 是否已到期或次数已用完
+    pub fn is_exhausted(&self) -> bool {
+        let expired = self.expires_at.as_deref().is_some_and(|expires_at| {
+            expires_at <= Utc::now().format(DB_TIME_FORMAT).to_string().as_str()
+        });
+        expired || self.uses_left == Some(0)
+    }
+}
+
+// 更换过的我的身份码
 #[derive(Debug, Clone, PartialEq)]
-pub struct FriendEntry {
-    pub id: i64,          // 主键ID
-    pub address: String,  // IPv6地址
-    pub alias: String,    // 备注
+pub struct IdentityHistoryEntry {
+    pub id: i64,             // 主键ID
+    pub identity: String,    // 旧身份码
+    pub successor: String,   // 接替它的身份码
+    pub signed: bool,        // 是否由旧私钥签名，早期随机生成的身份码没有私钥
+    pub retired_at: String,  // 更换时间
+    pub revoked: bool,       // 是否已撤销
+}
+
+// 联系人更换身份码后等待确认的变更
+#[derive(Debug, Clone, PartialEq)]
+pub struct PendingRotation {
+    pub contact: Contact,     // 仍为旧身份码的联系人
+    pub new_identity: String, // 对方出示的新身份码
+    pub received_at: String,  // 收到时间
+    pub proven: bool,         // 是否有旧身份码的签名证明；早期身份码没有私钥，只能由用户核对安全码
+}
+
+// 数据库中 CURRENT_TIMESTAMP 的格式（UTC）
+pub const DB_TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+// 邀请令牌的字符数，不含连字符
+const INVITATION_TOKEN_LENGTH: usize = 12;
+
+// 入站连接记录最多保留的条数，超出时删除最早的记录
+const MAX_CONNECTION_EVENTS: i64 = 10_000;
+
+// 联系人分组，用于一次选择多个发送目标
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ContactGroup {
+    pub id: i64,              // 主键ID，新建时为 0
+    pub name: String,         // 分组名称
+    pub member_ids: Vec<i64>, // 成员联系人ID
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -30,151 +207,340 @@ pub struct FileReceiveRecord {
     pub sender_identity: String, // 发送方身份标识
     pub received_at: String,    // 接收时间
     pub save_path: String,      // 保存路径
+    pub resolution: ConflictResolution, // 同名文件的处理结果
+    pub contact_name: Option<String>,   // 发送方对应的联系人名称
+    pub sender_authenticated: bool,     // 身份码是否经握手确认
+    pub sender_verified: bool,          // 身份码经握手确认且对应的联系人已验证
+}
+
+// 入站连接的结果
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConnectionOutcome {
+    Accepted,                // 已接收文件
+    RejectedNotWhitelisted,  // 不在白名单中被拒绝
+    RejectedBusy,            // 连接数已满被拒绝
+    RejectedRateLimited,     // 被封禁或连接过于频繁
+    RejectedClosed,          // 接收功能已关闭
+    RejectedCode,            // 口令模式下口令失效或已被他人使用
+    RejectedBlocked,         // 身份码或地址在屏蔽列表中
+    RejectedIdentityMismatch, // 身份码与握手密钥不符
+    HandshakeFailed,         // 握手失败
+    Timeout,                 // 超时
+    Closed,                  // 连接中途关闭或出错
+}
+
+impl ConnectionOutcome {
+    pub const ALL: [ConnectionOutcome; 11] = [
+        ConnectionOutcome::Accepted,
+        ConnectionOutcome::RejectedNotWhitelisted,
+        ConnectionOutcome::RejectedBusy,
+        ConnectionOutcome::RejectedRateLimited,
+        ConnectionOutcome::RejectedClosed,
+        ConnectionOutcome::RejectedCode,
+        ConnectionOutcome::RejectedBlocked,
+        ConnectionOutcome::RejectedIdentityMismatch,
+        ConnectionOutcome::HandshakeFailed,
+        ConnectionOutcome::Timeout,
+        ConnectionOutcome::Closed,
+    ];
+    
+    /// 数据库中保存的值
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConnectionOutcome::Accepted => "accepted",
+            ConnectionOutcome::RejectedNotWhitelisted => "rejected_not_whitelisted",
+            ConnectionOutcome::RejectedBusy => "rejected_busy",
+            ConnectionOutcome::RejectedRateLimited => "rejected_rate_limited",
+            ConnectionOutcome::RejectedClosed => "rejected_closed",
+            ConnectionOutcome::RejectedCode => "rejected_code",
+            ConnectionOutcome::RejectedBlocked => "rejected_blocked",
+            ConnectionOutcome::RejectedIdentityMismatch => "rejected_identity_mismatch",
+            ConnectionOutcome::HandshakeFailed => "handshake_failed",
+            ConnectionOutcome::Timeout => "timeout",
+            ConnectionOutcome::Closed => "closed",
+        }
+    }
+    
+    /// 从数据库中的值解析，未知的值视为 Closed
+    pub fn from_db_value(value: &str) -> Self {
+        Self::ALL
+            .into_iter()
+            .find(|outcome| outcome.as_str() == value)
+            .unwrap_or(ConnectionOutcome::Closed)
+    }
+    
+    /// 界面上显示的名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            ConnectionOutcome::Accepted => "已接收",
+            ConnectionOutcome::RejectedNotWhitelisted => "不在白名单",
+            ConnectionOutcome::RejectedBusy => "繁忙拒绝",
+            ConnectionOutcome::RejectedRateLimited => "频率限制",
+            ConnectionOutcome::RejectedClosed => "接收已关闭",
+            ConnectionOutcome::RejectedCode => "口令无效",
+            ConnectionOutcome::RejectedBlocked => "已屏蔽",
+            ConnectionOutcome::RejectedIdentityMismatch => "身份码不符",
+            ConnectionOutcome::HandshakeFailed => "握手失败",
+            ConnectionOutcome::Timeout => "超时",
+            ConnectionOutcome::Closed => "已关闭",
+        }
+    }
+}
+
+// 屏蔽条目的类型
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BlockKind {
+    Identity, // 身份码
+    Prefix,   // IPv6 地址或地址前缀
+}
+
+impl BlockKind {
+    pub const ALL: [BlockKind; 2] = [BlockKind::Identity, BlockKind::Prefix];
+    
+    /// 数据库中保存的值
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BlockKind::Identity => "identity",
+            BlockKind::Prefix => "prefix",
+        }
+    }
+    
+    /// 从数据库中的值解析，未知的值视为 Identity
+    pub fn from_db_value(value: &str) -> Self {
+        Self::ALL
+            .into_iter()
+            .find(|kind| kind.as_str() == value)
+            .unwrap_or(BlockKind::Identity)
+    }
+    
+    /// 界面上显示的名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            BlockKind::Identity => "身份码",
+            BlockKind::Prefix => "地址段",
+        }
+    }
+    
+    /// 检查并规范化条目的值：地址段统一为 "网络地址/前缀长度"，单个地址视为 /128
+    fn normalize(&self, value: &str) -> Result<String> {
+        let value = value.trim();
+        match self {
+            BlockKind::Identity => {
+                if value.len() != 64 {
+                    return Err(NearbyError::validation("身份码必须为64字符"));
+                }
+                Ok(value.to_string())
+            }
+            BlockKind::Prefix => {
+                let (network, len) = parse_ipv6_prefix(value)
+                    .ok_or_else(|| NearbyError::validation(format!("无效的 IPv6 地址段: {}", value)))?;
+                Ok(format!("{}/{}", network, len))
+            }
+        }
+    }
+}
+
+// 屏蔽列表中的条目，在所有接收模式下都会拒绝
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockEntry {
+    pub id: i64,            // 主键ID
+    pub kind: BlockKind,    // 类型
+    pub value: String,      // 身份码或地址段
+    pub note: String,       // 备注
+    pub created_at: String, // 添加时间
+}
+
+// 解析 "地址/前缀长度" 或单个地址，返回按前缀长度清零主机位后的网络地址
+fn parse_ipv6_prefix(value: &str) -> Option<(Ipv6Addr, u8)> {
+    let (address, len) = match value.split_once('/') {
+        Some((address, len)) => (address, len.trim().parse::<u8>().ok()?),
+        None => (value, 128),
+    };
+    if len > 128 {
+        return None;
+    }
+    let address: Ipv6Addr = address.trim().parse().ok()?;
+    Some((Ipv6Addr::from(u128::from(address) & prefix_mask(len)), len))
+}
+
+fn prefix_mask(len: u8) -> u128 {
+    if len == 0 { 0 } else { u128::MAX << (128 - len as u32) }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConnectionEvent {
+    pub id: i64,                        // 主键ID
+    pub occurred_at: String,            // 发生时间
+    pub source_address: String,         // 来源地址
+    pub claimed_identity: String,       // 对方声称的身份码
+    pub authenticated_identity: String, // 握手得到的对方公钥（十六进制）
+    pub outcome: ConnectionOutcome,     // 结果
+    pub bytes: u64,                     // 接收的字节数
 }
 
 impl AddressBook {
-    /// 获取数据库连接
-    fn get_connection() -> Result<Connection> {
-        let db_path = Self::get_db_path();
-        Connection::open(db_path)
+    /// 获取共享的数据库连接，其他线程需等待当前使用者归还
+    fn get_connection() -> Result<DbConnection> {
+        // 持有锁的线程 panic 后连接本身仍然可用
+        let mut guard = CONNECTION.lock().unwrap_or_else(|e| e.into_inner());
+        if guard.is_none() {
+            *guard = Some(Self::open_connection()?);
+        }
+        Ok(DbConnection(guard))
     }
     
-    /// 获取数据库文件路径
-    pub fn get_db_path() -> PathBuf {
-        if let Some(mut data_dir) = data_dir() {
-            data_dir.push("Nearby");
-            std::fs::create_dir_all(&data_dir).ok();
-            data_dir.push("address_book.db");
-            data_dir
-        } else {
-            PathBuf::from("address_book.db")
+    /// 打开数据库文件，启用 WAL 模式和忙等待超时
+    fn open_connection() -> Result<Connection> {
+        let conn = Connection::open(Self::get_db_path())?;
+        let journal_mode: String = conn.pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get(0))?;
+        conn.busy_timeout(Duration::from_secs(5))?;
+        // 删除联系人时级联删除其地址
+        conn.pragma_update(None, "foreign_keys", true)?;
+        debug!("已打开数据库连接，日志模式: {}", journal_mode);
+        Ok(conn)
+    }
+    
+    /// 在后台线程执行数据库操作，避免阻塞界面和异步任务
+    pub async fn call<T, F>(f: F) -> Result<T>
+    where
+        F: FnOnce() -> Result<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        match tokio::task::spawn_blocking(f).await {
+            Ok(result) => result,
+            Err(e) => Err(NearbyError::Io(e.into())),
         }
     }
     
-    /// 初始化数据库表
-    pub fn init_db() -> Result<()> {
+    /// 把数据库的一致快照写入 dest，dest 不能已存在
+    pub fn snapshot_database(dest: &Path) -> Result<()> {
         let conn = Self::get_connection()?;
-        
-        // 创建身份标识表（存储身份标识）
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS identities (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                identity TEXT NOT NULL UNIQUE,
-                alias TEXT NOT NULL,
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP
-            )",
-            [],
-        )?;
-        
-        // 创建好友列表表（存储IPv6地址）
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS friends (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                address TEXT NOT NULL UNIQUE,
-                alias TEXT NOT NULL,
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP
-            )",
-            [],
-        )?;
-        
-        // 创建我的身份码表
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS my_identity (
-                id INTEGER PRIMARY KEY CHECK (id = 1),
-                identity TEXT NOT NULL UNIQUE,
-                created_at DATETIME DEFAULT CURRENT_TIMESTAMP
-            )",
-            [],
-        )?;
-        
-        // 创建文件接收记录表
-        conn.execute(
-            "CREATE TABLE IF NOT EXISTS file_receive_records (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                filename TEXT NOT NULL,
-                file_size INTEGER NOT NULL,
-                sender_ipv6 TEXT NOT NULL,
-                sender_identity TEXT NOT NULL,
-                save_path TEXT NOT NULL,
-                received_at DATETIME DEFAULT CURRENT_TIMESTAMP
-            )",
-            [],
-        )?;
-        
-        // 验证表结构
-        Self::verify_table_structure()?;
-        
-        // 确保存在我的身份码
-        Self::ensure_my_identity()?;
-        
-        info!("数据库初始化完成");
+        conn.execute("VACUUM INTO ?1", [dest.to_string_lossy()])?;
         Ok(())
     }
     
-    fn check_table_has_column(conn: &Connection, table_name: &str, column_name: &str, table_desc: &str) -> Result<()> {
-        let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table_name))?;
-        let columns: Vec<String> = stmt
-            .query_map([], |row| {
-                Ok(row.get::<_, String>(1)?) // 获取列名
-            })?
-            .collect::<Result<Vec<String>>>()?;
-        
-        debug!("{}列: {:?}", table_desc, columns);
-        
-        if !columns.contains(&column_name.to_string()) {
-            return Err(rusqlite::Error::InvalidParameterName(
-                format!("{}缺少 {} 列", table_desc, column_name)
-            ));
+    /// 用 source 数据库文件替换当前数据库并迁移到最新版本，
+    /// 原数据库保留为 address_book.db.before-restore
+    pub fn replace_database(source: &Path) -> Result<()> {
+        // 替换前确认文件是完整的 Nearby 数据库
+        {
+            let candidate = Connection::open(source)?;
+            let integrity: String = candidate.query_row("PRAGMA integrity_check", [], |row| row.get(0))?;
+            if integrity != "ok" {
+                return Err(NearbyError::validation("备份中的数据库已损坏"));
+            }
+            let version = migrations::current_version(&candidate)?;
+            let latest = migrations::latest_version();
+            if version > latest {
+                return Err(NearbyError::DatabaseVersion { found: version, supported: latest });
+            }
+            let has_identity: bool = candidate.query_row(
+                "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = 'my_identity'",
+                [],
+                |row| row.get(0),
+            )?;
+            if !has_identity {
+                return Err(NearbyError::validation("备份中没有身份信息"));
+            }
         }
         
-        Ok(())
+        Self::reopen_database(|| {
+            let db_path = Self::get_db_path();
+            if db_path.exists() {
+                std::fs::rename(&db_path, db_path.with_extension("db.before-restore"))?;
+            }
+            for suffix in ["-wal", "-shm"] {
+                let mut sidecar = db_path.clone().into_os_string();
+                sidecar.push(suffix);
+                std::fs::remove_file(sidecar).ok();
+            }
+            std::fs::copy(source, &db_path)?;
+            info!("已从 {} 恢复数据库", source.display());
+            Ok(())
+        })
     }
     
-    /// 验证表结构是否正确
-    fn verify_table_structure() -> Result<()> {
-        let conn = Self::get_connection()?;
-        
-        // 检查 identities 表结构
-        Self::check_table_has_column(&conn, "identities", "identity", "身份标识表")?;
-        
-        // 检查 friends 表结构
-        Self::check_table_has_column(&conn, "friends", "address", "好友表")?;
+    /// 关闭共享连接，执行 before_open（替换数据库文件或切换档案）后重新初始化数据库；
+    /// 期间持有连接锁，其他线程不会打开旧的数据库
+    pub fn reopen_database(before_open: impl FnOnce() -> Result<()>) -> Result<()> {
+        {
+            let mut guard = CONNECTION.lock().unwrap_or_else(|e| e.into_inner());
+            if let Some(conn) = guard.take() {
+                if let Err((conn, e)) = conn.close() {
+                    *guard = Some(conn);
+                    return Err(e.into());
+                }
+            }
+            before_open()?;
+        }
+        Self::init_db()
+    }
+    
+    /// 获取当前档案的数据库文件路径
+    pub fn get_db_path() -> PathBuf {
+        let mut path = Profiles::active_dir();
+        std::fs::create_dir_all(&path).ok();
+        path.push("address_book.db");
+        path
+    }
+    
+    /// 初始化数据库：执行迁移并确保存在我的身份码
+    pub fn init_db() -> Result<()> {
+        {
+            let mut conn = Self::get_connection()?;
+            
+            // 按 user_version 执行尚未应用的迁移
+            migrations::migrate(&mut conn, &Self::get_db_path())?;
+            Self::load_blocked_prefixes(&conn)?;
+        }
         
-        // 检查文件接收记录表结构
-        Self::check_table_has_column(&conn, "file_receive_records", "filename", "文件接收记录表")?;
-        Self::check_table_has_column(&conn, "file_receive_records", "file_size", "文件接收记录表")?;
-        Self::check_table_has_column(&conn, "file_receive_records", "sender_ipv6", "文件接收记录表")?;
-        Self::check_table_has_column(&conn, "file_receive_records", "sender_identity", "文件接收记录表")?;
-        Self::check_table_has_column(&conn, "file_receive_records", "save_path", "文件接收记录表")?;
+        // 确保存在我的身份码
+        Self::ensure_my_identity()?;
         
+        info!("数据库初始化完成");
         Ok(())
     }
     
-    /// 确保存在我的身份码
+    /// 确保存在我的身份码；早期随机生成的身份码没有私钥，无法在握手中证明归属，更换为新的身份密钥
     fn ensure_my_identity() -> Result<()> {
-        let conn = Self::get_connection()?;
+        let has_secret: Option<bool> = {
+            let conn = Self::get_connection()?;
+            conn.query_row("SELECT secret_key IS NOT NULL FROM my_identity WHERE id = 1", [], |row| row.get(0))
+                .optional()?
+        };
         
-        // 检查是否已存在身份码
-        let mut stmt = conn.prepare("SELECT COUNT(*) FROM my_identity WHERE id = 1")?;
-        let count: i64 = stmt.query_row([], |row| row.get(0))?;
-        
-        if count == 0 {
-            // 生成新的身份码
-            let new_identity = CreateIdentity::new();
-            let identity_str: String = new_identity.iter().collect();
-            
-            // 插入新的身份码
-            conn.execute(
-                "INSERT INTO my_identity (id, identity) VALUES (1, ?1)",
-                &[&identity_str],
-            )?;
-            
-            info!("已生成新的身份码: {}", identity_str);
+        match has_secret {
+            None => {
+                // 生成新的身份密钥，身份码即公钥
+                let (identity_str, secret) = IdentityKey::generate();
+                
+                // 插入新的身份码
+                let conn = Self::get_connection()?;
+                conn.execute(
+                    "INSERT INTO my_identity (id, identity, secret_key) VALUES (1, ?1, ?2)",
+                    rusqlite::params![identity_str, secret.to_vec()],
+                )?;
+                
+                info!("已生成新的身份码: {}", identity_str);
+            }
+            Some(false) => {
+                // 早期身份码无法签名，联系人只能在收到文件时看到未经证明的变更，由界面告知用户
+                let old_identity = Self::get_my_identity()?;
+                let identity = Self::rotate_my_identity()?;
+                warn!("早期的身份码 {} 没有私钥，已更换为新的身份码: {}", old_identity, identity);
+                *LEGACY_REPLACEMENT.lock().unwrap() = Some(old_identity);
+            }
+            Some(true) => {}
         }
         
         Ok(())
     }
     
+    /// 取出启动时被更换的早期身份码，只返回一次
+    pub fn take_legacy_replacement() -> Option<String> {
+        LEGACY_REPLACEMENT.lock().unwrap().take()
+    }
+    
     // ===== 我的身份码操作 =====
     
     /// 获取我的身份码
@@ -185,73 +551,431 @@ impl AddressBook {
         Ok(identity)
     }
     
-    /// 重置我的身份码
-    pub fn reset_my_identity() -> Result<String> {
-        let conn = Self::get_connection()?;
+    /// 轮换我的身份码：生成新的身份密钥，并用旧私钥为新身份码签名，
+    /// 联系人下次收到文件时据此更新；早期随机生成的身份码没有私钥，无法签名
+    pub fn rotate_my_identity() -> Result<String> {
+        let mut conn = Self::get_connection()?;
+        let tx = conn.transaction()?;
+        let old_identity: String = tx.query_row("SELECT identity FROM my_identity WHERE id = 1", [], |row| row.get(0))?;
+        let old_secret = Self::load_secret_key(&tx)?;
         
-        // 生成新的身份码
-        let new_identity = CreateIdentity::new();
-        let identity_str: String = new_identity.iter().collect();
+        let (new_identity, new_secret) = IdentityKey::generate();
+        let signature = old_secret.map(|secret| IdentityKey::sign_rotation(&secret, &new_identity).signature);
+        let stored_secret = Self::seal_secret_key(&tx, &new_secret)?;
         
-        // 更新身份码
-        conn.execute(
-            "UPDATE my_identity SET identity = ?1, created_at = CURRENT_TIMESTAMP WHERE id = 1",
-            &[&identity_str],
+        tx.execute(
+            "INSERT INTO identity_history (identity, successor, signature) VALUES (?1, ?2, ?3)",
+            rusqlite::params![old_identity, new_identity, signature],
+        )?;
+        tx.execute(
+            "UPDATE my_identity SET identity = ?1, secret_key = ?2, created_at = CURRENT_TIMESTAMP WHERE id = 1",
+            rusqlite::params![new_identity, stored_secret],
         )?;
+        tx.commit()?;
         
-        info!("已重置身份码: {}", identity_str);
-        Ok(identity_str)
+        if signature.is_some() {
+            info!("已轮换身份码: {} -> {}", old_identity, new_identity);
+        } else {
+            info!("已更换身份码（旧身份码没有私钥，无法签名）: {}", new_identity);
+        }
+        Ok(new_identity)
     }
     
-    // ===== 身份标识表操作 =====
+    /// 读取身份私钥，设置了口令时需要先解锁；早期随机生成的身份码没有私钥
+    fn load_secret_key(conn: &Connection) -> Result<Option<[u8; 32]>> {
+        let (stored, salt): (Option<Vec<u8>>, Option<Vec<u8>>) = conn.query_row(
+            "SELECT secret_key, key_salt FROM my_identity WHERE id = 1",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        let Some(stored) = stored else { return Ok(None) };
+        let secret = match salt {
+            None => stored,
+            Some(_) => {
+                let key = KeyVault::unlocked_key()
+                    .ok_or_else(|| NearbyError::validation("身份密钥已锁定，请先输入口令解锁"))?;
+                KeyVault::open(&key, &stored).ok_or_else(|| NearbyError::validation("无法解密身份私钥"))?
+            }
+        };
+        <[u8; 32]>::try_from(secret.as_slice())
+            .map(Some)
+            .map_err(|_| NearbyError::validation("身份私钥已损坏"))
+    }
     
-    /// 添加身份标识
-    pub fn add_identity(identity: &str, alias: &str) -> Result<()> {
-        if identity.len() != 64 {
-            return Err(rusqlite::Error::InvalidParameterName("身份标识必须为64字符".to_string()));
+    /// 按当前的保护状态得到要保存的私钥：设置了口令时加密，否则原样保存
+    fn seal_secret_key(conn: &Connection, secret: &[u8; 32]) -> Result<Vec<u8>> {
+        let salt: Option<Vec<u8>> = conn.query_row("SELECT key_salt FROM my_identity WHERE id = 1", [], |row| row.get(0))?;
+        if salt.is_none() {
+            return Ok(secret.to_vec());
         }
-        
+        let key = KeyVault::unlocked_key()
+            .ok_or_else(|| NearbyError::validation("身份密钥已锁定，请先输入口令解锁"))?;
+        Ok(KeyVault::seal(&key, secret))
+    }
+    
+    /// 握手使用的静态私钥，由身份私钥转换而来，对方据此确认身份码属于本机；私钥已锁定时返回错误
+    pub fn my_noise_key() -> Result<[u8; 32]> {
         let conn = Self::get_connection()?;
-        conn.execute(
-            "INSERT INTO identities (identity, alias) VALUES (?1, ?2)",
-            &[identity, alias],
+        let secret = Self::load_secret_key(&conn)?
+            .ok_or_else(|| NearbyError::validation("当前身份码没有私钥，请先轮换身份码"))?;
+        Ok(IdentityKey::noise_private_key(&secret))
+    }
+    
+    /// 身份私钥是否设置了口令
+    pub fn identity_key_protected() -> Result<bool> {
+        let conn = Self::get_connection()?;
+        let protected: bool = conn.query_row(
+            "SELECT key_salt IS NOT NULL FROM my_identity WHERE id = 1",
+            [],
+            |row| row.get(0),
         )?;
-        info!("已添加身份标识: {} ({})", alias, identity);
+        Ok(protected)
+    }
+    
+    /// 身份私钥是否设置了口令且尚未解锁
+    pub fn identity_key_locked() -> Result<bool> {
+        Ok(Self::identity_key_protected()? && KeyVault::unlocked_key().is_none())
+    }
+    
+    /// 用口令解锁身份私钥，口令错误时返回错误
+    pub fn unlock_identity_key(passphrase: &str) -> Result<()> {
+        let (stored, salt): (Option<Vec<u8>>, Option<Vec<u8>>) = {
+            let conn = Self::get_connection()?;
+            conn.query_row(
+                "SELECT secret_key, key_salt FROM my_identity WHERE id = 1",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )?
+        };
+        let (Some(stored), Some(salt)) = (stored, salt) else {
+            return Ok(());
+        };
+        let key = KeyVault::derive_key(passphrase, &salt)?;
+        if KeyVault::open(&key, &stored).is_none() {
+            return Err(NearbyError::validation("口令错误"));
+        }
+        KeyVault::set_unlocked_key(Some(key));
+        info!("身份私钥已解锁");
         Ok(())
     }
     
-    /// 更新身份标识
-    pub fn update_identity(id: i64, identity: &str, alias: &str) -> Result<()> {
-        if identity.len() != 64 {
-            return Err(rusqlite::Error::InvalidParameterName("身份标识必须为64字符".to_string()));
+    /// 设置、修改或移除身份私钥的口令：未设置口令时 current 为空，new 为空表示移除口令
+    pub fn set_identity_passphrase(current: &str, new: &str) -> Result<()> {
+        let mut conn = Self::get_connection()?;
+        let tx = conn.transaction()?;
+        let salt: Option<Vec<u8>> = tx.query_row("SELECT key_salt FROM my_identity WHERE id = 1", [], |row| row.get(0))?;
+        
+        // 已设置口令时先用当前口令解锁，确认口令正确
+        if let Some(salt) = salt {
+            let key = KeyVault::derive_key(current, &salt).map_err(|_| NearbyError::validation("请输入当前口令"))?;
+            let stored: Option<Vec<u8>> = tx.query_row("SELECT secret_key FROM my_identity WHERE id = 1", [], |row| row.get(0))?;
+            if stored.and_then(|stored| KeyVault::open(&key, &stored)).is_none() {
+                return Err(NearbyError::validation("当前口令错误"));
+            }
+            KeyVault::set_unlocked_key(Some(key));
+        } else if new.is_empty() {
+            return Err(NearbyError::validation("新口令不能为空"));
         }
         
+        let secret = Self::load_secret_key(&tx)?
+            .ok_or_else(|| NearbyError::validation("当前身份码没有私钥，请先轮换身份码"))?;
+        if new.is_empty() {
+            tx.execute(
+                "UPDATE my_identity SET secret_key = ?1, key_salt = NULL WHERE id = 1",
+                rusqlite::params![secret.to_vec()],
+            )?;
+            tx.commit()?;
+            KeyVault::set_unlocked_key(None);
+            info!("已移除身份私钥的口令");
+        } else {
+            let salt = KeyVault::new_salt();
+            let key = KeyVault::derive_key(new, &salt)?;
+            tx.execute(
+                "UPDATE my_identity SET secret_key = ?1, key_salt = ?2 WHERE id = 1",
+                rusqlite::params![KeyVault::seal(&key, &secret), salt.to_vec()],
+            )?;
+            tx.commit()?;
+            KeyVault::set_unlocked_key(Some(key));
+            info!("已设置身份私钥的口令");
+        }
+        Ok(())
+    }
+    
+    /// 获取更换过的身份码，最近更换的在前
+    pub fn get_identity_history() -> Result<Vec<IdentityHistoryEntry>> {
         let conn = Self::get_connection()?;
-        conn.execute(
-            "UPDATE identities SET identity = ?1, alias = ?2 WHERE id = ?3",
-            &[identity, alias, &id.to_string()],
+        let mut stmt = conn.prepare(
+            "SELECT id, identity, successor, signature IS NOT NULL, retired_at, revoked
+             FROM identity_history ORDER BY retired_at DESC, id DESC"
+        )?;
+        let entries = stmt.query_map([], |row| {
+            Ok(IdentityHistoryEntry {
+                id: row.get(0)?,
+                identity: row.get(1)?,
+                successor: row.get(2)?,
+                signed: row.get(3)?,
+                retired_at: row.get(4)?,
+                revoked: row.get(5)?,
+            })
+        })?;
+        
+        let mut result = Vec::new();
+        for entry in entries {
+            result.push(entry?);
+        }
+        Ok(result)
+    }
+    
+    /// 撤销旧身份码：不再向联系人出示它签发的轮换证明
+    pub fn revoke_identity(id: i64) -> Result<()> {
+        let conn = Self::get_connection()?;
+        conn.execute("UPDATE identity_history SET revoked = 1 WHERE id = ?1", [id])?;
+        info!("已撤销旧身份码 ID: {}", id);
+        Ok(())
+    }
+    
+    /// 发送时附带的轮换证明：未撤销且有签名的旧身份码，最近更换的在前
+    pub fn my_rotation_proofs() -> Result<Vec<RotationProof>> {
+        let conn = Self::get_connection()?;
+        let mut stmt = conn.prepare(
+            "SELECT identity, successor, signature FROM identity_history
+             WHERE signature IS NOT NULL AND revoked = 0
+             ORDER BY retired_at DESC, id DESC LIMIT ?1"
+        )?;
+        let proofs = stmt.query_map([MAX_ROTATION_PROOFS as i64], |row| {
+            Ok(RotationProof {
+                old_identity: row.get(0)?,
+                new_identity: row.get(1)?,
+                signature: row.get(2)?,
+            })
+        })?;
+        
+        let mut result = Vec::new();
+        for proof in proofs {
+            result.push(proof?);
+        }
+        Ok(result)
+    }
+    
+    /// 更换前没有私钥的早期身份码（未撤销），发送时告知对方以便对方核对后更新
+    pub fn my_legacy_identity() -> Result<Option<String>> {
+        let conn = Self::get_connection()?;
+        let identity = conn
+            .query_row(
+                "SELECT identity FROM identity_history
+                 WHERE signature IS NULL AND revoked = 0
+                 ORDER BY retired_at DESC, id DESC LIMIT 1",
+                [],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(identity)
+    }
+    
+    // ===== 联系人操作 =====
+    
+    /// 添加联系人，返回新联系人的ID
+    pub fn add_contact(contact: &Contact) -> Result<i64> {
+        let contact = contact.normalized()?;
+        let mut conn = Self::get_connection()?;
+        let tx = conn.transaction()?;
+        tx.execute(
+            "INSERT INTO contacts (name, identity, port, trusted, trust_expires_at, trust_uses_left, verified, notes)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            rusqlite::params![
+                contact.name,
+                Self::identity_param(&contact.identity),
+                contact.port,
+                contact.trusted,
+                contact.trust_expires_at,
+                contact.trust_uses_left,
+                contact.verified,
+                contact.notes,
+            ],
         )?;
-        info!("已更新身份标识 ID {}: {} ({})", id, alias, identity);
+        let id = tx.last_insert_rowid();
+        Self::save_contact_addresses(&tx, id, &contact.addresses)?;
+        tx.commit()?;
+        info!("已添加联系人: {} (ID {})", contact.name, id);
+        Ok(id)
+    }
+    
+    /// 更新联系人及其地址列表
+    pub fn update_contact(contact: &Contact) -> Result<()> {
+        let contact = contact.normalized()?;
+        let mut conn = Self::get_connection()?;
+        let tx = conn.transaction()?;
+        let previous_identity = Self::contact_identity(&tx, contact.id)?;
+        tx.execute(
+            "UPDATE contacts SET name = ?1, identity = ?2, port = ?3, trusted = ?4, trust_expires_at = ?5,
+                trust_uses_left = ?6, verified = ?7, notes = ?8
+             WHERE id = ?9",
+            rusqlite::params![
+                contact.name,
+                Self::identity_param(&contact.identity),
+                contact.port,
+                contact.trusted,
+                contact.trust_expires_at,
+                contact.trust_uses_left,
+                contact.verified,
+                contact.notes,
+                contact.id,
+            ],
+        )?;
+        // 只删除已移除的地址，保留其余地址上次成功的记录
+        let existing: Vec<String> = {
+            let mut stmt = tx.prepare("SELECT address FROM contact_addresses WHERE contact_id = ?1")?;
+            let rows = stmt.query_map([contact.id], |row| row.get(0))?;
+            rows.collect::<rusqlite::Result<_>>()?
+        };
+        for address in existing.iter().filter(|a| !contact.addresses.contains(a)) {
+            tx.execute(
+                "DELETE FROM contact_addresses WHERE contact_id = ?1 AND address = ?2",
+                rusqlite::params![contact.id, address],
+            )?;
+        }
+        Self::save_contact_addresses(&tx, contact.id, &contact.addresses)?;
+        tx.commit()?;
+        // 白名单设置可能已修改，已扣除的发送会话需要重新检查
+        if let Some(identity) = previous_identity {
+            SessionLedger::forget_identity(&identity);
+        }
+        info!("已更新联系人 ID {}: {}", contact.id, contact.name);
         Ok(())
     }
     
-    /// 删除身份标识
-    pub fn delete_identity(id: i64) -> Result<()> {
+    /// 删除联系人，地址随之删除
+    pub fn delete_contact(id: i64) -> Result<()> {
         let conn = Self::get_connection()?;
-        conn.execute("DELETE FROM identities WHERE id = ?1", [id])?;
-        info!("已删除身份标识 ID: {}", id);
+        let identity = Self::contact_identity(&conn, id)?;
+        conn.execute("DELETE FROM contacts WHERE id = ?1", [id])?;
+        if let Some(identity) = identity {
+            SessionLedger::forget_identity(&identity);
+        }
+        info!("已删除联系人 ID: {}", id);
         Ok(())
     }
     
-    /// 获取所有身份标识
-    pub fn get_all_identities() -> Result<Vec<IdentityEntry>> {
+    // 联系人的身份码，没有身份码或联系人不存在时返回 None
+    fn contact_identity(conn: &Connection, id: i64) -> Result<Option<String>> {
+        let identity = conn
+            .query_row("SELECT identity FROM contacts WHERE id = ?1", [id], |row| row.get::<_, Option<String>>(0))
+            .optional()?;
+        Ok(identity.flatten())
+    }
+    
+    /// 获取所有联系人
+    pub fn get_all_contacts() -> Result<Vec<Contact>> {
+        Self::query_contacts(
+            "SELECT id, name, identity, trusted, notes, port, verified, trust_expires_at, trust_uses_left FROM contacts ORDER BY created_at DESC, id DESC",
+            &[],
+        )
+    }
+    
+    /// 搜索联系人，匹配名称、身份码、备注和地址
+    pub fn search_contacts(query: &str) -> Result<Vec<Contact>> {
+        let search_pattern = format!("%{}%", query);
+        Self::query_contacts(
+            "SELECT id, name, identity, trusted, notes, port, verified, trust_expires_at, trust_uses_left FROM contacts
+             WHERE name LIKE ?1 OR identity LIKE ?1 OR notes LIKE ?1
+                OR id IN (SELECT contact_id FROM contact_addresses WHERE address LIKE ?1)
+             ORDER BY created_at DESC, id DESC",
+            &[&search_pattern],
+        )
+    }
+    
+    /// 按完整身份码查找联系人
+    pub fn get_contact_by_identity(identity: &str) -> Result<Option<Contact>> {
+        let contacts = Self::query_contacts(
+            "SELECT id, name, identity, trusted, notes, port, verified, trust_expires_at, trust_uses_left FROM contacts WHERE identity = ?1",
+            &[&identity],
+        )?;
+        Ok(contacts.into_iter().next())
+    }
+    
+    /// 记录某个地址发送成功，下次向该联系人发送时优先使用
+    pub fn record_address_success(address: &str) -> Result<()> {
         let conn = Self::get_connection()?;
-        let mut stmt = conn.prepare("SELECT id, identity, alias FROM identities ORDER BY created_at DESC")?;
-        let entries = stmt.query_map([], |row| {
-            Ok(IdentityEntry {
+        let updated = conn.execute(
+            "UPDATE contact_addresses SET last_success_at = CURRENT_TIMESTAMP WHERE address = ?1",
+            [address],
+        )?;
+        if updated > 0 {
+            debug!("已记录地址发送成功: {}", address);
+        }
+        Ok(())
+    }
+    
+    /// 把身份码对应的联系人标记为已验证，没有对应的联系人时返回 false
+    pub fn mark_identity_verified(identity: &str) -> Result<bool> {
+        let conn = Self::get_connection()?;
+        let updated = conn.execute("UPDATE contacts SET verified = 1 WHERE identity = ?1", [identity])?;
+        if updated > 0 {
+            info!("已将身份码 {} 的联系人标记为已验证", identity);
+        }
+        Ok(updated > 0)
+    }
+    
+    /// 身份码是否属于白名单中仍然有效的联系人，不扣除次数
+    pub fn is_trusted(identity: &str) -> Result<bool> {
+        let conn = Self::get_connection()?;
+        let trusted = conn
+            .query_row(
+                "SELECT 1 FROM contacts
+                 WHERE identity = ?1 AND trusted = 1
+                   AND (trust_expires_at IS NULL OR trust_expires_at > CURRENT_TIMESTAMP)
+                   AND (trust_uses_left IS NULL OR trust_uses_left > 0)",
+                [identity],
+                |_| Ok(()),
+            )
+            .optional()?;
+        Ok(trusted.is_some())
+    }
+    
+    /// 身份码是否仍在白名单中且未到期，不看剩余次数；用于已扣除过的发送会话
+    pub fn is_trust_active(identity: &str) -> Result<bool> {
+        let conn = Self::get_connection()?;
+        let trusted = conn
+            .query_row(
+                "SELECT 1 FROM contacts
+                 WHERE identity = ?1 AND trusted = 1
+                   AND (trust_expires_at IS NULL OR trust_expires_at > CURRENT_TIMESTAMP)",
+                [identity],
+                |_| Ok(()),
+            )
+            .optional()?;
+        Ok(trusted.is_some())
+    }
+    
+    /// 传输成功后扣除白名单的一次使用次数，没有次数限制时不变；次数已用完时返回 false
+    pub fn consume_trust(identity: &str) -> Result<bool> {
+        let conn = Self::get_connection()?;
+        let updated = conn.execute(
+            "UPDATE contacts SET trust_uses_left = trust_uses_left - 1
+             WHERE identity = ?1 AND trusted = 1
+               AND (trust_expires_at IS NULL OR trust_expires_at > CURRENT_TIMESTAMP)
+               AND (trust_uses_left IS NULL OR trust_uses_left > 0)",
+            [identity],
+        )?;
+        Ok(updated > 0)
+    }
+    
+    fn query_contacts(sql: &str, params: &[&dyn rusqlite::ToSql]) -> Result<Vec<Contact>> {
+        let conn = Self::get_connection()?;
+        let mut stmt = conn.prepare(sql)?;
+        let entries = stmt.query_map(params, |row| {
+            Ok(Contact {
                 id: row.get(0)?,
-                identity: row.get(1)?,
-                alias: row.get(2)?,
+                name: row.get(1)?,
+                identity: row.get::<_, Option<String>>(2)?.unwrap_or_default(),
+                addresses: Vec::new(),
+                port: row.get(5)?,
+                last_successful: None,
+                trusted: row.get(3)?,
+                trust_expires_at: row.get(7)?,
+                trust_uses_left: row.get(8)?,
+                verified: row.get(6)?,
+                notes: row.get(4)?,
             })
         })?;
         
@@ -259,50 +983,194 @@ impl AddressBook {
         for entry in entries {
             result.push(entry?);
         }
+        
+        let mut address_stmt = conn.prepare(
+            "SELECT address, last_success_at FROM contact_addresses WHERE contact_id = ?1 ORDER BY position, id"
+        )?;
+        for contact in &mut result {
+            let addresses = address_stmt.query_map([contact.id], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?))
+            })?;
+            
+            // 上次成功时间最晚的地址
+            let mut latest: Option<String> = None;
+            for address in addresses {
+                let (address, success_at) = address?;
+                if success_at.is_some() && success_at > latest {
+                    latest = success_at;
+                    contact.last_successful = Some(address.clone());
+                }
+                contact.addresses.push(address);
+            }
+        }
         Ok(result)
     }
     
-    // ===== 好友列表表操作 =====
+    // 按列表顺序写入联系人的地址，已有的地址只更新顺序
+    fn save_contact_addresses(tx: &rusqlite::Transaction, contact_id: i64, addresses: &[String]) -> Result<()> {
+        for (position, address) in addresses.iter().enumerate() {
+            tx.execute(
+                "INSERT INTO contact_addresses (contact_id, address, position) VALUES (?1, ?2, ?3)
+                 ON CONFLICT(contact_id, address) DO UPDATE SET position = excluded.position",
+                rusqlite::params![contact_id, address, position as i64],
+            )?;
+        }
+        Ok(())
+    }
+    
+    // 身份码为空时保存为 NULL，避免与唯一约束冲突
+    fn identity_param(identity: &str) -> Option<&str> {
+        if identity.is_empty() { None } else { Some(identity) }
+    }
+    
+    // ===== 联系人身份码变更 =====
+    
+    /// 对方以新身份码连接并出示了轮换证明时，沿签名有效的证明链查找仍为旧身份码的联系人；
+    /// 新身份码已属于某个联系人时返回 None。只读取，不记录变更
+    pub fn resolve_identity_rotation(claimed: &str, proofs: &[RotationProof]) -> Result<Option<Contact>> {
+        if proofs.is_empty() || Self::get_contact_by_identity(claimed)?.is_some() {
+            return Ok(None);
+        }
+        let contacts = Self::get_all_contacts()?;
+        let Some(old_identity) = IdentityKey::resolve_chain(proofs, claimed, |identity| {
+            contacts.iter().any(|c| c.identity == identity)
+        }) else {
+            return Ok(None);
+        };
+        Ok(contacts.into_iter().find(|c| c.identity == old_identity))
+    }
     
-    /// 添加IPv6地址到好友列表
-    pub fn add_friend(address: &str, alias: &str) -> Result<()> {
+    /// 记录联系人更换身份码，等待确认；调用方需已确认对方持有新身份码并允许了本次连接
+    pub fn record_identity_rotation(contact: &Contact, new_identity: &str) -> Result<()> {
         let conn = Self::get_connection()?;
-        conn.execute(
-            "INSERT INTO friends (address, alias) VALUES (?1, ?2)",
-            &[address, alias],
+        let updated = conn.execute(
+            "INSERT INTO identity_rotations (contact_id, new_identity, proven) VALUES (?1, ?2, 1)
+             ON CONFLICT(contact_id) DO UPDATE SET new_identity = excluded.new_identity, proven = 1, received_at = CURRENT_TIMESTAMP
+             WHERE new_identity != excluded.new_identity OR proven = 0",
+            rusqlite::params![contact.id, new_identity],
         )?;
-        info!("已添加好友: {} ({})", alias, address);
+        if updated > 0 {
+            info!("联系人 {} 已更换身份码: {}，等待确认", contact.name, new_identity);
+        }
         Ok(())
     }
     
-    /// 更新好友信息
-    pub fn update_friend(id: i64, address: &str, alias: &str) -> Result<()> {
+    /// 对方声称由早期身份码更换而来时，记录为未经证明的变更，由用户核对安全码后确认；
+    /// 只适用于没有私钥的早期身份码，有私钥的身份码必须出示签名。返回是否有对应的联系人
+    pub fn record_unproven_rotation(legacy_identity: &str, new_identity: &str) -> Result<bool> {
+        if IdentityKey::noise_public_key(legacy_identity).is_some() || Self::get_contact_by_identity(new_identity)?.is_some() {
+            return Ok(false);
+        }
+        let Some(contact) = Self::get_contact_by_identity(legacy_identity)? else {
+            return Ok(false);
+        };
         let conn = Self::get_connection()?;
-        conn.execute(
-            "UPDATE friends SET address = ?1, alias = ?2 WHERE id = ?3",
-            &[address, alias, &id.to_string()],
+        let updated = conn.execute(
+            "INSERT INTO identity_rotations (contact_id, new_identity, proven) VALUES (?1, ?2, 0)
+             ON CONFLICT(contact_id) DO UPDATE SET new_identity = excluded.new_identity, proven = 0, received_at = CURRENT_TIMESTAMP
+             WHERE new_identity != excluded.new_identity",
+            rusqlite::params![contact.id, new_identity],
         )?;
-        info!("已更新好友 ID {}: {} ({})", id, alias, address);
+        if updated > 0 {
+            warn!("联系人 {} 的早期身份码已更换为 {}，无法证明，等待核对", contact.name, new_identity);
+        }
+        Ok(true)
+    }
+    
+    /// 获取等待确认的联系人身份码变更
+    pub fn get_pending_rotations() -> Result<Vec<PendingRotation>> {
+        let pending: Vec<(i64, String, String, bool)> = {
+            let conn = Self::get_connection()?;
+            let mut stmt = conn.prepare(
+                "SELECT contact_id, new_identity, received_at, proven FROM identity_rotations ORDER BY received_at"
+            )?;
+            let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)))?;
+            rows.collect::<rusqlite::Result<_>>()?
+        };
+        let contacts = Self::get_all_contacts()?;
+        Ok(pending
+            .into_iter()
+            .filter_map(|(contact_id, new_identity, received_at, proven)| {
+                let contact = contacts.iter().find(|c| c.id == contact_id)?.clone();
+                Some(PendingRotation { contact, new_identity, received_at, proven })
+            })
+            .collect())
+    }
+    
+    /// 确认联系人的身份码变更，白名单和验证状态随之保留
+    pub fn accept_identity_rotation(contact_id: i64) -> Result<()> {
+        let mut conn = Self::get_connection()?;
+        let tx = conn.transaction()?;
+        let new_identity: String = tx.query_row(
+            "SELECT new_identity FROM identity_rotations WHERE contact_id = ?1",
+            [contact_id],
+            |row| row.get(0),
+        )?;
+        let owner: Option<String> = tx
+            .query_row("SELECT name FROM contacts WHERE identity = ?1", [&new_identity], |row| row.get(0))
+            .optional()?;
+        if let Some(owner) = owner {
+            return Err(NearbyError::validation(format!("新身份码已属于联系人 {}", owner)));
+        }
+        tx.execute("UPDATE contacts SET identity = ?1 WHERE id = ?2", rusqlite::params![new_identity, contact_id])?;
+        tx.execute("DELETE FROM identity_rotations WHERE contact_id = ?1", [contact_id])?;
+        tx.commit()?;
+        info!("已更新联系人 ID {} 的身份码: {}", contact_id, new_identity);
+        Ok(())
+    }
+    
+    /// 忽略联系人的身份码变更
+    pub fn dismiss_identity_rotation(contact_id: i64) -> Result<()> {
+        let conn = Self::get_connection()?;
+        conn.execute("DELETE FROM identity_rotations WHERE contact_id = ?1", [contact_id])?;
+        Ok(())
+    }
+    
+    // ===== 联系人分组操作 =====
+    
+    /// 添加分组，返回新分组的ID
+    pub fn add_group(group: &ContactGroup) -> Result<i64> {
+        let name = Self::group_name(group)?;
+        let mut conn = Self::get_connection()?;
+        let tx = conn.transaction()?;
+        tx.execute("INSERT INTO contact_groups (name) VALUES (?1)", [&name])?;
+        let id = tx.last_insert_rowid();
+        Self::save_group_members(&tx, id, &group.member_ids)?;
+        tx.commit()?;
+        info!("已添加分组: {} ({} 个成员)", name, group.member_ids.len());
+        Ok(id)
+    }
+    
+    /// 更新分组名称和成员
+    pub fn update_group(group: &ContactGroup) -> Result<()> {
+        let name = Self::group_name(group)?;
+        let mut conn = Self::get_connection()?;
+        let tx = conn.transaction()?;
+        tx.execute("UPDATE contact_groups SET name = ?1 WHERE id = ?2", rusqlite::params![name, group.id])?;
+        tx.execute("DELETE FROM contact_group_members WHERE group_id = ?1", [group.id])?;
+        Self::save_group_members(&tx, group.id, &group.member_ids)?;
+        tx.commit()?;
+        info!("已更新分组 ID {}: {} ({} 个成员)", group.id, name, group.member_ids.len());
         Ok(())
     }
     
-    /// 从好友列表删除地址
-    pub fn delete_friend(id: i64) -> Result<()> {
+    /// 删除分组，成员联系人不受影响
+    pub fn delete_group(id: i64) -> Result<()> {
         let conn = Self::get_connection()?;
-        conn.execute("DELETE FROM friends WHERE id = ?1", [id])?;
-        info!("已删除好友 ID: {}", id);
+        conn.execute("DELETE FROM contact_groups WHERE id = ?1", [id])?;
+        info!("已删除分组 ID: {}", id);
         Ok(())
     }
     
-    /// 获取所有好友地址
-    pub fn get_all_friends() -> Result<Vec<FriendEntry>> {
+    /// 获取所有分组及其成员
+    pub fn get_all_groups() -> Result<Vec<ContactGroup>> {
         let conn = Self::get_connection()?;
-        let mut stmt = conn.prepare("SELECT id, address, alias FROM friends ORDER BY created_at DESC")?;
+        let mut stmt = conn.prepare("SELECT id, name FROM contact_groups ORDER BY name")?;
         let entries = stmt.query_map([], |row| {
-            Ok(FriendEntry {
+            Ok(ContactGroup {
                 id: row.get(0)?,
-                address: row.get(1)?,
-                alias: row.get(2)?,
+                name: row.get(1)?,
+                member_ids: Vec::new(),
             })
         })?;
         
@@ -310,24 +1178,80 @@ impl AddressBook {
         for entry in entries {
             result.push(entry?);
         }
+        
+        let mut member_stmt = conn.prepare(
+            "SELECT contact_id FROM contact_group_members WHERE group_id = ?1 ORDER BY contact_id"
+        )?;
+        for group in &mut result {
+            let members = member_stmt.query_map([group.id], |row| row.get(0))?;
+            for member in members {
+                group.member_ids.push(member?);
+            }
+        }
         Ok(result)
     }
     
-    // 搜索好友
-    pub fn search_friends(query: &str) -> Result<Vec<FriendEntry>> {
+    fn group_name(group: &ContactGroup) -> Result<String> {
+        let name = group.name.trim();
+        if name.is_empty() {
+            return Err(NearbyError::validation("分组名称不能为空"));
+        }
+        Ok(name.to_string())
+    }
+    
+    fn save_group_members(tx: &rusqlite::Transaction, group_id: i64, member_ids: &[i64]) -> Result<()> {
+        for contact_id in member_ids {
+            tx.execute(
+                "INSERT OR IGNORE INTO contact_group_members (group_id, contact_id) VALUES (?1, ?2)",
+                [group_id, *contact_id],
+            )?;
+        }
+        Ok(())
+    }
+    
+    // ===== 邀请令牌操作 =====
+    
+    /// 生成邀请令牌，uses 为可用次数，days 为有效天数，至少设置一项
+    pub fn create_invitation(note: &str, uses: Option<u32>, days: Option<u32>, add_to_whitelist: bool) -> Result<Invitation> {
+        if uses.is_none() && days.is_none() {
+            return Err(NearbyError::validation("请设置可用次数或有效天数"));
+        }
+        if uses == Some(0) || days == Some(0) {
+            return Err(NearbyError::validation("可用次数和有效天数必须大于 0"));
+        }
+        let token = ReceiveCode::random_code(INVITATION_TOKEN_LENGTH);
         let conn = Self::get_connection()?;
-        let search_pattern = format!("%{}%", query);
-        let mut stmt = conn.prepare(
-            "SELECT id, address, alias FROM friends
-             WHERE alias LIKE ?1 OR address LIKE ?2
-             ORDER BY created_at DESC"
+        conn.execute(
+            "INSERT INTO invitations (token, note, uses_left, expires_at, add_to_whitelist)
+             VALUES (?1, ?2, ?3, CASE WHEN ?4 IS NULL THEN NULL ELSE datetime('now', '+' || ?4 || ' days') END, ?5)",
+            rusqlite::params![token, note.trim(), uses, days, add_to_whitelist],
         )?;
+        let id = conn.last_insert_rowid();
+        info!("已生成邀请令牌 ID {}", id);
+        drop(conn);
         
-        let entries = stmt.query_map([&search_pattern, &search_pattern], |row| {
-            Ok(FriendEntry {
+        Self::get_invitations()?
+            .into_iter()
+            .find(|invitation| invitation.id == id)
+            .ok_or_else(|| NearbyError::validation("邀请令牌保存失败"))
+    }
+    
+    /// 获取所有邀请令牌，新生成的在前
+    pub fn get_invitations() -> Result<Vec<Invitation>> {
+        let conn = Self::get_connection()?;
+        let mut stmt = conn.prepare(
+            "SELECT id, token, note, uses_left, expires_at, add_to_whitelist, created_at
+             FROM invitations ORDER BY created_at DESC, id DESC"
+        )?;
+        let entries = stmt.query_map([], |row| {
+            Ok(Invitation {
                 id: row.get(0)?,
-                address: row.get(1)?,
-                alias: row.get(2)?,
+                token: row.get(1)?,
+                note: row.get(2)?,
+                uses_left: row.get(3)?,
+                expires_at: row.get(4)?,
+                add_to_whitelist: row.get(5)?,
+                created_at: row.get(6)?,
             })
         })?;
         
@@ -338,21 +1262,127 @@ impl AddressBook {
         Ok(result)
     }
     
-    // 搜索身份标识
-    pub fn search_identities(query: &str) -> Result<Vec<IdentityEntry>> {
+    /// 撤销邀请令牌
+    pub fn delete_invitation(id: i64) -> Result<()> {
+        let conn = Self::get_connection()?;
+        conn.execute("DELETE FROM invitations WHERE id = ?1", [id])?;
+        SessionLedger::forget_invitation(id);
+        info!("已撤销邀请令牌 ID: {}", id);
+        Ok(())
+    }
+    
+    /// 查找仍然有效的邀请令牌，返回其ID，不扣除次数
+    pub fn find_invitation(token: &str) -> Result<Option<i64>> {
+        let token = ReceiveCode::normalize(token);
+        let conn = Self::get_connection()?;
+        let id = conn
+            .query_row(
+                "SELECT id FROM invitations
+                 WHERE REPLACE(token, '-', '') = ?1
+                   AND (expires_at IS NULL OR expires_at > CURRENT_TIMESTAMP)
+                   AND (uses_left IS NULL OR uses_left > 0)",
+                [&token],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(id)
+    }
+    
+    /// 邀请令牌是否未被撤销且未到期，不看剩余次数；用于已扣除过的发送会话
+    pub fn is_invitation_active(id: i64) -> Result<bool> {
+        let conn = Self::get_connection()?;
+        let active = conn
+            .query_row(
+                "SELECT 1 FROM invitations
+                 WHERE id = ?1 AND (expires_at IS NULL OR expires_at > CURRENT_TIMESTAMP)",
+                [id],
+                |_| Ok(()),
+            )
+            .optional()?;
+        Ok(active.is_some())
+    }
+    
+    /// 传输成功后使用邀请令牌，扣除一次并返回 true，令牌已失效时返回 false；
+    /// 令牌要求加入白名单时把发送方长期加入白名单，没有对应联系人时新建
+    pub fn redeem_invitation(id: i64, identity: &str) -> Result<bool> {
+        let mut conn = Self::get_connection()?;
+        let tx = conn.transaction()?;
+        let invitation = tx
+            .query_row(
+                "SELECT note, add_to_whitelist FROM invitations
+                 WHERE id = ?1
+                   AND (expires_at IS NULL OR expires_at > CURRENT_TIMESTAMP)
+                   AND (uses_left IS NULL OR uses_left > 0)",
+                [id],
+                |row| Ok((row.get::<_, String>(0)?, row.get::<_, bool>(1)?)),
+            )
+            .optional()?;
+        let Some((note, add_to_whitelist)) = invitation else {
+            return Ok(false);
+        };
+        tx.execute("UPDATE invitations SET uses_left = uses_left - 1 WHERE id = ?1", [id])?;
+        
+        if add_to_whitelist {
+            let updated = tx.execute(
+                "UPDATE contacts SET trusted = 1, trust_expires_at = NULL, trust_uses_left = NULL WHERE identity = ?1",
+                [identity],
+            )?;
+            if updated == 0 {
+                let name = if note.is_empty() { format!("邀请 {}", id) } else { note };
+                tx.execute(
+                    "INSERT INTO contacts (name, identity, trusted) VALUES (?1, ?2, 1)",
+                    rusqlite::params![name, identity],
+                )?;
+            }
+            info!("身份码 {} 使用邀请令牌 ID {}，已加入白名单", identity, id);
+        } else {
+            info!("身份码 {} 使用邀请令牌 ID {}", identity, id);
+        }
+        tx.commit()?;
+        Ok(true)
+    }
+    
+    // ===== 屏蔽列表操作 =====
+    
+    /// 添加屏蔽条目，返回新条目的ID
+    pub fn add_block_entry(kind: BlockKind, value: &str, note: &str) -> Result<i64> {
+        let value = kind.normalize(value)?;
+        let conn = Self::get_connection()?;
+        let inserted = conn.execute(
+            "INSERT OR IGNORE INTO blocklist (kind, value, note) VALUES (?1, ?2, ?3)",
+            rusqlite::params![kind.as_str(), value, note.trim()],
+        )?;
+        if inserted == 0 {
+            return Err(NearbyError::validation(format!("{} 已在屏蔽列表中", value)));
+        }
+        let id = conn.last_insert_rowid();
+        Self::load_blocked_prefixes(&conn)?;
+        info!("已屏蔽{}: {}", kind.label(), value);
+        Ok(id)
+    }
+    
+    /// 删除屏蔽条目
+    pub fn delete_block_entry(id: i64) -> Result<()> {
+        let conn = Self::get_connection()?;
+        conn.execute("DELETE FROM blocklist WHERE id = ?1", [id])?;
+        Self::load_blocked_prefixes(&conn)?;
+        info!("已删除屏蔽条目 ID: {}", id);
+        Ok(())
+    }
+    
+    /// 获取所有屏蔽条目，新添加的在前
+    pub fn get_block_entries() -> Result<Vec<BlockEntry>> {
         let conn = Self::get_connection()?;
-        let search_pattern = format!("%{}%", query);
         let mut stmt = conn.prepare(
-            "SELECT id, identity, alias FROM identities
-             WHERE alias LIKE ?1 OR identity LIKE ?2
-             ORDER BY created_at DESC"
+            "SELECT id, kind, value, note, created_at FROM blocklist ORDER BY created_at DESC, id DESC"
         )?;
-        
-        let entries = stmt.query_map([&search_pattern, &search_pattern], |row| {
-            Ok(IdentityEntry {
+        let entries = stmt.query_map([], |row| {
+            Ok(BlockEntry {
                 id: row.get(0)?,
-                identity: row.get(1)?,
-                alias: row.get(2)?,
+                kind: BlockKind::from_db_value(&row.get::<_, String>(1)?),
+                value: row.get(2)?,
+                note: row.get(3)?,
+                created_at: row.get(4)?,
             })
         })?;
         
@@ -363,6 +1393,45 @@ impl AddressBook {
         Ok(result)
     }
     
+    /// 身份码是否在屏蔽列表中
+    pub fn is_identity_blocked(identity: &str) -> Result<bool> {
+        let conn = Self::get_connection()?;
+        let blocked = conn
+            .query_row(
+                "SELECT 1 FROM blocklist WHERE kind = ?1 AND value = ?2",
+                [BlockKind::Identity.as_str(), identity],
+                |_| Ok(()),
+            )
+            .optional()?;
+        Ok(blocked.is_some())
+    }
+    
+    /// 地址是否落在屏蔽列表的某个地址段中，IPv4 地址按映射后的 IPv6 地址比较；
+    /// 只读取内存中的地址段，不访问数据库
+    pub fn is_address_blocked(address: IpAddr) -> bool {
+        let address = match address {
+            IpAddr::V6(v6) => u128::from(v6),
+            IpAddr::V4(v4) => u128::from(v4.to_ipv6_mapped()),
+        };
+        BLOCKED_PREFIXES
+            .lock()
+            .unwrap()
+            .iter()
+            .any(|(network, len)| address & prefix_mask(*len) == u128::from(*network))
+    }
+    
+    // 从数据库重新加载屏蔽的地址段
+    fn load_blocked_prefixes(conn: &Connection) -> Result<()> {
+        let mut stmt = conn.prepare("SELECT value FROM blocklist WHERE kind = ?1")?;
+        let prefixes = stmt.query_map([BlockKind::Prefix.as_str()], |row| row.get::<_, String>(0))?;
+        let mut loaded = Vec::new();
+        for prefix in prefixes {
+            loaded.extend(parse_ipv6_prefix(&prefix?));
+        }
+        *BLOCKED_PREFIXES.lock().unwrap() = loaded;
+        Ok(())
+    }
+    
     // ===== 文件接收记录操作 =====
     
     fn query_file_records(
@@ -381,6 +1450,10 @@ impl AddressBook {
                 sender_identity: row.get(4)?,
                 save_path: row.get(5)?,
                 received_at: row.get(6)?,
+                resolution: ConflictResolution::from_db_value(&row.get::<_, String>(7)?),
+                contact_name: row.get(8)?,
+                sender_authenticated: row.get(9)?,
+                sender_verified: row.get(10)?,
             })
         })?;
         
@@ -391,19 +1464,20 @@ impl AddressBook {
         Ok(result)
     }
     
-    /// 添加文件接收记录
+    /// 添加文件接收记录，身份码已在握手中确认
     pub fn add_file_receive_record(
         filename: &str,
         file_size: u64,
         sender_ipv6: &str,
         sender_identity: &str,
         save_path: &str,
+        resolution: ConflictResolution,
     ) -> Result<()> {
         let conn = Self::get_connection()?;
         conn.execute(
-            "INSERT INTO file_receive_records (filename, file_size, sender_ipv6, sender_identity, save_path)
-             VALUES (?1, ?2, ?3, ?4, ?5)",
-            &[filename, &file_size.to_string(), sender_ipv6, sender_identity, save_path],
+            "INSERT INTO file_receive_records (filename, file_size, sender_ipv6, sender_identity, save_path, conflict_resolution, sender_authenticated)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, 1)",
+            &[filename, &file_size.to_string(), sender_ipv6, sender_identity, save_path, resolution.as_str()],
         )?;
         
         info!("已记录文件接收: {} ({} 字节) 来自 {}",
@@ -414,9 +1488,12 @@ impl AddressBook {
     /// 获取所有文件接收记录
     pub fn get_all_file_receive_records() -> Result<Vec<FileReceiveRecord>> {
         Self::query_file_records(
-            "SELECT id, filename, file_size, sender_ipv6, sender_identity, save_path, received_at
-            FROM file_receive_records
-            ORDER BY received_at DESC",
+            "SELECT r.id, r.filename, r.file_size, r.sender_ipv6, r.sender_identity, r.save_path, r.received_at,
+                    r.conflict_resolution, c.name, r.sender_authenticated,
+                    r.sender_authenticated AND COALESCE(c.verified, 0)
+            FROM file_receive_records r
+            LEFT JOIN contacts c ON c.identity = r.sender_identity
+            ORDER BY r.received_at DESC",
             &[]
         )
     }
@@ -436,4 +1513,159 @@ impl AddressBook {
         info!("已清除所有文件接收记录");
         Ok(())
     }
-}
\ No newline at end of file
+    
+    // ===== 入站连接审计操作 =====
+    
+    /// 记录一次入站连接
+    pub fn add_connection_event(
+        source_address: &str,
+        claimed_identity: &str,
+        authenticated_identity: &str,
+        outcome: ConnectionOutcome,
+        bytes: u64,
+    ) -> Result<()> {
+        let conn = Self::get_connection()?;
+        conn.execute(
+            "INSERT INTO connection_events (source_address, claimed_identity, authenticated_identity, outcome, bytes)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            rusqlite::params![source_address, claimed_identity, authenticated_identity, outcome.as_str(), bytes],
+        )?;
+        conn.execute(
+            "DELETE FROM connection_events
+             WHERE id <= (SELECT id FROM connection_events ORDER BY id DESC LIMIT 1 OFFSET ?1)",
+            [MAX_CONNECTION_EVENTS],
+        )?;
+        debug!("已记录入站连接: {} {}", source_address, outcome.as_str());
+        Ok(())
+    }
+    
+    /// 按结果和关键字筛选入站连接记录，关键字匹配地址和身份
+    pub fn get_connection_events(
+        outcome: Option<ConnectionOutcome>,
+        query: &str,
+    ) -> Result<Vec<ConnectionEvent>> {
+        let conn = Self::get_connection()?;
+        let search_pattern = format!("%{}%", query);
+        let mut stmt = conn.prepare(
+            "SELECT id, occurred_at, source_address, claimed_identity, authenticated_identity, outcome, bytes
+             FROM connection_events
+             WHERE (?1 IS NULL OR outcome = ?1)
+               AND (source_address LIKE ?2 OR claimed_identity LIKE ?2 OR authenticated_identity LIKE ?2)
+             ORDER BY occurred_at DESC, id DESC"
+        )?;
+        
+        let entries = stmt.query_map(
+            rusqlite::params![outcome.map(|o| o.as_str()), search_pattern],
+            |row| {
+                Ok(ConnectionEvent {
+                    id: row.get(0)?,
+                    occurred_at: row.get(1)?,
+                    source_address: row.get(2)?,
+                    claimed_identity: row.get(3)?,
+                    authenticated_identity: row.get(4)?,
+                    outcome: ConnectionOutcome::from_db_value(&row.get::<_, String>(5)?),
+                    bytes: row.get(6)?,
+                })
+            },
+        )?;
+        
+        let mut result = Vec::new();
+        for entry in entries {
+            result.push(entry?);
+        }
+        Ok(result)
+    }
+    
+    /// 清除所有入站连接记录
+    pub fn delete_all_connection_events() -> Result<()> {
+        let conn = Self::get_connection()?;
+        conn.execute("DELETE FROM connection_events", [])?;
+        info!("已清除所有入站连接记录");
+        Ok(())
+    }
+    
+    // ===== 程序设置操作 =====
+    
+    /// 读取所有已保存的设置项
+    pub fn get_settings() -> Result<HashMap<String, String>> {
+        let conn = Self::get_connection()?;
+        let mut stmt = conn.prepare("SELECT key, value FROM settings")?;
+        let entries = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        
+        let mut result = HashMap::new();
+        for entry in entries {
+            let (key, value) = entry?;
+            result.insert(key, value);
+        }
+        Ok(result)
+    }
+    
+    /// 在一个事务中保存多个设置项
+    pub fn save_settings(values: &[(&str, String)]) -> Result<()> {
+        let mut conn = Self::get_connection()?;
+        let tx = conn.transaction()?;
+        for (key, value) in values {
+            tx.execute(
+                "INSERT INTO settings (key, value) VALUES (?1, ?2)
+                 ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated_at = CURRENT_TIMESTAMP",
+                rusqlite::params![key, value],
+            )?;
+        }
+        tx.commit()?;
+        debug!("已保存 {} 个设置项", values.len());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn prefix(address: &str, len: u8) -> Option<(Ipv6Addr, u8)> {
+        Some((address.parse().unwrap(), len))
+    }
+    
+    #[test]
+    fn parse_ipv6_prefix_masks_host_bits() {
+        assert_eq!(parse_ipv6_prefix("2001:db8:1:2:3:4:5:6/64"), prefix("2001:db8:1:2::", 64));
+        assert_eq!(parse_ipv6_prefix(" 2001:db8::1 / 48 "), prefix("2001:db8::", 48));
+        assert_eq!(parse_ipv6_prefix("2001:db8:ffff::/33"), prefix("2001:db8:8000::", 33));
+        assert_eq!(parse_ipv6_prefix("2001:db8::1/0"), prefix("::", 0));
+    }
+    
+    #[test]
+    fn parse_ipv6_prefix_defaults_to_single_address() {
+        assert_eq!(parse_ipv6_prefix("2001:db8::1"), prefix("2001:db8::1", 128));
+        assert_eq!(parse_ipv6_prefix("2001:db8::1/128"), prefix("2001:db8::1", 128));
+    }
+    
+    #[test]
+    fn parse_ipv6_prefix_rejects_invalid_input() {
+        assert_eq!(parse_ipv6_prefix("2001:db8::1/129"), None);
+        assert_eq!(parse_ipv6_prefix("2001:db8::1/-1"), None);
+        assert_eq!(parse_ipv6_prefix("2001:db8::1/"), None);
+        assert_eq!(parse_ipv6_prefix("192.0.2.1/24"), None);
+        assert_eq!(parse_ipv6_prefix(""), None);
+    }
+    
+    #[test]
+    fn blocked_prefixes_are_checked_in_memory() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        migrations::migrate(&mut conn, Path::new(":memory:")).unwrap();
+        conn.execute_batch(
+            "INSERT INTO blocklist (kind, value) VALUES ('prefix', '2001:db8:99::/48');
+             INSERT INTO blocklist (kind, value) VALUES ('prefix', '::ffff:198.51.100.0/120');
+             INSERT INTO blocklist (kind, value) VALUES ('identity', '2001:db8:aa::1');",
+        ).unwrap();
+        AddressBook::load_blocked_prefixes(&conn).unwrap();
+        
+        assert!(AddressBook::is_address_blocked("2001:db8:99:1::5".parse().unwrap()));
+        assert!(AddressBook::is_address_blocked("198.51.100.7".parse().unwrap()));
+        assert!(!AddressBook::is_address_blocked("2001:db8:98::1".parse().unwrap()));
+        assert!(!AddressBook::is_address_blocked("2001:db8:aa::1".parse().unwrap()));
+        
+        conn.execute("DELETE FROM blocklist WHERE kind = 'prefix'", []).unwrap();
+        AddressBook::load_blocked_prefixes(&conn).unwrap();
+        assert!(!AddressBook::is_address_blocked("2001:db8:99:1::5".parse().unwrap()));
+    }
+}
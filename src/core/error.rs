@@ -0,0 +1,121 @@
+// src/core/error.rs
+// 核心模块统一使用的错误类型
+use std::error;
+use std::fmt;
+use std::io;
+
+pub type Result<T, E = NearbyError> = std::result::Result<T, E>;
+
+// 对方拒绝本次传输的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RejectReason {
+    Busy,         // 接收方连接数已满
+    ShuttingDown, // 接收方正在关闭
+    Refused,      // 接收方未接受（接收已关闭或不在白名单中）
+    CodeUnavailable, // 接收方的口令已过期或已被使用
+}
+
+#[derive(Debug)]
+pub enum NearbyError {
+    Io(io::Error),                // 本地文件读写失败
+    Network(io::Error),           // 连接或网络读写失败
+    Timeout { phase: String, secs: u64 }, // 某个传输阶段超时
+    Handshake(String),            // Noise 握手失败
+    Protocol(String),             // 对方发送了无效的数据
+    Rejected(RejectReason),       // 对方拒绝了本次传输
+    IdentityMismatch,             // 对方握手使用的密钥与联系人的身份码不符
+    Validation(String),           // 输入不合法
+    Database(rusqlite::Error),    // 数据库操作失败
+    DatabaseVersion { found: i64, supported: i64 }, // 数据库版本高于程序支持的版本
+}
+
+impl NearbyError {
+    pub fn validation(message: impl Into<String>) -> Self {
+        NearbyError::Validation(message.into())
+    }
+    
+    pub fn protocol(message: impl Into<String>) -> Self {
+        NearbyError::Protocol(message.into())
+    }
+    
+    // 是否由超时引起
+    pub fn is_timeout(&self) -> bool {
+        matches!(self, NearbyError::Timeout { .. })
+    }
+    
+    // 是否因为接收方繁忙被拒绝，发送方据此退避重试
+    pub fn is_busy(&self) -> bool {
+        matches!(self, NearbyError::Rejected(RejectReason::Busy))
+    }
+}
+
+impl fmt::Display for RejectReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RejectReason::Busy => write!(f, "接收方繁忙"),
+            RejectReason::ShuttingDown => write!(f, "接收方正在关闭"),
+            RejectReason::Refused => write!(f, "接收方拒绝了本次传输"),
+            RejectReason::CodeUnavailable => write!(f, "接收口令已过期或已被使用"),
+        }
+    }
+}
+
+impl fmt::Display for NearbyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NearbyError::Io(e) => write!(f, "文件读写失败: {}", e),
+            NearbyError::Network(e) => write!(f, "网络错误: {}", e),
+            NearbyError::Timeout { phase, secs } => write!(f, "{}超时 ({} 秒)", phase, secs),
+            NearbyError::Handshake(e) => write!(f, "握手失败: {}", e),
+            NearbyError::Protocol(e) => write!(f, "协议错误: {}", e),
+            NearbyError::Rejected(reason) => write!(f, "{}", reason),
+            NearbyError::IdentityMismatch => write!(f, "对方的握手密钥与联系人的身份码不符"),
+            NearbyError::Validation(e) => write!(f, "{}", e),
+            NearbyError::Database(e) => write!(f, "数据库错误: {}", e),
+            NearbyError::DatabaseVersion { found, supported } => {
+                write!(f, "数据库版本 {} 高于程序支持的版本 {}", found, supported)
+            }
+        }
+    }
+}
+
+impl error::Error for NearbyError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self {
+            NearbyError::Io(e) | NearbyError::Network(e) => Some(e),
+            NearbyError::Database(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+// 未特别说明的 I/O 错误视为本地文件错误，网络读写需显式使用 NearbyError::Network
+impl From<io::Error> for NearbyError {
+    fn from(e: io::Error) -> Self {
+        NearbyError::Io(e)
+    }
+}
+
+impl From<rusqlite::Error> for NearbyError {
+    fn from(e: rusqlite::Error) -> Self {
+        NearbyError::Database(e)
+    }
+}
+
+impl From<snow::Error> for NearbyError {
+    fn from(e: snow::Error) -> Self {
+        NearbyError::Handshake(e.to_string())
+    }
+}
+
+impl From<std::string::FromUtf8Error> for NearbyError {
+    fn from(_: std::string::FromUtf8Error) -> Self {
+        NearbyError::Protocol("收到的文本不是有效的 UTF-8".to_string())
+    }
+}
+
+impl From<std::net::AddrParseError> for NearbyError {
+    fn from(_: std::net::AddrParseError) -> Self {
+        NearbyError::Validation("无效的 IPv6 地址".to_string())
+    }
+}
@@ -1,36 +1,212 @@
 // src/core/filereceiver.rs
-use std::error;
-use std::net::{Ipv6Addr, SocketAddr, SocketAddrV6};
-use std::path::PathBuf;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv6Addr, SocketAddr, SocketAddrV6};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::time::{Duration, Instant, UNIX_EPOCH};
 use tokio::fs::File;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::{TcpListener, TcpStream};
-use tokio::sync::{Notify, Semaphore};
+use tokio::sync::Notify;
 use std::sync::Mutex;
 use pnet::datalink;
 use log::{info, error, warn};
-
-// 定义可发送的错误类型
-type ReceiveError = Box<dyn error::Error + Send + Sync>;
+use crate::core::db::{AddressBook, ConnectionOutcome, Contact};
+use crate::core::error::{NearbyError, RejectReason};
+use crate::core::identity_key::{IdentityKey, RotationProof};
+use crate::core::peer_guard::PeerGuard;
+use crate::core::profile::{Profiles, DEFAULT_PROFILE};
+use crate::core::receive_code::ReceiveCode;
+use crate::core::session_ledger::{PaidBy, SessionLedger};
+use crate::core::settings::{ConflictPolicy, SenderFolder, Settings};
+use crate::core::protocol::{
+    self, with_timeout, ConflictResolution, HandshakeExtras, SIGNAL_BUSY, SIGNAL_COMPLETE, SIGNAL_NEED_HASH, SIGNAL_READY,
+    SIGNAL_REJECTED, SIGNAL_SHUTTING_DOWN,
+};
 
 pub struct FileReceiver;
 
 static SERVER_RUNNING: AtomicBool = AtomicBool::new(false);
 static STOP_NOTIFY: tokio::sync::OnceCell<Arc<Notify>> = tokio::sync::OnceCell::const_new();
-static ACTIVE_CONNECTIONS: tokio::sync::OnceCell<Arc<Semaphore>> = tokio::sync::OnceCell::const_new();
+
+// 并发连接限制（全局和每个对端）
+pub const DEFAULT_MAX_CONNECTIONS: usize = 10;
+pub const DEFAULT_MAX_CONNECTIONS_PER_PEER: usize = 5;
+static MAX_CONNECTIONS: AtomicUsize = AtomicUsize::new(DEFAULT_MAX_CONNECTIONS);
+static MAX_CONNECTIONS_PER_PEER: AtomicUsize = AtomicUsize::new(DEFAULT_MAX_CONNECTIONS_PER_PEER);
+// 每个来源的进行中连接数，与 PeerGuard 相同，IPv6 按 /64 前缀统计
+static PEER_CONNECTIONS: Mutex<Option<HashMap<IpAddr, usize>>> = Mutex::new(None);
+
+// 关闭时的排空状态
+pub const DEFAULT_DRAIN_GRACE_SECS: u64 = 30;
+static ACTIVE_TRANSFERS: AtomicUsize = AtomicUsize::new(0);
+static DRAINING: AtomicBool = AtomicBool::new(false);
+static CANCEL_TRANSFERS: AtomicBool = AtomicBool::new(false);
+static SKIP_GRACE: AtomicBool = AtomicBool::new(false);
+static DRAIN_GRACE_SECS: AtomicU64 = AtomicU64::new(DEFAULT_DRAIN_GRACE_SECS);
+
+// 进行中传输的计数守卫，任务结束（包括出错）时自动减一
+struct TransferGuard {
+    peer: IpAddr,
+}
+
+impl TransferGuard {
+    fn new(peer: IpAddr) -> Self {
+        ACTIVE_TRANSFERS.fetch_add(1, Ordering::SeqCst);
+        let peer = PeerGuard::peer_key(peer);
+        let mut peers = PEER_CONNECTIONS.lock().unwrap();
+        *peers.get_or_insert_with(HashMap::new).entry(peer).or_insert(0) += 1;
+        TransferGuard { peer }
+    }
+}
+
+impl Drop for TransferGuard {
+    fn drop(&mut self) {
+        ACTIVE_TRANSFERS.fetch_sub(1, Ordering::SeqCst);
+        let mut peers = PEER_CONNECTIONS.lock().unwrap();
+        if let Some(peers) = peers.as_mut() {
+            if let Some(count) = peers.get_mut(&self.peer) {
+                *count -= 1;
+                if *count == 0 {
+                    peers.remove(&self.peer);
+                }
+            }
+        }
+    }
+}
+
+// 接收服务器的运行标志，只有成功设置标志的启动才持有它，服务器退出（包括启动失败）时自动清除；
+// 已有服务器在运行或正在启动时无法获取，避免重复启动的失败清除了仍在运行的服务器的标志
+struct ServerRunning;
+
+impl ServerRunning {
+    fn acquire() -> Option<Self> {
+        SERVER_RUNNING
+            .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+            .ok()
+            .map(|_| ServerRunning)
+    }
+}
+
+impl Drop for ServerRunning {
+    fn drop(&mut self) {
+        SERVER_RUNNING.store(false, Ordering::SeqCst);
+    }
+}
+
+// 单个入站连接的审计信息，连接结束时写入数据库
+struct ConnectionAudit {
+    source_address: String,
+    claimed_identity: String,
+    authenticated_identity: String,
+    outcome: Option<ConnectionOutcome>,
+    bytes: u64,
+}
+
+impl ConnectionAudit {
+    fn new(peer_addr: SocketAddr) -> Self {
+        ConnectionAudit {
+            source_address: peer_addr.ip().to_string(),
+            claimed_identity: String::new(),
+            authenticated_identity: String::new(),
+            outcome: None,
+            bytes: 0,
+        }
+    }
+    
+    // 根据处理结果确定最终结果并记录
+    fn finish(self, result: &Result<(), NearbyError>) {
+        let outcome = match (self.outcome, result) {
+            (Some(outcome), _) => outcome,
+            (None, Err(e)) if e.is_timeout() => ConnectionOutcome::Timeout,
+            (None, _) => ConnectionOutcome::Closed,
+        };
+        self.record(outcome);
+    }
+    
+    // 在后台线程写入数据库，不阻塞接受循环
+    fn record(self, outcome: ConnectionOutcome) {
+        tokio::spawn(async move {
+            let result = AddressBook::call(move || AddressBook::add_connection_event(
+                &self.source_address,
+                &self.claimed_identity,
+                &self.authenticated_identity,
+                outcome,
+                self.bytes,
+            )).await;
+            if let Err(e) = result {
+                error!("记录入站连接失败: {}", e);
+            }
+        });
+    }
+}
 
 // 添加接收状态管理
 #[derive(Debug, Clone, PartialEq)]
 pub enum ReceiveStatus {
     Closed,    // 关闭状态 - 不接收任何文件
     Open,      // 开启状态 - 接收所有文件
-    Collect    // 收藏状态 - 只接收白名单中的文件
+    Collect,   // 收藏状态 - 只接收白名单中的文件
+    Code       // 口令状态 - 只接收知道一次性口令的发送方
+}
+
+impl ReceiveStatus {
+    pub const ALL: [ReceiveStatus; 4] = [
+        ReceiveStatus::Closed,
+        ReceiveStatus::Open,
+        ReceiveStatus::Collect,
+        ReceiveStatus::Code,
+    ];
+    
+    /// 数据库中保存的值
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReceiveStatus::Closed => "closed",
+            ReceiveStatus::Open => "open",
+            ReceiveStatus::Collect => "collect",
+            ReceiveStatus::Code => "code",
+        }
+    }
+    
+    /// 从数据库中的值解析，未知的值视为 Closed
+    pub fn from_db_value(value: &str) -> Self {
+        Self::ALL
+            .into_iter()
+            .find(|status| status.as_str() == value)
+            .unwrap_or(ReceiveStatus::Closed)
+    }
+    
+    /// 界面上显示的名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            ReceiveStatus::Closed => "关闭",
+            ReceiveStatus::Open => "开启",
+            ReceiveStatus::Collect => "收藏（仅白名单）",
+            ReceiveStatus::Code => "口令（一次性）",
+        }
+    }
 }
 
 static RECEIVE_STATUS: Mutex<ReceiveStatus> = Mutex::new(ReceiveStatus::Closed);
 
+// 限时开启：到期后恢复到开启前的模式
+pub const TIMED_OPEN_MINUTES: [u64; 3] = [5, 15, 60];
+
+struct OpenTimer {
+    id: u64,                  // 计时编号，用于识别已被取消或替换的计时任务
+    expires_at: Instant,
+    previous: ReceiveStatus, // 到期后恢复的模式
+}
+
+static OPEN_TIMER: Mutex<Option<OpenTimer>> = Mutex::new(None);
+
+// 频率限制拒绝的连接按地址抽样记录：同一地址在间隔内只记录一次，记录的地址数有上限
+const RATE_LIMIT_LOG_INTERVAL: Duration = Duration::from_secs(60);
+const MAX_RATE_LIMIT_LOGGED: usize = 256;
+static RATE_LIMIT_LOGGED: Mutex<Option<HashMap<IpAddr, Instant>>> = Mutex::new(None);
+static NEXT_TIMER_ID: AtomicU64 = AtomicU64::new(0);
+
 impl FileReceiver {
     // 返回ipv6地址
     pub fn get_ipv6_addr() -> Vec<Ipv6Addr> {
@@ -54,9 +230,22 @@ impl FileReceiver {
             .collect()
     }
     
-    // 设置接收状态
-    pub fn set_receive_status(status: ReceiveStatus) -> Result<(), ReceiveError> {
+    // 设置接收状态，手动切换模式会取消限时开启；身份私钥锁定时无法完成握手，不允许开启
+    pub async fn set_receive_status(status: ReceiveStatus) -> Result<(), NearbyError> {
+        // 读取私钥状态需要访问数据库，放到阻塞线程中执行，不阻塞界面
+        if status != ReceiveStatus::Closed && AddressBook::call(AddressBook::identity_key_locked).await? {
+            return Err(NearbyError::validation("身份密钥已锁定，解锁后才能开启接收"));
+        }
+        if OPEN_TIMER.lock().unwrap().take().is_some() {
+            info!("已取消限时开启");
+        }
         let mut current_status = RECEIVE_STATUS.lock().unwrap();
+        // 离开口令模式时口令立即失效，进入时如果没有有效口令则生成一个
+        if status != ReceiveStatus::Code {
+            ReceiveCode::revoke();
+        } else if ReceiveCode::current().is_none() {
+            ReceiveCode::generate();
+        }
         *current_status = status;
         
         match &*current_status {
@@ -71,7 +260,7 @@ impl FileReceiver {
                     });
                 }
             }
-            ReceiveStatus::Open | ReceiveStatus::Collect => {
+            ReceiveStatus::Open | ReceiveStatus::Collect | ReceiveStatus::Code => {
                 info!("接收功能已开启 - 接收所有文件");
                 // 如果服务器未运行，启动它
                 if !SERVER_RUNNING.load(Ordering::SeqCst) {
@@ -87,39 +276,268 @@ impl FileReceiver {
         Ok(())
     }
     
+    // 按设置进入启动时的接收模式，身份私钥锁定时应在解锁后调用
+    pub async fn apply_startup_mode() -> Result<(), NearbyError> {
+        let mode = Settings::current().startup_receive_mode;
+        if mode == ReceiveStatus::Closed {
+            return Ok(());
+        }
+        Self::set_receive_status(mode).await
+    }
+    
     // 获取当前接收状态
     pub fn get_receive_status() -> ReceiveStatus {
         let status = RECEIVE_STATUS.lock().unwrap();
         status.clone()
     }
     
-    // 检查身份是否在白名单中
+    // 在一段时间内切换到开启模式，到期后恢复到之前的模式；
+    // 已在限时开启时重新计时，恢复的模式不变
+    pub async fn open_for(duration: Duration) -> Result<(), NearbyError> {
+        let previous = match OPEN_TIMER.lock().unwrap().as_ref() {
+            Some(timer) => timer.previous.clone(),
+            // 原本就是开启模式时，到期后关闭
+            None => match Self::get_receive_status() {
+                ReceiveStatus::Open => ReceiveStatus::Closed,
+                status => status,
+            },
+        };
+        Self::set_receive_status(ReceiveStatus::Open).await?;
+        
+        let id = NEXT_TIMER_ID.fetch_add(1, Ordering::SeqCst);
+        *OPEN_TIMER.lock().unwrap() = Some(OpenTimer {
+            id,
+            expires_at: Instant::now() + duration,
+            previous: previous.clone(),
+        });
+        info!("限时开启 {} 分钟，到期后恢复为{}", duration.as_secs() / 60, previous.label());
+        
+        tokio::spawn(async move {
+            tokio::time::sleep(duration).await;
+            // 计时期间被取消或重新计时时不做处理
+            let expired = {
+                let mut timer = OPEN_TIMER.lock().unwrap();
+                match timer.as_ref() {
+                    Some(current) if current.id == id => timer.take(),
+                    _ => None,
+                }
+            };
+            if let Some(timer) = expired {
+                info!("限时开启已到期，恢复为{}", timer.previous.label());
+                if let Err(e) = Self::set_receive_status(timer.previous).await {
+                    error!("限时开启到期后恢复接收模式失败: {}", e);
+                }
+            }
+        });
+        Ok(())
+    }
+    
+    // 限时开启的剩余时间和到期后恢复的模式，未在限时开启时返回 None
+    pub fn open_timer() -> Option<(Duration, ReceiveStatus)> {
+        OPEN_TIMER.lock().unwrap().as_ref().map(|timer| {
+            (timer.expires_at.saturating_duration_since(Instant::now()), timer.previous.clone())
+        })
+    }
+    
+    // 当前仍在进行的传输数量
+    pub fn active_transfer_count() -> usize {
+        ACTIVE_TRANSFERS.load(Ordering::SeqCst)
+    }
+    
+    // 是否正在等待进行中的传输结束
+    pub fn is_draining() -> bool {
+        DRAINING.load(Ordering::SeqCst)
+    }
+    
+    // 不再等待宽限期，立即取消进行中的传输（退出时用户选择不再等待）
+    pub fn cancel_drain() {
+        SKIP_GRACE.store(true, Ordering::SeqCst);
+    }
+    
+    // 设置关闭时等待传输结束的宽限期（秒）
+    pub fn set_drain_grace_period(secs: u64) {
+        DRAIN_GRACE_SECS.store(secs, Ordering::SeqCst);
+    }
+    
+    // 获取关闭时等待传输结束的宽限期（秒）
+    pub fn get_drain_grace_period() -> u64 {
+        DRAIN_GRACE_SECS.load(Ordering::SeqCst)
+    }
+    
+    // 设置最大并发连接数（全局）
+    pub fn set_max_connections(limit: usize) {
+        MAX_CONNECTIONS.store(limit.max(1), Ordering::SeqCst);
+    }
+    
+    // 获取最大并发连接数（全局）
+    pub fn get_max_connections() -> usize {
+        MAX_CONNECTIONS.load(Ordering::SeqCst)
+    }
+    
+    // 设置每个对端地址的最大并发连接数
+    pub fn set_max_connections_per_peer(limit: usize) {
+        MAX_CONNECTIONS_PER_PEER.store(limit.max(1), Ordering::SeqCst);
+    }
+    
+    // 获取每个对端地址的最大并发连接数
+    pub fn get_max_connections_per_peer() -> usize {
+        MAX_CONNECTIONS_PER_PEER.load(Ordering::SeqCst)
+    }
+    
+    // 频率限制拒绝的连接每个地址在一段时间内只记录一次，避免大量连接写满审计表
+    fn should_log_rate_limited(peer: IpAddr) -> bool {
+        let peer = PeerGuard::peer_key(peer);
+        let mut logged = RATE_LIMIT_LOGGED.lock().unwrap();
+        let logged = logged.get_or_insert_with(HashMap::new);
+        let now = Instant::now();
+        logged.retain(|_, at| now.duration_since(*at) < RATE_LIMIT_LOG_INTERVAL);
+        if logged.contains_key(&peer) || logged.len() >= MAX_RATE_LIMIT_LOGGED {
+            return false;
+        }
+        logged.insert(peer, now);
+        true
+    }
+    
+    // 检查是否还能接受来自该地址的连接，超出限制时返回原因
+    fn check_connection_limits(peer: IpAddr) -> Option<&'static str> {
+        if Self::active_transfer_count() >= Self::get_max_connections() {
+            return Some("并发连接数已满");
+        }
+        let peers = PEER_CONNECTIONS.lock().unwrap();
+        let peer_count = peers
+            .as_ref()
+            .and_then(|peers| peers.get(&PeerGuard::peer_key(peer)).copied())
+            .unwrap_or(0);
+        if peer_count >= Self::get_max_connections_per_peer() {
+            return Some("该地址的并发连接数已满");
+        }
+        None
+    }
+    
+    // 检查身份是否属于白名单中仍然有效的联系人，次数在传输成功后再扣除
     async fn check_identity_in_whitelist(identity: &str) -> bool {
-        // 使用 db.rs 的 search_identities 验证身份码是否存在
-        match crate::core::db::AddressBook::search_identities(identity).ok() {
-            Some(identities) => {
-                // 如果找到匹配的身份码，返回 true
-                !identities.is_empty()
+        let identity = identity.to_string();
+        match AddressBook::call(move || AddressBook::is_trusted(&identity)).await {
+            Ok(trusted) => trusted,
+            Err(e) => {
+                error!("查询白名单失败: {}", e);
+                false
+            }
+        }
+    }
+    
+    // 查找发送方在握手中附带的邀请令牌，令牌在传输成功后再使用
+    async fn find_invitation(token: &str) -> Option<i64> {
+        if token.is_empty() {
+            return None;
+        }
+        let token = token.to_string();
+        match AddressBook::call(move || AddressBook::find_invitation(&token)).await {
+            Ok(id) => id,
+            Err(e) => {
+                error!("查询邀请令牌失败: {}", e);
+                None
+            }
+        }
+    }
+    
+    // 文件传输成功后扣除允许本次连接的白名单次数或邀请令牌，同一会话只扣除一次
+    async fn settle_grant(grant: Option<PaidBy>, identity: &str, session: &str) {
+        let Some(grant) = grant else { return };
+        if !SessionLedger::mark_paid(identity, session, grant.clone()) {
+            return;
+        }
+        let identity = identity.to_string();
+        let result = AddressBook::call(move || match grant {
+            PaidBy::Trust(known_identity) => AddressBook::consume_trust(&known_identity),
+            PaidBy::Invitation(id) => AddressBook::redeem_invitation(id, &identity),
+        }).await;
+        match result {
+            Ok(true) => {}
+            Ok(false) => warn!("白名单次数或邀请令牌已被其他发送用完"),
+            Err(e) => error!("扣除白名单次数或邀请令牌失败: {}", e),
+        }
+    }
+    
+    // 会话扣除时依据的白名单或邀请令牌是否仍然有效（不看剩余次数），
+    // 已被移除、撤销或到期时清除由它扣除的会话
+    async fn is_grant_active(source: PaidBy) -> bool {
+        let lookup = source.clone();
+        let result = AddressBook::call(move || match &lookup {
+            PaidBy::Trust(identity) => AddressBook::is_trust_active(identity),
+            PaidBy::Invitation(id) => AddressBook::is_invitation_active(*id),
+        }).await;
+        let active = result.unwrap_or_else(|e| {
+            error!("查询白名单或邀请令牌失败: {}", e);
+            false
+        });
+        if !active {
+            match source {
+                PaidBy::Trust(identity) => SessionLedger::forget_identity(&identity),
+                PaidBy::Invitation(id) => SessionLedger::forget_invitation(id),
             }
-            None => false
         }
+        active
     }
     
-    // 执行 Noise 协议握手（作为响应者）
-    async fn perform_noise_handshake(stream: &mut TcpStream) -> Result<snow::TransportState, ReceiveError> {
+    // 查询屏蔽列表，查询失败时按已屏蔽处理，与白名单检查一致
+    async fn is_blocked<F>(query: F) -> bool
+    where
+        F: FnOnce() -> Result<bool, NearbyError> + Send + 'static,
+    {
+        match AddressBook::call(query).await {
+            Ok(blocked) => blocked,
+            Err(e) => {
+                error!("查询屏蔽列表失败: {}", e);
+                true
+            }
+        }
+    }
+    
+    // 对方以新身份码连接并出示了轮换证明时，返回仍为旧身份码的联系人
+    async fn resolve_identity_rotation(identity: &str, proofs: Vec<RotationProof>) -> Option<Contact> {
+        if proofs.is_empty() {
+            return None;
+        }
+        let identity = identity.to_string();
+        match AddressBook::call(move || AddressBook::resolve_identity_rotation(&identity, &proofs)).await {
+            Ok(contact) => contact,
+            Err(e) => {
+                error!("查询身份码变更失败: {}", e);
+                None
+            }
+        }
+    }
+    
+    // 记录对方声称由早期身份码更换而来的变更，等待用户核对
+    async fn record_unproven_rotation(legacy_identity: String, identity: String) {
+        if let Err(e) = AddressBook::call(move || AddressBook::record_unproven_rotation(&legacy_identity, &identity)).await {
+            error!("记录身份码变更失败: {}", e);
+        }
+    }
+    
+    // 执行 Noise 协议握手（作为响应者），静态密钥由身份私钥转换而来，提供预共享密钥时使用 XXpsk3 模式；
+    // 同时返回发送方在第三条消息中附带的邀请令牌和身份码轮换证明
+    async fn perform_noise_handshake(
+        stream: &mut TcpStream,
+        static_key: &[u8; 32],
+        psk: Option<[u8; 32]>,
+    ) -> Result<(snow::TransportState, HandshakeExtras), NearbyError> {
         info!("开始 Noise 协议握手...");
         
         // 创建响应者 - 使用正确的 API
-        let builder = snow::Builder::new("Noise_XX_25519_ChaChaPoly_BLAKE2s".parse()?);
-        let static_key = builder.generate_keypair()?.private;
-        let mut noise = builder
-            .local_private_key(&static_key)
-            .build_responder()?;
+        let pattern = if psk.is_some() { protocol::NOISE_PATTERN_PSK } else { protocol::NOISE_PATTERN };
+        let builder = snow::Builder::new(pattern.parse()?);
+        let mut builder = builder.local_private_key(static_key);
+        if let Some(psk) = psk.as_ref() {
+            builder = builder.psk(3, psk);
+        }
+        let mut noise = builder.build_responder()?;
         
         // 接收第一条消息
-        let len = stream.read_u16().await? as usize;
+        let len = stream.read_u16().await.map_err(NearbyError::Network)? as usize;
         let mut msg = vec![0u8; len];
-        stream.read_exact(&mut msg).await?;
+        stream.read_exact(&mut msg).await.map_err(NearbyError::Network)?;
         
         // 读取并消费第一条消息
         let mut handshake_buffer1 = vec![0u8; 65535];
@@ -128,29 +546,30 @@ impl FileReceiver {
         // 发送响应消息
         let mut handshake_buffer2 = vec![0u8; 65535];
         let len = noise.write_message(&[], &mut handshake_buffer2)?;
-        stream.write_u16(len as u16).await?;
-        stream.write_all(&handshake_buffer2[..len]).await?;
+        stream.write_u16(len as u16).await.map_err(NearbyError::Network)?;
+        stream.write_all(&handshake_buffer2[..len]).await.map_err(NearbyError::Network)?;
         
         // 接收第三条消息
-        let len = stream.read_u16().await? as usize;
+        let len = stream.read_u16().await.map_err(NearbyError::Network)? as usize;
         let mut msg = vec![0u8; len];
-        stream.read_exact(&mut msg).await?;
+        stream.read_exact(&mut msg).await.map_err(NearbyError::Network)?;
         
         // 读取第三条消息完成握手
         let mut handshake_buffer3 = vec![0u8; 65535];
-        let _ = noise.read_message(&msg, &mut handshake_buffer3)?;
+        let payload_len = noise.read_message(&msg, &mut handshake_buffer3)?;
+        let extras = HandshakeExtras::decode(&handshake_buffer3[..payload_len]);
         
         // 转换为传输模式
         let transport = noise.into_transport_mode()?;
         
         info!("Noise 协议握手完成");
-        Ok(transport)
+        Ok((transport, extras))
     }
     
     // 修改 read_encrypted 函数：
-    async fn read_encrypted(transport: &mut snow::TransportState, stream: &mut TcpStream, buffer: &mut [u8]) -> Result<usize, ReceiveError> {
+    async fn read_encrypted(transport: &mut snow::TransportState, stream: &mut TcpStream, buffer: &mut [u8]) -> Result<usize, NearbyError> {
         // 先读取加密数据的长度
-        let encrypted_len = stream.read_u16().await? as usize;
+        let encrypted_len = stream.read_u16().await.map_err(NearbyError::Network)? as usize;
         if encrypted_len == 0 {
             // 长度为0表示传输结束
             return Ok(0);
@@ -159,50 +578,57 @@ impl FileReceiver {
         // 检查缓冲区是否足够大
         if encrypted_len > 65535 {
             error!("错误的加密数据长度: {}", encrypted_len);
-            return Err("无效的加密数据长度".into());
+            return Err(NearbyError::protocol("无效的加密数据长度"));
         }
         
         let mut encrypted_data = vec![0u8; encrypted_len];
-        stream.read_exact(&mut encrypted_data).await?;
+        stream.read_exact(&mut encrypted_data).await.map_err(NearbyError::Network)?;
         
         // 解密数据
         match transport.read_message(&encrypted_data, buffer) {
             Ok(len) => Ok(len),
             Err(e) => {
                 error!("解密数据失败: {}", e);
-                Err(e.into())
+                Err(NearbyError::protocol(format!("解密数据失败: {}", e)))
             }
         }
     }
     
     // 使用加密通道写入数据
     #[allow(dead_code)]
-    async fn write_encrypted(transport: &mut snow::TransportState, stream: &mut TcpStream, data: &[u8]) -> Result<(), ReceiveError> {
+    async fn write_encrypted(transport: &mut snow::TransportState, stream: &mut TcpStream, data: &[u8]) -> Result<(), NearbyError> {
         let mut buffer = vec![0u8; 65535];
         
         // 加密数据
-        let len = transport.write_message(data, &mut buffer)?;
+        let len = transport
+            .write_message(data, &mut buffer)
+            .map_err(|e| NearbyError::protocol(format!("加密数据失败: {}", e)))?;
         if len == 0 || len > 65535 {
             error!("错误的加密数据长度");
-            return Err("无效的加密数据长度".into());
+            return Err(NearbyError::protocol("无效的加密数据长度"));
         }
         
         // 发送加密数据的长度和数据
-        stream.write_u16(len as u16).await?;
-        stream.write_all(&buffer[..len]).await?;
+        stream.write_u16(len as u16).await.map_err(NearbyError::Network)?;
+        stream.write_all(&buffer[..len]).await.map_err(NearbyError::Network)?;
         
         Ok(())
     }
     
     // 发送传输结束信号
-    async fn send_transfer_complete(stream: &mut TcpStream) -> Result<(), ReceiveError> {
+    async fn send_transfer_complete(stream: &mut TcpStream) -> Result<(), NearbyError> {
         // 发送长度为0的数据包表示传输结束
-        stream.write_u16(0).await?;
-        stream.flush().await?;
+        Self::send_signal(stream, SIGNAL_COMPLETE).await
+    }
+    
+    // 向发送方发送状态信号
+    async fn send_signal(stream: &mut TcpStream, signal: u16) -> Result<(), NearbyError> {
+        stream.write_u16(signal).await.map_err(NearbyError::Network)?;
+        stream.flush().await.map_err(NearbyError::Network)?;
         Ok(())
     }
     
-    pub async fn start_server() -> Result<(), ReceiveError> {
+    pub async fn start_server() -> Result<(), NearbyError> {
         // 检查当前状态，如果是关闭状态则不启动服务器
         let status = Self::get_receive_status();
         if status == ReceiveStatus::Closed {
@@ -210,22 +636,31 @@ impl FileReceiver {
             return Ok(());
         }
         
-        // 设置服务器运行标志
-        SERVER_RUNNING.store(true, Ordering::SeqCst);
-        
-        // 初始化信号量，限制最大并发连接数
-        let _ = ACTIVE_CONNECTIONS.set(Arc::new(Semaphore::new(10))); // 最多10个并发连接
+        // 设置服务器运行标志，已有服务器在运行时不重复启动
+        let Some(running) = ServerRunning::acquire() else {
+            info!("接收服务器已在运行");
+            return Ok(());
+        };
         
-        // 创建停止通知
-        let stop_notify = Arc::new(Notify::new());
-        let _ = STOP_NOTIFY.set(stop_notify.clone());
+        // 握手使用身份私钥转换的静态密钥，发送方据此确认连接的是身份码的持有者
+        let static_key = AddressBook::call(AddressBook::my_noise_key).await?;
+        Self::serve(running, static_key, Settings::current().port).await
+    }
+    
+    // 在指定端口接受连接，直到收到停止通知；返回时释放端口并清除运行标志
+    async fn serve(_running: ServerRunning, static_key: [u8; 32], port: u16) -> Result<(), NearbyError> {
+        // 停止通知在多次启动之间共用，stop_server 总能通知到当前的服务器
+        let stop_notify = STOP_NOTIFY
+            .get_or_init(|| async { Arc::new(Notify::new()) })
+            .await
+            .clone();
         
-        // 绑定到所有IPv6地址的6789端口
-        let addr = SocketAddrV6::new("::".parse()?, 6789, 0, 0);
-        let listener = TcpListener::bind(addr).await?;
+        // 绑定到所有IPv6地址的指定端口
+        let addr = SocketAddrV6::new(Ipv6Addr::UNSPECIFIED, port, 0, 0);
+        let listener = TcpListener::bind(addr).await.map_err(NearbyError::Network)?;
         
         info!("文件接收服务器启动，监听在: {}", addr);
-        info!("当前接收模式: {:?}", status);
+        info!("当前接收模式: {:?}", Self::get_receive_status());
         info!("使用 Noise 协议加密传输");
         info!("等待连接... (按停止按钮可关闭服务器)");
         
@@ -234,27 +669,59 @@ impl FileReceiver {
             tokio::select! {
                 accept_result = listener.accept() => {
                     match accept_result {
-                        Ok((stream, peer_addr)) => {
+                        Ok((mut stream, peer_addr)) => {
                             info!("接收到来自 {} 的连接", peer_addr);
                             
+                            let audit = ConnectionAudit::new(peer_addr);
+                            
                             // 在处理连接前再次检查状态
                             let current_status = Self::get_receive_status();
                             if current_status == ReceiveStatus::Closed {
                                 warn!("接收功能已关闭，拒绝连接");
+                                audit.record(ConnectionOutcome::RejectedClosed);
                                 continue;
                             }
-
-                            // 获取连接许可，如果达到最大连接数会等待
-                            let connections = ACTIVE_CONNECTIONS.get().unwrap().clone();
-                            let permit = connections.acquire_owned().await;
-
+                            
+                            // 屏蔽列表在所有接收模式下都生效，被屏蔽的地址不计入频率限制，也不占用连接数
+                            if AddressBook::is_address_blocked(peer_addr.ip()) {
+                                warn!("地址 {} 在屏蔽列表中，拒绝连接", peer_addr.ip());
+                                audit.record(ConnectionOutcome::RejectedBlocked);
+                                continue;
+                            }
+                            
+                            // 来源地址被封禁或连接过于频繁时直接断开
+                            if let Err(reason) = PeerGuard::check_attempt(peer_addr.ip()) {
+                                warn!("{}，拒绝来自 {} 的连接", reason, peer_addr);
+                                if Self::should_log_rate_limited(peer_addr.ip()) {
+                                    audit.record(ConnectionOutcome::RejectedRateLimited);
+                                }
+                                continue;
+                            }
+                            
+                            // 连接数已满时立即回复繁忙，不阻塞接受循环
+                            if let Some(reason) = Self::check_connection_limits(peer_addr.ip()) {
+                                warn!("{}，通知 {} 稍后重试", reason, peer_addr);
+                                audit.record(ConnectionOutcome::RejectedBusy);
+                                tokio::spawn(async move {
+                                    let _ = Self::send_signal(&mut stream, SIGNAL_BUSY).await;
+                                });
+                                continue;
+                            }
+                            
                             // 为每个连接生成一个异步任务
+                            let transfer_guard = TransferGuard::new(peer_addr.ip());
                             tokio::spawn(async move {
-                                if let Err(e) = Self::handle_client(stream, current_status, peer_addr).await {
-                                    error!("处理客户端时出错: {}", e);
+                                let _transfer_guard = transfer_guard;
+                                let mut audit = audit;
+                                let result = Self::handle_client(stream, current_status, &static_key, peer_addr, &mut audit).await;
+                                if let Err(e) = &result {
+                                    if e.is_timeout() {
+                                        warn!("来自 {} 的连接超时，已断开: {}", peer_addr, e);
+                                    } else {
+                                        error!("处理客户端时出错: {}", e);
+                                    }
                                 }
-                                // permit 在这里被 drop，释放连接计数
-                                drop(permit);
+                                audit.finish(&result);
                             });
                         }
                         Err(e) => {
@@ -269,48 +736,205 @@ impl FileReceiver {
             }
         }
         
-        // 重置运行标志
-        SERVER_RUNNING.store(false, Ordering::SeqCst);
         info!("接收服务器已安全关闭");
         Ok(())
     }
     
-    pub async fn stop_server() -> Result<(), ReceiveError> {
+    pub async fn stop_server() -> Result<(), NearbyError> {
+        DRAINING.store(true, Ordering::SeqCst);
         if SERVER_RUNNING.load(Ordering::SeqCst) {
             if let Some(notify) = STOP_NOTIFY.get() {
                 notify.notify_one();
-                // 等待一小段时间让服务器完全关闭
-                tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+                // 等待服务器退出并释放端口，重新启动时才能绑定同一端口
+                let deadline = Instant::now() + Duration::from_secs(2);
+                while SERVER_RUNNING.load(Ordering::SeqCst) && Instant::now() < deadline {
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                }
             }
         }
+        
+        // 不再接受新连接后，等待进行中的传输结束
+        Self::drain_transfers().await;
+        DRAINING.store(false, Ordering::SeqCst);
+        Ok(())
+    }
+    
+    // 端口等设置变化后，重新启动正在运行的接收服务器
+    pub async fn restart_server() -> Result<(), NearbyError> {
+        if !SERVER_RUNNING.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+        info!("重新启动接收服务器...");
+        Self::stop_server().await?;
+        tokio::spawn(async {
+            if let Err(e) = Self::start_server().await {
+                error!("启动服务器时出错: {}", e);
+            }
+        });
         Ok(())
     }
     
+    // 关闭接收功能并等待进行中的传输结束（用于退出程序或切换档案前）
+    pub async fn shutdown() {
+        OPEN_TIMER.lock().unwrap().take();
+        ReceiveCode::revoke();
+        *RECEIVE_STATUS.lock().unwrap() = ReceiveStatus::Closed;
+        if let Err(e) = Self::stop_server().await {
+            error!("停止服务器时出错: {}", e);
+        }
+    }
+    
+    // 在宽限期内等待进行中的传输完成，超时后通知它们取消
+    async fn drain_transfers() {
+        let active = Self::active_transfer_count();
+        if active == 0 {
+            SKIP_GRACE.store(false, Ordering::SeqCst);
+            return;
+        }
+        
+        let grace = Duration::from_secs(Self::get_drain_grace_period());
+        info!("等待 {} 个进行中的传输完成 (最长 {} 秒)", active, grace.as_secs());
+        
+        let deadline = Instant::now() + grace;
+        while Self::active_transfer_count() > 0 {
+            // 服务器已被重新开启，不再继续排空
+            if SERVER_RUNNING.load(Ordering::SeqCst) {
+                info!("接收服务器已重新开启，停止等待");
+                return;
+            }
+            if SKIP_GRACE.load(Ordering::SeqCst) {
+                warn!("不再等待，取消剩余的 {} 个传输", Self::active_transfer_count());
+                CANCEL_TRANSFERS.store(true, Ordering::SeqCst);
+                break;
+            }
+            if Instant::now() >= deadline {
+                warn!("宽限期已到，取消剩余的 {} 个传输", Self::active_transfer_count());
+                CANCEL_TRANSFERS.store(true, Ordering::SeqCst);
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        
+        // 给被取消的传输留出清理时间
+        let cleanup_deadline = Instant::now() + Duration::from_secs(5);
+        while Self::active_transfer_count() > 0 && Instant::now() < cleanup_deadline {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        CANCEL_TRANSFERS.store(false, Ordering::SeqCst);
+        SKIP_GRACE.store(false, Ordering::SeqCst);
+        
+        info!("进行中的传输已全部结束");
+    }
+    
     async fn handle_client(
         mut stream: TcpStream,
         current_status: ReceiveStatus,
-        peer_addr: SocketAddr
-    ) -> Result<(), ReceiveError> {
+        static_key: &[u8; 32],
+        peer_addr: SocketAddr,
+        audit: &mut ConnectionAudit,
+    ) -> Result<(), NearbyError> {
+        let timeouts = protocol::get_timeouts();
+        
+        // 口令模式下握手需要口令导出的预共享密钥，口令已过期时直接拒绝
+        let psk = if current_status == ReceiveStatus::Code {
+            match ReceiveCode::active_psk() {
+                Some(psk) => Some(psk),
+                None => {
+                    warn!("接收口令已失效，拒绝连接");
+                    audit.outcome = Some(ConnectionOutcome::RejectedCode);
+                    Self::send_signal(&mut stream, SIGNAL_REJECTED).await?;
+                    return Ok(());
+                }
+            }
+        } else {
+            None
+        };
+        
+        // 告知发送方连接已被接受
+        Self::send_signal(&mut stream, SIGNAL_READY).await?;
+        
         // 首先进行 Noise 协议握手
-        let mut transport = Self::perform_noise_handshake(&mut stream).await?;
+        let handshake = with_timeout(
+            timeouts.handshake,
+            "握手",
+            Self::perform_noise_handshake(&mut stream, static_key, psk),
+        ).await;
+        let (mut transport, extras) = match handshake {
+            Ok(result) => result,
+            Err(e) => {
+                PeerGuard::record_failure(peer_addr.ip());
+                if !e.is_timeout() {
+                    audit.outcome = Some(ConnectionOutcome::HandshakeFailed);
+                }
+                return Err(e);
+            }
+        };
+        
+        // 握手得到的对方静态公钥，对方证明了持有对应的私钥
+        let remote_static = transport.get_remote_static().map(|key| key.to_vec());
+        if let Some(remote_static) = &remote_static {
+            audit.authenticated_identity = remote_static.iter().map(|b| format!("{:02x}", b)).collect();
+        }
         
         // 接收身份标识（64字符固定长度）
         let mut identity_bytes = vec![0u8; 64];
-        let identity_len = Self::read_encrypted(&mut transport, &mut stream, &mut identity_bytes).await?;
+        let identity_len = with_timeout(
+            timeouts.metadata,
+            "接收身份标识",
+            Self::read_encrypted(&mut transport, &mut stream, &mut identity_bytes),
+        ).await?;
         if identity_len == 0 {
             info!("接收到传输结束信号，连接正常关闭");
             return Ok(());
         }
         let identity = String::from_utf8(identity_bytes)?;
+        audit.claimed_identity = identity.clone();
         
         info!("接收到身份标识: {}", identity);
         
+        // 身份码必须与握手中的静态公钥对应，否则对方只是冒用了别人的身份码
+        if !IdentityKey::matches_noise_key(&identity, remote_static.as_deref()) {
+            warn!("身份 {} 与握手密钥不符，拒绝连接", identity);
+            PeerGuard::record_failure(peer_addr.ip());
+            audit.outcome = Some(ConnectionOutcome::RejectedIdentityMismatch);
+            Self::send_signal(&mut stream, SIGNAL_REJECTED).await?;
+            return Ok(());
+        }
+        audit.authenticated_identity = identity.clone();
+        
+        // 对方更换了身份码时，白名单和屏蔽列表按通讯录中的旧身份码判断
+        let rotated_from = Self::resolve_identity_rotation(&identity, extras.rotation).await;
+        let known_identity = rotated_from.as_ref().map(|contact| contact.identity.clone()).unwrap_or_else(|| identity.clone());
+        // 早期身份码没有私钥，无法证明更换，只记录下来由用户核对，不据此放行
+        if rotated_from.is_none() && !extras.legacy_identity.is_empty() {
+            Self::record_unproven_rotation(extras.legacy_identity, identity.clone()).await;
+        }
+        
+        let lookup = [identity.clone(), known_identity.clone()];
+        let blocked = Self::is_blocked(move || {
+            for identity in &lookup {
+                if AddressBook::is_identity_blocked(identity)? {
+                    return Ok(true);
+                }
+            }
+            Ok(false)
+        }).await;
+        if blocked {
+            warn!("身份 {} 在屏蔽列表中，拒绝接收文件", identity);
+            audit.outcome = Some(ConnectionOutcome::RejectedBlocked);
+            Self::send_signal(&mut stream, SIGNAL_REJECTED).await?;
+            return Ok(());
+        }
+        
         // 根据当前状态决定是否接收文件
+        let mut grant = None;
+        let mut code_claim = None;
         match current_status {
             ReceiveStatus::Closed => {
                 info!("接收功能已关闭，拒绝接收文件");
+                audit.outcome = Some(ConnectionOutcome::RejectedClosed);
                 // 发送拒绝信号
-                Self::send_transfer_complete(&mut stream).await?;
+                Self::send_signal(&mut stream, SIGNAL_REJECTED).await?;
                 return Ok(());
             }
             ReceiveStatus::Open => {
@@ -318,20 +942,65 @@ impl FileReceiver {
                 // 继续处理文件接收
             }
             ReceiveStatus::Collect => {
-                // 检查身份是否在白名单中
-                if !Self::check_identity_in_whitelist(&identity).await {
+                // 同一次发送已经扣除过、且扣除依据仍然有效时直接允许，否则检查白名单，不在时再检查邀请令牌
+                let paid = match SessionLedger::paid_by(&identity, &extras.session) {
+                    Some(source) => Self::is_grant_active(source).await,
+                    None => false,
+                };
+                if paid {
+                    info!("身份 {} 的本次发送已获允许，继续接收文件", identity);
+                } else if Self::check_identity_in_whitelist(&known_identity).await {
+                    info!("身份 {} 在白名单中，允许接收文件", identity);
+                    grant = Some(PaidBy::Trust(known_identity.clone()));
+                } else if let Some(id) = Self::find_invitation(&extras.invitation).await {
+                    info!("身份 {} 持有有效的邀请令牌，允许接收文件", identity);
+                    grant = Some(PaidBy::Invitation(id));
+                } else {
                     warn!("身份 {} 不在白名单中，拒绝接收文件", identity);
+                    PeerGuard::record_failure(peer_addr.ip());
+                    audit.outcome = Some(ConnectionOutcome::RejectedNotWhitelisted);
                     // 发送拒绝信号
-                    Self::send_transfer_complete(&mut stream).await?;
+                    Self::send_signal(&mut stream, SIGNAL_REJECTED).await?;
                     return Ok(());
                 }
-                info!("身份 {} 在白名单中，允许接收文件", identity);
+            }
+            ReceiveStatus::Code => {
+                // 握手成功说明对方知道口令，但口令只供第一个使用它的发送方的一次发送使用
+                match ReceiveCode::claim(&identity, &extras.session) {
+                    Some(claim) => code_claim = Some(claim),
+                    None => {
+                        warn!("接收口令已被其他发送使用，拒绝身份 {}", identity);
+                        audit.outcome = Some(ConnectionOutcome::RejectedCode);
+                        Self::send_signal(&mut stream, SIGNAL_REJECTED).await?;
+                        return Ok(());
+                    }
+                }
+                info!("身份 {} 使用口令连接，允许接收文件", identity);
             }
         }
         
+        // 已允许本次连接，记录联系人的身份码变更等待确认
+        if let Some(contact) = rotated_from {
+            let new_identity = identity.clone();
+            if let Err(e) = AddressBook::call(move || AddressBook::record_identity_rotation(&contact, &new_identity)).await {
+                error!("记录身份码变更失败: {}", e);
+            }
+        }
+        
+        // 接收方正在关闭，不再开始新的文件传输
+        if Self::is_draining() {
+            info!("接收方正在关闭，拒绝新的文件传输");
+            Self::send_signal(&mut stream, SIGNAL_SHUTTING_DOWN).await?;
+            return Ok(());
+        }
+        
         // 接收文件名长度
         let mut file_name_len_bytes = vec![0u8; 8];
-        let file_name_len_size = Self::read_encrypted(&mut transport, &mut stream, &mut file_name_len_bytes).await?;
+        let file_name_len_size = with_timeout(
+            timeouts.metadata,
+            "接收文件名长度",
+            Self::read_encrypted(&mut transport, &mut stream, &mut file_name_len_bytes),
+        ).await?;
         if file_name_len_size == 0 {
             info!("接收到传输结束信号，连接正常关闭");
             return Ok(());
@@ -340,18 +1009,34 @@ impl FileReceiver {
         
         // 接收文件名
         let mut file_name_bytes = vec![0u8; file_name_len as usize];
-        let file_name_size = Self::read_encrypted(&mut transport, &mut stream, &mut file_name_bytes).await?;
+        let file_name_size = with_timeout(
+            timeouts.metadata,
+            "接收文件名",
+            Self::read_encrypted(&mut transport, &mut stream, &mut file_name_bytes),
+        ).await?;
         if file_name_size == 0 {
             info!("接收到传输结束信号，连接正常关闭");
             return Ok(());
         }
         let file_name = String::from_utf8(file_name_bytes)?;
         
+        // 文件名由对方提供，只允许单个文件名，不能包含路径
+        if !is_safe_file_name(&file_name) {
+            warn!("文件名不合法，拒绝接收: {:?}", file_name);
+            PeerGuard::record_failure(peer_addr.ip());
+            Self::send_signal(&mut stream, SIGNAL_REJECTED).await?;
+            return Err(NearbyError::protocol(format!("文件名不合法: {:?}", file_name)));
+        }
+        
         info!("接收文件: {}", file_name);
         
         // 接收文件大小
         let mut file_size_bytes = vec![0u8; 8];
-        let file_size_size = Self::read_encrypted(&mut transport, &mut stream, &mut file_size_bytes).await?;
+        let file_size_size = with_timeout(
+            timeouts.metadata,
+            "接收文件大小",
+            Self::read_encrypted(&mut transport, &mut stream, &mut file_size_bytes),
+        ).await?;
         if file_size_size == 0 {
             info!("接收到传输结束信号，连接正常关闭");
             return Ok(());
@@ -360,20 +1045,64 @@ impl FileReceiver {
         
         info!("文件大小: {} 字节", file_size);
         
+        // 接收文件修改时间（Unix 秒）
+        let mut modified_bytes = vec![0u8; 8];
+        let modified_size = with_timeout(
+            timeouts.metadata,
+            "接收文件修改时间",
+            Self::read_encrypted(&mut transport, &mut stream, &mut modified_bytes),
+        ).await?;
+        if modified_size == 0 {
+            info!("接收到传输结束信号，连接正常关闭");
+            return Ok(());
+        }
+        // 修改时间由发送方提供，晚于当前时间的按当前时间处理，
+        // 避免一个未来时间的文件让“保留较新的文件”永远跳过之后的版本
+        let now_secs = std::time::SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+        let modified = u64::from_be_bytes(modified_bytes.try_into().unwrap()).min(now_secs);
+        
         // 创建 downloads 目录
-        let downloads_dir = Self::get_downloads_dir().await?;
+        let downloads_dir = Self::get_downloads_dir(&identity, &file_name).await?;
         tokio::fs::create_dir_all(&downloads_dir).await?;
         
         // 构建保存路径
         let save_path = downloads_dir.join(&file_name);
         
-        // 处理文件名冲突
-        let final_save_path = Self::get_unique_filename(save_path).await;
+        // 按设置处理文件名冲突，并把结果告知发送方
+        let (final_save_path, resolution) = Self::resolve_conflict(
+            &mut transport,
+            &mut stream,
+            save_path,
+            file_size,
+            modified,
+        ).await?;
+        Self::send_signal(&mut stream, resolution.signal()).await?;
+        
+        if resolution == ConflictResolution::Skipped {
+            info!("已存在相同或更新的文件，跳过: {}", final_save_path.display());
+            PeerGuard::record_success(peer_addr.ip());
+            audit.outcome = Some(ConnectionOutcome::Accepted);
+            Self::settle_grant(grant, &identity, &extras.session).await;
+            if let Some(claim) = code_claim.as_mut() {
+                claim.mark_succeeded();
+            }
+            Self::record_received_file(file_name, file_size, peer_addr, identity, &final_save_path, resolution).await;
+            return Ok(());
+        }
+        
+        info!("保存文件到: {} ({})", final_save_path.display(), resolution.label());
         
-        info!("保存文件到: {}", final_save_path.display());
+        // 覆盖时先写入临时文件，传输完成后再替换，失败时不会破坏已有文件
+        let write_path = if resolution == ConflictResolution::Overwritten {
+            let mut part_name = final_save_path.file_name().unwrap_or_default().to_os_string();
+            part_name.push(".part");
+            final_save_path.with_file_name(part_name)
+        } else {
+            final_save_path.clone()
+        };
         
         // 创建文件
-        let mut file = File::create(&final_save_path).await?;
+        let mut file = File::create(&write_path).await?;
         
         let buffer_size = if file_size <= 10 * 1024 * 1024 {
             64 * 1024      // 小文件: 64KB
@@ -388,8 +1117,24 @@ impl FileReceiver {
         let mut buffer = vec![0u8;  buffer_size]; // 减小缓冲区大小到32KB
         
         while received < file_size {
+            // 宽限期已到，取消传输并删除未完成的文件
+            if CANCEL_TRANSFERS.load(Ordering::SeqCst) {
+                warn!("接收方正在关闭，取消传输: 已接收 {}/{} 字节", received, file_size);
+                drop(file);
+                if let Err(e) = tokio::fs::remove_file(&write_path).await {
+                    error!("删除未完成的文件失败: {}", e);
+                }
+                Self::send_signal(&mut stream, SIGNAL_SHUTTING_DOWN).await?;
+                return Err(NearbyError::Rejected(RejectReason::ShuttingDown));
+            }
+            
             let bytes_to_read = std::cmp::min(buffer.len() as u64, file_size - received) as usize;
-            match Self::read_encrypted(&mut transport, &mut stream, &mut buffer[..bytes_to_read]).await {
+            let frame = with_timeout(
+                timeouts.idle,
+                "等待数据",
+                Self::read_encrypted(&mut transport, &mut stream, &mut buffer[..bytes_to_read]),
+            ).await;
+            match frame {
                 Ok(bytes_read) => {
                     if bytes_read == 0 {
                         // 传输结束信号
@@ -398,13 +1143,14 @@ impl FileReceiver {
                             break;
                         } else {
                             error!("文件传输中断: 已接收 {}/{} 字节", received, file_size);
-                            return Err("文件传输中断".into());
+                            return Err(NearbyError::protocol(format!("文件传输中断: 已接收 {}/{} 字节", received, file_size)));
                         }
                     }
                     
                     // 异步写入文件
                     file.write_all(&buffer[..bytes_read]).await?;
                     received += bytes_read as u64;
+                    audit.bytes = received;
                     
                     // 每接收 1MB 打印一次进度，避免频繁打印
                     if received % (1024 * 1024) < 32 * 1024 || received == file_size {
@@ -419,37 +1165,184 @@ impl FileReceiver {
             }
         }
         
+        // 保留发送方文件的修改时间，之后“保留较新的文件”才能正确比较
+        file.flush().await?;
+        let file = file.into_std().await;
+        if modified > 0 {
+            if let Err(e) = file.set_modified(UNIX_EPOCH + Duration::from_secs(modified)) {
+                warn!("设置文件修改时间失败: {}", e);
+            }
+        }
+        drop(file);
+        if write_path != final_save_path {
+            tokio::fs::rename(&write_path, &final_save_path).await?;
+        }
+        
         info!("文件接收完成: {}", final_save_path.display());
         
         // 发送传输完成确认
         Self::send_transfer_complete(&mut stream).await?;
+        PeerGuard::record_success(peer_addr.ip());
+        audit.outcome = Some(ConnectionOutcome::Accepted);
+        
+        Self::settle_grant(grant, &identity, &extras.session).await;
+        if let Some(claim) = code_claim.as_mut() {
+            claim.mark_succeeded();
+        }
+        Self::record_received_file(file_name, file_size, peer_addr, identity, &final_save_path, resolution).await;
+        
+        Ok(())
+    }
+    
+    // 按设置处理同名文件，返回最终保存路径和处理结果
+    async fn resolve_conflict(
+        transport: &mut snow::TransportState,
+        stream: &mut TcpStream,
+        save_path: PathBuf,
+        file_size: u64,
+        modified: u64,
+    ) -> Result<(PathBuf, ConflictResolution), NearbyError> {
+        let existing = match tokio::fs::metadata(&save_path).await {
+            Ok(metadata) => metadata,
+            Err(_) => return Ok((save_path, ConflictResolution::None)),
+        };
+        
+        match Settings::current().conflict_policy {
+            ConflictPolicy::Rename => {
+                Ok((Self::get_unique_filename(save_path).await, ConflictResolution::Renamed))
+            }
+            ConflictPolicy::Overwrite => Ok((save_path, ConflictResolution::Overwritten)),
+            ConflictPolicy::SkipIfIdentical => {
+                // 大小不同时一定不同，不必计算哈希
+                if existing.len() == file_size
+                    && Self::same_content(transport, stream, &save_path, file_size).await?
+                {
+                    Ok((save_path, ConflictResolution::Skipped))
+                } else {
+                    Ok((Self::get_unique_filename(save_path).await, ConflictResolution::Renamed))
+                }
+            }
+            // 比较的是发送方声明的修改时间，接收时已限制为不晚于当前时间
+            ConflictPolicy::KeepNewer => {
+                if protocol::modified_secs(&existing) >= modified {
+                    Ok((save_path, ConflictResolution::Skipped))
+                } else {
+                    Ok((save_path, ConflictResolution::Overwritten))
+                }
+            }
+        }
+    }
+    
+    // 向发送方索取文件哈希，与已有文件比较
+    async fn same_content(
+        transport: &mut snow::TransportState,
+        stream: &mut TcpStream,
+        existing_path: &Path,
+        file_size: u64,
+    ) -> Result<bool, NearbyError> {
+        Self::send_signal(stream, SIGNAL_NEED_HASH).await?;
         
-        if let Err(e) = crate::core::db::AddressBook::add_file_receive_record(
+        let mut remote_hash = [0u8; 32];
+        let hash_len = with_timeout(
+            protocol::hash_timeout(file_size),
+            "接收文件哈希",
+            Self::read_encrypted(transport, stream, &mut remote_hash),
+        ).await?;
+        if hash_len != remote_hash.len() {
+            return Err(NearbyError::protocol("无效的文件哈希"));
+        }
+        
+        let local_hash = protocol::file_sha256(existing_path).await?;
+        Ok(local_hash == remote_hash)
+    }
+    
+    // 把接收结果写入历史记录
+    async fn record_received_file(
+        file_name: String,
+        file_size: u64,
+        peer_addr: SocketAddr,
+        identity: String,
+        save_path: &Path,
+        resolution: ConflictResolution,
+    ) {
+        let record_path = save_path.to_string_lossy().to_string();
+        let record_result = AddressBook::call(move || AddressBook::add_file_receive_record(
             &file_name,
             file_size,
             &peer_addr.ip().to_string(),
             &identity,
-            &final_save_path.to_string_lossy(),
-        ) {
+            &record_path,
+            resolution,
+        )).await;
+        if let Err(e) = record_result {
             error!("记录文件接收信息失败: {}", e);
         } else {
             info!("文件接收记录已保存到数据库");
         }
+    }
+    
+    /// 获取本次传输的保存目录：下载目录加上按分类规则生成的子文件夹
+    async fn get_downloads_dir(identity: &str, file_name: &str) -> Result<PathBuf, NearbyError> {
+        let settings = Settings::current();
+        let mut dir = if settings.download_dir.is_empty() {
+            Self::default_downloads_dir()?
+        } else {
+            PathBuf::from(&settings.download_dir)
+        };
         
-        Ok(())
+        let routing = settings.routing;
+        match routing.by_sender {
+            SenderFolder::None => {}
+            SenderFolder::Alias => {
+                let lookup = identity.to_string();
+                let contact = AddressBook::call(move || AddressBook::get_contact_by_identity(&lookup))
+                    .await
+                    .unwrap_or_else(|e| {
+                        warn!("查找发送方联系人失败: {}", e);
+                        None
+                    });
+                // 不在联系人中的发送方使用身份码前缀
+                let folder = contact
+                    .map(|c| c.name)
+                    .unwrap_or_else(|| identity.chars().take(8).collect());
+                dir.push(sanitize_folder_name(&folder));
+            }
+            SenderFolder::Identity => dir.push(sanitize_folder_name(identity)),
+        }
+        if routing.by_date {
+            dir.push(chrono::Local::now().format("%Y-%m-%d").to_string());
+        }
+        if routing.by_extension {
+            let extension = std::path::Path::new(file_name)
+                .extension()
+                .map(|ext| ext.to_string_lossy().to_lowercase())
+                .unwrap_or_default();
+            if extension.is_empty() {
+                dir.push("无扩展名");
+            } else {
+                dir.push(sanitize_folder_name(&extension));
+            }
+        }
+        
+        Ok(dir)
     }
     
-    /// 获取 downloads 目录路径
-    async fn get_downloads_dir() -> Result<PathBuf, ReceiveError> {
+    /// 默认下载目录
+    pub fn default_downloads_dir() -> Result<PathBuf, NearbyError> {
         // 首先尝试获取用户目录下的 Downloads
+        // 默认档案以外的档案使用各自的文件夹
+        let suffix = match Profiles::active().as_str() {
+            DEFAULT_PROFILE => String::new(),
+            name => format!("-{}", name),
+        };
         if let Some(mut downloads_dir) = dirs::download_dir() {
-            downloads_dir.push("Nearby-receive");
+            downloads_dir.push(format!("Nearby-receive{}", suffix));
             return Ok(downloads_dir);
         }
         
         // 如果无法获取系统 Downloads 目录，使用当前目录下的 downloads 文件夹
         let current_dir = std::env::current_dir()?;
-        Ok(current_dir.join("downloads"))
+        Ok(current_dir.join(format!("downloads{}", suffix)))
     }
     
     /// 处理文件名冲突，如果文件已存在则添加数字后缀
@@ -482,6 +1375,38 @@ impl FileReceiver {
     }
 }
 
+/// 把备注或身份码转换为可用作文件夹名的文本
+fn sanitize_folder_name(name: &str) -> String {
+    let cleaned: String = name
+        .trim()
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+    // 避免生成 . 或 .. 这样的特殊目录
+    let cleaned = cleaned.trim_matches('.').to_string();
+    if cleaned.is_empty() {
+        "未知发送方".to_string()
+    } else {
+        cleaned
+    }
+}
+
+/// 文件名只能是单个路径组成部分：非空，不是 . 或 ..，不含路径分隔符和控制字符，
+/// Windows 上也不能含盘符或数据流使用的冒号
+fn is_safe_file_name(name: &str) -> bool {
+    if name.is_empty() || name.contains(['/', '\\']) || name.chars().any(|c| c.is_control()) {
+        return false;
+    }
+    if cfg!(windows) && name.contains(':') {
+        return false;
+    }
+    Path::new(name).file_name().is_some_and(|file_name| file_name == name)
+}
+
 /// 判断是否为特殊IPv6地址（链路本地、多播等）
 fn is_special_ipv6_address(ip: Ipv6Addr) -> bool {
     let segments = ip.segments();
@@ -498,4 +1423,62 @@ fn is_special_ipv6_address(ip: Ipv6Addr) -> bool {
         return true;
     }
     false
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn any_address(port: u16) -> SocketAddrV6 {
+        SocketAddrV6::new(Ipv6Addr::UNSPECIFIED, port, 0, 0)
+    }
+    
+    // 端口无法绑定说明服务器仍在监听
+    async fn is_listening(port: u16) -> bool {
+        TcpListener::bind(any_address(port)).await.is_err()
+    }
+    
+    async fn wait_until_listening(port: u16) {
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while !is_listening(port).await {
+            assert!(Instant::now() < deadline, "服务器没有在端口 {} 上监听", port);
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    }
+    
+    // 更换端口后重新启动：旧服务器退出后才在新端口启动，重复启动和启动失败都不会清除运行中服务器的标志
+    #[tokio::test]
+    async fn restart_on_port_change() {
+        let key = [7u8; 32];
+        let occupied = TcpListener::bind(any_address(0)).await.unwrap();
+        let occupied_port = occupied.local_addr().unwrap().port();
+        let (old_port, new_port) = {
+            let old = TcpListener::bind(any_address(0)).await.unwrap();
+            let new = TcpListener::bind(any_address(0)).await.unwrap();
+            (old.local_addr().unwrap().port(), new.local_addr().unwrap().port())
+        };
+        
+        let old_server = tokio::spawn(FileReceiver::serve(ServerRunning::acquire().unwrap(), key, old_port));
+        wait_until_listening(old_port).await;
+        assert!(ServerRunning::acquire().is_none(), "服务器运行时不能重复启动");
+        assert!(SERVER_RUNNING.load(Ordering::SeqCst));
+        
+        // 停止时等待旧服务器退出并释放端口
+        FileReceiver::stop_server().await.unwrap();
+        assert!(old_server.await.unwrap().is_ok());
+        assert!(!SERVER_RUNNING.load(Ordering::SeqCst));
+        assert!(!is_listening(old_port).await);
+        
+        let new_server = tokio::spawn(FileReceiver::serve(ServerRunning::acquire().unwrap(), key, new_port));
+        wait_until_listening(new_port).await;
+        assert!(SERVER_RUNNING.load(Ordering::SeqCst));
+        
+        FileReceiver::stop_server().await.unwrap();
+        assert!(new_server.await.unwrap().is_ok());
+        assert!(!SERVER_RUNNING.load(Ordering::SeqCst));
+        
+        // 端口被占用时启动失败，只清除这次启动设置的标志
+        let result = FileReceiver::serve(ServerRunning::acquire().unwrap(), key, occupied_port).await;
+        assert!(matches!(result, Err(NearbyError::Network(_))));
+        assert!(!SERVER_RUNNING.load(Ordering::SeqCst));
+    }
+}
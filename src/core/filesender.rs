@@ -1,45 +1,80 @@
 // src/core/filesender.rs
-use std::error;
 use std::net::SocketAddrV6;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use tokio::fs::File;
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::TcpStream;
 use crate::core::db::AddressBook;
-use log::{info, error};
+use crate::core::error::{NearbyError, RejectReason};
+use crate::core::identity_key::IdentityKey;
+use crate::core::receive_code::ReceiveCode;
+use crate::core::settings::Settings;
+use crate::core::protocol::{
+    self, with_timeout, ConflictResolution, HandshakeExtras, SIGNAL_BUSY, SIGNAL_COMPLETE, SIGNAL_NEED_HASH, SIGNAL_READY,
+    SIGNAL_REJECTED, SIGNAL_SHUTTING_DOWN,
+};
+use log::{info, error, warn};
+use rand::Rng;
 use tokio::sync::Semaphore;
 
-const CONCURRENT_TRANSFERS: usize = 5;
-type SendError = Box<dyn error::Error + Send + Sync>;
+// 接收方繁忙时的重试次数和初始退避时间
+const BUSY_RETRIES: u32 = 5;
+const BUSY_BACKOFF_MS: u64 = 1000;
+
+// 联系人有多个地址时，每个地址的连接超时
+const FAILOVER_CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
 
 pub struct FileSender;
 
+// 单个文件的发送结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct SentFile {
+    pub resolution: ConflictResolution, // 接收方对同名文件的处理结果
+    pub address: String,                // 最终连接成功的地址
+}
+
+// 握手时使用的凭据：身份私钥转换的静态密钥、接收方口令导出的预共享密钥，
+// 以及在第三条消息中附带的邀请令牌和身份码轮换证明
+#[derive(Clone)]
+struct Credentials {
+    static_key: [u8; 32],
+    psk: Option<[u8; 32]>,
+    extras: HandshakeExtras,
+}
+
 impl FileSender {
-    // 执行 Noise 协议握手（作为发起者）
-    async fn perform_noise_handshake(stream: &mut TcpStream) -> Result<snow::TransportState, SendError> {
+    // 执行 Noise 协议握手（作为发起者），静态密钥由身份私钥转换而来，接收方据此确认身份码；
+    // 提供预共享密钥时使用 XXpsk3 模式
+    async fn perform_noise_handshake(
+        stream: &mut TcpStream,
+        credentials: &Credentials,
+    ) -> Result<snow::TransportState, NearbyError> {
         info!("开始 Noise 协议握手...");
         
         // 创建发起者
-        let builder = snow::Builder::new("Noise_XX_25519_ChaChaPoly_BLAKE2s".parse()?);
-        let static_key = builder.generate_keypair()?.private;
-        let mut noise = builder
-            .local_private_key(&static_key)
-            .build_initiator()?;
+        let pattern = if credentials.psk.is_some() { protocol::NOISE_PATTERN_PSK } else { protocol::NOISE_PATTERN };
+        let builder = snow::Builder::new(pattern.parse()?);
+        let mut builder = builder.local_private_key(&credentials.static_key);
+        if let Some(psk) = credentials.psk.as_ref() {
+            builder = builder.psk(3, psk);
+        }
+        let mut noise = builder.build_initiator()?;
         
         // 发送第一条握手消息
         info!("准备发送第一条握手消息...");
         let mut handshake_buffer1 = vec![0u8; 65535];
         let len = noise.write_message(&[], &mut handshake_buffer1)?;
         
-        stream.write_u16(len as u16).await?;
-        stream.write_all(&handshake_buffer1[..len]).await?;
+        stream.write_u16(len as u16).await.map_err(NearbyError::Network)?;
+        stream.write_all(&handshake_buffer1[..len]).await.map_err(NearbyError::Network)?;
         info!("成功发送第一条握手消息");
         
         // 接收响应消息
         info!("等待接收响应消息...");
-        let len = stream.read_u16().await? as usize;
+        let len = stream.read_u16().await.map_err(NearbyError::Network)? as usize;
         let mut msg = vec![0u8; len];
-        stream.read_exact(&mut msg).await?;
+        stream.read_exact(&mut msg).await.map_err(NearbyError::Network)?;
         info!("成功接收响应消息");
         
         // 读取响应消息
@@ -50,10 +85,10 @@ impl FileSender {
         // 发送第三条握手消息
         info!("准备发送第三条握手消息...");
         let mut handshake_buffer3 = vec![0u8; 65535];
-        let len = noise.write_message(&[], &mut handshake_buffer3)?;
+        let len = noise.write_message(&credentials.extras.encode(), &mut handshake_buffer3)?;
         
-        stream.write_u16(len as u16).await?;
-        stream.write_all(&handshake_buffer3[..len]).await?;
+        stream.write_u16(len as u16).await.map_err(NearbyError::Network)?;
+        stream.write_all(&handshake_buffer3[..len]).await.map_err(NearbyError::Network)?;
         info!("成功发送第三条握手消息");
         
         // 转换为传输模式
@@ -63,114 +98,231 @@ impl FileSender {
         Ok(transport)
     }
     
-    // 使用加密通道读取数据
-    #[allow(dead_code)]
-    async fn read_encrypted(transport: &mut snow::TransportState, stream: &mut TcpStream, buffer: &mut [u8]) -> Result<usize, Box<dyn error::Error>> {
-        // 先读取加密数据的长度
-        let encrypted_len = match stream.read_u16().await {
-            Ok(len) => len as usize,
-            Err(e) => {
-                error!("读取加密数据长度失败: {}", e);
-                return Err(e.into());
-            }
-        };
-        
-        if encrypted_len == 0 {
-            // 长度为0表示传输结束
-            return Ok(0);
-        }
-        
-        let mut encrypted_data = vec![0u8; encrypted_len];
-        if let Err(e) = stream.read_exact(&mut encrypted_data).await {
-            error!("读取加密数据内容失败: {}", e);
-            return Err(e.into());
-        }
-        
-        // 解密数据
-        let len = match transport.read_message(&encrypted_data, buffer) {
-            Ok(l) => l,
-            Err(e) => {
-                error!("解密数据失败: {}", e);
-                return Err(e.into());
-            }
-        };
-        
-        Ok(len)
-    }
-    
     // 使用加密通道写入数据
-    async fn write_encrypted(transport: &mut snow::TransportState, stream: &mut TcpStream, data: &[u8]) -> Result<(), SendError> {
+    async fn write_encrypted(transport: &mut snow::TransportState, stream: &mut TcpStream, data: &[u8]) -> Result<(), NearbyError> {
         let mut buffer = vec![0u8; 65535];
         
         // 加密数据
-        let len = transport.write_message(data, &mut buffer)?;
+        let len = transport
+            .write_message(data, &mut buffer)
+            .map_err(|e| NearbyError::protocol(format!("加密数据失败: {}", e)))?;
         
         // 发送加密数据的长度和数据
-        stream.write_u16(len as u16).await?;
-        stream.write_all(&buffer[..len]).await?;
+        stream.write_u16(len as u16).await.map_err(NearbyError::Network)?;
+        stream.write_all(&buffer[..len]).await.map_err(NearbyError::Network)?;
         
         Ok(())
     }
     
     // 等待传输完成确认
-    async fn wait_for_transfer_complete(stream: &mut TcpStream) -> Result<(), SendError> {
+    async fn wait_for_transfer_complete(stream: &mut TcpStream) -> Result<(), NearbyError> {
         info!("等待接收方的传输完成确认...");
         
         // 读取结束信号（长度为0的数据包）
-        let end_signal = stream.read_u16().await?;
+        let end_signal = stream.read_u16().await.map_err(NearbyError::Network)?;
         
-        if end_signal == 0 {
-            info!("接收方已确认传输完成");
-            Ok(())
-        } else {
-            error!("无效的传输结束信号: {}", end_signal);
-            Err("无效的传输结束信号".into())
+        match end_signal {
+            SIGNAL_COMPLETE => {
+                info!("接收方已确认传输完成");
+                Ok(())
+            }
+            SIGNAL_SHUTTING_DOWN => {
+                error!("接收方正在关闭，传输已取消");
+                Err(NearbyError::Rejected(RejectReason::ShuttingDown))
+            }
+            _ => {
+                error!("无效的传输结束信号: {}", end_signal);
+                Err(NearbyError::protocol(format!("无效的传输结束信号: {}", end_signal)))
+            }
+        }
+    }
+    
+    // 等待接收方告知是否接受此连接
+    async fn wait_for_ready(stream: &mut TcpStream) -> Result<(), NearbyError> {
+        let status = stream.read_u16().await.map_err(NearbyError::Network)?;
+        match status {
+            SIGNAL_READY => Ok(()),
+            SIGNAL_BUSY => {
+                warn!("接收方繁忙");
+                Err(NearbyError::Rejected(RejectReason::Busy))
+            }
+            // 握手前只有口令模式会拒绝连接
+            SIGNAL_REJECTED => {
+                warn!("接收口令已过期或已被使用");
+                Err(NearbyError::Rejected(RejectReason::CodeUnavailable))
+            }
+            _ => {
+                error!("无效的连接状态: {}", status);
+                Err(NearbyError::protocol(format!("无效的连接状态: {}", status)))
+            }
+        }
+    }
+    
+    // 写入失败后，尝试读取接收方留下的信号以给出更准确的原因
+    async fn explain_write_failure(stream: &mut TcpStream, write_error: NearbyError) -> NearbyError {
+        let signal = tokio::time::timeout(
+            tokio::time::Duration::from_secs(1),
+            stream.read_u16(),
+        ).await;
+        
+        match signal {
+            Ok(Ok(SIGNAL_SHUTTING_DOWN)) => {
+                error!("接收方正在关闭，传输已取消");
+                NearbyError::Rejected(RejectReason::ShuttingDown)
+            }
+            Ok(Ok(SIGNAL_REJECTED)) => {
+                error!("接收方拒绝了本次传输");
+                NearbyError::Rejected(RejectReason::Refused)
+            }
+            _ => write_error,
+        }
+    }
+    
+    // 等待接收方告知同名文件的处理结果，需要时先发送文件哈希
+    async fn wait_for_resolution(
+        transport: &mut snow::TransportState,
+        stream: &mut TcpStream,
+        file_path: &str,
+        file_size: u64,
+    ) -> Result<ConflictResolution, NearbyError> {
+        let timeouts = protocol::get_timeouts();
+        let mut signal = with_timeout(
+            timeouts.metadata,
+            "等待接收方确认文件信息",
+            async { stream.read_u16().await.map_err(NearbyError::Network) },
+        ).await?;
+        
+        // 接收方存在同名同大小的文件，需要比较内容
+        if signal == SIGNAL_NEED_HASH {
+            info!("接收方请求文件哈希: {}", file_path);
+            let hash_timeout = protocol::hash_timeout(file_size);
+            let hash = with_timeout(
+                hash_timeout,
+                "计算文件哈希",
+                async { protocol::file_sha256(std::path::Path::new(file_path)).await.map_err(NearbyError::from) },
+            ).await?;
+            Self::write_encrypted(transport, stream, &hash).await?;
+            signal = with_timeout(
+                hash_timeout,
+                "等待接收方比较文件",
+                async { stream.read_u16().await.map_err(NearbyError::Network) },
+            ).await?;
+        }
+        
+        match signal {
+            SIGNAL_REJECTED => {
+                error!("接收方拒绝了本次传输");
+                Err(NearbyError::Rejected(RejectReason::Refused))
+            }
+            SIGNAL_SHUTTING_DOWN => {
+                error!("接收方正在关闭，传输已取消");
+                Err(NearbyError::Rejected(RejectReason::ShuttingDown))
+            }
+            _ => ConflictResolution::from_signal(signal).ok_or_else(|| {
+                error!("无效的同名文件处理信号: {}", signal);
+                NearbyError::protocol(format!("无效的同名文件处理信号: {}", signal))
+            }),
         }
     }
     
     // 发送传输结束信号
-    async fn send_transfer_complete(stream: &mut TcpStream) -> Result<(), SendError> {
+    async fn send_transfer_complete(stream: &mut TcpStream) -> Result<(), NearbyError> {
         info!("发送传输结束信号...");
         
         // 发送长度为0的数据包表示传输结束
-        stream.write_u16(0).await?;
-        stream.flush().await?;
+        stream.write_u16(0).await.map_err(NearbyError::Network)?;
+        stream.flush().await.map_err(NearbyError::Network)?;
         
         info!("传输结束信号已发送");
         Ok(())
     }
     
-    // 发送单个文件的内部实现
-    async fn send_single_file(
-        ipv6_addr: &str,
-        file_path: &str,
-    ) -> Result<(), SendError> {
-        // 如果 IP 地址为空，默认使用本地地址 (::1)
-        let actual_ip = if ipv6_addr.is_empty() {
-            "::1"
+    // 按顺序尝试各个地址，返回第一个连接成功的连接和地址
+    async fn connect_any(addresses: &[String], port: u16) -> Result<(TcpStream, String), NearbyError> {
+        // 只有一个地址时不必缩短超时
+        let connect_timeout = if addresses.len() > 1 {
+            FAILOVER_CONNECT_TIMEOUT
         } else {
-            ipv6_addr
+            protocol::get_timeouts().handshake
         };
         
-        // 解析IPv6地址和端口
-        let socket_addr = format!("[{}]:6789", actual_ip);
-        let addr: SocketAddrV6 = socket_addr.parse()?;
+        let mut last_error = NearbyError::validation("没有可用的地址");
+        for address in addresses {
+            // 如果 IP 地址为空，默认使用本地地址 (::1)
+            let actual_ip = if address.is_empty() { "::1" } else { address.as_str() };
+            
+            // 解析IPv6地址和端口
+            let addr: SocketAddrV6 = match format!("[{}]:{}", actual_ip, port).parse() {
+                Ok(addr) => addr,
+                Err(e) => {
+                    warn!("跳过无效的地址: {}", actual_ip);
+                    last_error = NearbyError::from(e);
+                    continue;
+                }
+            };
+            
+            info!("正在连接到接收方: {}", addr);
+            let connected = with_timeout(
+                connect_timeout,
+                "连接接收方",
+                async { TcpStream::connect(addr).await.map_err(NearbyError::Network) },
+            ).await;
+            match connected {
+                Ok(stream) => {
+                    info!("已连接到接收方: {}", addr);
+                    return Ok((stream, actual_ip.to_string()));
+                }
+                Err(e) => {
+                    warn!("连接 {} 失败: {}", addr, e);
+                    last_error = e;
+                }
+            }
+        }
+        Err(last_error)
+    }
+    
+    // 发送单个文件的内部实现，addresses 为同一接收方的候选地址，
+    // expected_identity 为联系人的身份码，握手得到的对方静态公钥必须与之对应
+    async fn send_single_file(
+        addresses: &[String],
+        port: u16,
+        credentials: &Credentials,
+        expected_identity: Option<&str>,
+        file_path: &str,
+    ) -> Result<SentFile, NearbyError> {
+        // 连接到接收方
+        let (mut stream, address) = Self::connect_any(addresses, port).await?;
         
-        info!("正在连接到接收方: {}", addr);
+        let timeouts = protocol::get_timeouts();
         
-        // 连接到接收方
-        let mut stream = TcpStream::connect(addr).await?;
-        info!("已连接到接收方: {}", addr);
+        // 等待接收方接受连接
+        with_timeout(
+            timeouts.handshake,
+            "等待接收方响应",
+            Self::wait_for_ready(&mut stream),
+        ).await?;
         
         // 进行 Noise 协议握手
-        let mut transport = Self::perform_noise_handshake(&mut stream).await?;
+        let mut transport = with_timeout(
+            timeouts.handshake,
+            "握手",
+            Self::perform_noise_handshake(&mut stream, credentials),
+        ).await?;
+        
+        // 确认对方是联系人本人，否则不发送任何内容
+        if let Some(expected) = expected_identity {
+            if !IdentityKey::matches_noise_key(expected, transport.get_remote_static()) {
+                error!("{} 的握手密钥与联系人的身份码不符，停止发送", address);
+                return Err(NearbyError::IdentityMismatch);
+            }
+        }
         
         // 获取自己的身份码
-        let my_identity = AddressBook::get_my_identity()?;
+        let my_identity = AddressBook::call(AddressBook::get_my_identity).await?;
         
         if my_identity.len() != 64 {
             error!("身份码长度不正确: {} (期望64字符)", my_identity.len());
-            return Err("身份码长度不正确，必须为64字符".into());
+            return Err(NearbyError::validation("身份码长度不正确，必须为64字符"));
         }
         
         info!("使用身份码: {}", my_identity);
@@ -182,30 +334,49 @@ impl FileSender {
             Some(name) => name.to_string_lossy().to_string(),
             None => {
                 error!("无法从路径获取文件名: {}", file_path);
-                return Err("无效的文件路径".into());
+                return Err(NearbyError::validation("无效的文件路径"));
             }
         };
         
-        // 获取文件大小
-        let file_size = file.metadata().await?.len();
+        // 获取文件大小和修改时间
+        let metadata = file.metadata().await?;
+        let file_size = metadata.len();
+        let modified = protocol::modified_secs(&metadata);
         
         info!("开始发送文件: {} ({} 字节)", file_name, file_size);
         
-        // 首先发送身份码 (64字符固定长度) - 使用加密通道
-        Self::write_encrypted(&mut transport, &mut stream, my_identity.as_bytes()).await?;
-        info!("已发送身份码");
-        
-        // 发送文件名长度和文件名 - 使用加密通道
-        let file_name_bytes = file_name.as_bytes();
-        let file_name_len = file_name_bytes.len() as u64;
-        
-        Self::write_encrypted(&mut transport, &mut stream, &file_name_len.to_be_bytes()).await?;
-        Self::write_encrypted(&mut transport, &mut stream, file_name_bytes).await?;
-        info!("已发送文件名: {}", file_name);
+        // 元数据阶段：身份码、文件名、文件大小和修改时间
+        let sent = with_timeout(timeouts.metadata, "发送文件信息", async {
+            // 首先发送身份码 (64字符固定长度) - 使用加密通道
+            Self::write_encrypted(&mut transport, &mut stream, my_identity.as_bytes()).await?;
+            info!("已发送身份码");
+            
+            // 发送文件名长度和文件名 - 使用加密通道
+            let file_name_bytes = file_name.as_bytes();
+            let file_name_len = file_name_bytes.len() as u64;
+            
+            Self::write_encrypted(&mut transport, &mut stream, &file_name_len.to_be_bytes()).await?;
+            Self::write_encrypted(&mut transport, &mut stream, file_name_bytes).await?;
+            info!("已发送文件名: {}", file_name);
+            
+            // 发送文件大小 - 使用加密通道
+            Self::write_encrypted(&mut transport, &mut stream, &file_size.to_be_bytes()).await?;
+            info!("已发送文件大小: {} 字节", file_size);
+            
+            // 发送文件修改时间，供接收方比较同名文件的新旧
+            Self::write_encrypted(&mut transport, &mut stream, &modified.to_be_bytes()).await?;
+            Ok(())
+        }).await;
+        if let Err(e) = sent {
+            return Err(Self::explain_write_failure(&mut stream, e).await);
+        }
         
-        // 发送文件大小 - 使用加密通道
-        Self::write_encrypted(&mut transport, &mut stream, &file_size.to_be_bytes()).await?;
-        info!("已发送文件大小: {} 字节", file_size);
+        // 等待接收方处理同名文件
+        let resolution = Self::wait_for_resolution(&mut transport, &mut stream, file_path, file_size).await?;
+        if resolution == ConflictResolution::Skipped {
+            info!("接收方已有相同或更新的文件，跳过发送: {}", file_name);
+            return Ok(SentFile { resolution, address });
+        }
         
         // 使用缓冲区异步发送文件内容 - 使用加密通道
         let mut buffer = vec![0u8; 32 * 1024]; // 减少缓冲区大小到32KB，避免加密缓冲区溢出
@@ -219,7 +390,14 @@ impl FileSender {
             }
             
             // 使用加密通道发送数据 - 只发送实际读取的数据
-            Self::write_encrypted(&mut transport, &mut stream, &buffer[..bytes_read]).await?;
+            let written = with_timeout(
+                timeouts.idle,
+                "发送数据",
+                Self::write_encrypted(&mut transport, &mut stream, &buffer[..bytes_read]),
+            ).await;
+            if let Err(e) = written {
+                return Err(Self::explain_write_failure(&mut stream, e).await);
+            }
             
             total_sent += bytes_read;
             
@@ -232,7 +410,7 @@ impl FileSender {
         }
         
         // 确保所有数据都被刷新
-        stream.flush().await?;
+        stream.flush().await.map_err(NearbyError::Network)?;
         
         info!("文件数据发送完成，发送传输结束信号...");
         
@@ -242,37 +420,114 @@ impl FileSender {
         info!("等待接收方的传输完成确认...");
         
         // 等待接收方的传输完成确认
-        Self::wait_for_transfer_complete(&mut stream).await?;
-        
-        info!("文件传输完成: {}", file_name);
-        Ok(())
+        with_timeout(
+            timeouts.idle,
+            "等待传输完成确认",
+            Self::wait_for_transfer_complete(&mut stream),
+        ).await?;
+        
+        info!("文件传输完成: {} ({}, 通过 {})", file_name, resolution.label(), address);
+        Ok(SentFile { resolution, address })
     }
     
-    // 并发传输多个文件
+    // 发送单个文件，接收方繁忙时按指数退避自动重试
+    async fn send_single_file_with_retry(
+        addresses: &[String],
+        port: u16,
+        credentials: &Credentials,
+        expected_identity: Option<&str>,
+        file_path: &str,
+    ) -> Result<SentFile, NearbyError> {
+        let mut attempt = 0;
+        loop {
+            match Self::send_single_file(addresses, port, credentials, expected_identity, file_path).await {
+                Err(e) if e.is_busy() && attempt < BUSY_RETRIES => {
+                    // 加入随机抖动，避免多个任务同时重试
+                    let backoff = BUSY_BACKOFF_MS * 2u64.pow(attempt);
+                    let jitter = rand::thread_rng().gen_range(0..=backoff / 2);
+                    attempt += 1;
+                    warn!("接收方繁忙，{} 毫秒后第 {} 次重试: {}", backoff + jitter, attempt, file_path);
+                    tokio::time::sleep(tokio::time::Duration::from_millis(backoff + jitter)).await;
+                }
+                // 口令模式的接收方在握手后拒绝，说明口令已被其他发送使用
+                Err(NearbyError::Rejected(RejectReason::Refused)) if credentials.psk.is_some() => {
+                    return Err(NearbyError::Rejected(RejectReason::CodeUnavailable));
+                }
+                result => return result,
+            }
+        }
+    }
+    
+    // 并发传输多个文件到同一接收方，addresses 按优先顺序排列，连接失败时依次尝试下一个；
+    // port 为空时使用设置中的端口，identity 为联系人的身份码（手动输入地址时为空），
+    // receive_code 为接收方口令模式下显示的口令，invitation 为接收方生成的邀请令牌
     pub async fn send_files(
-        ipv6_addr: &str,
+        addresses: &[String],
+        port: Option<u16>,
+        identity: Option<&str>,
+        receive_code: Option<&str>,
+        invitation: Option<&str>,
         file_paths: &[String],
-    ) -> Result<Vec<(String, Result<(), SendError>)>, SendError> {
+    ) -> Result<Vec<(String, Result<SentFile, NearbyError>)>, NearbyError> {
         if file_paths.is_empty() {
             return Ok(Vec::new());
         }
+        if addresses.is_empty() {
+            return Err(NearbyError::validation("没有可用的地址"));
+        }
         
-        info!("开始并发发送 {} 个文件到 {}", file_paths.len(), ipv6_addr);
+        let port = port.unwrap_or(Settings::current().port);
+        // 身份私钥已锁定时无法证明身份，不发送
+        let static_key = AddressBook::call(AddressBook::my_noise_key).await?;
+        // 读取轮换证明失败时照常发送，只是对方无法自动更新身份码
+        let rotation = AddressBook::call(AddressBook::my_rotation_proofs).await.unwrap_or_else(|e| {
+            warn!("读取身份码轮换证明失败: {}", e);
+            Vec::new()
+        });
+        // 早期身份码没有私钥无法签名，告知对方由对方核对后更新
+        let legacy_identity = AddressBook::call(AddressBook::my_legacy_identity).await.unwrap_or_else(|e| {
+            warn!("读取早期身份码失败: {}", e);
+            None
+        });
+        let credentials = Credentials {
+            static_key,
+            psk: receive_code.map(ReceiveCode::derive_psk),
+            extras: HandshakeExtras {
+                invitation: invitation.map(|token| token.trim().to_string()).unwrap_or_default(),
+                rotation,
+                legacy_identity: legacy_identity.unwrap_or_default(),
+                session: (0..16).map(|_| format!("{:02x}", rand::random::<u8>())).collect(),
+            },
+        };
+        info!("开始并发发送 {} 个文件到 {} (端口 {})", file_paths.len(), addresses.join(", "), port);
         
         // 创建信号量限制并发数量
-        let semaphore = Arc::new(Semaphore::new(CONCURRENT_TRANSFERS));
+        let semaphore = Arc::new(Semaphore::new(Settings::current().concurrent_transfers));
+        // 某个地址连接成功后，之后的文件优先尝试它
+        let dial_order = Arc::new(Mutex::new(addresses.to_vec()));
+        let identity = identity.map(|identity| identity.to_string());
         let mut tasks = Vec::new();
         
         // 为每个文件创建异步任务
         for file_path in file_paths {
-            let ip = ipv6_addr.to_string();
             let path = file_path.clone();
             let semaphore = semaphore.clone();
+            let dial_order = dial_order.clone();
+            let credentials = credentials.clone();
+            let identity = identity.clone();
             
             let task = tokio::spawn(async move {
                 // 在任务内部获取许可
                 let _permit = semaphore.acquire().await;
-                let result = Self::send_single_file(&ip, &path).await;
+                let addresses = dial_order.lock().unwrap().clone();
+                let result = Self::send_single_file_with_retry(&addresses, port, &credentials, identity.as_deref(), &path).await;
+                if let Ok(sent) = &result {
+                    let mut order = dial_order.lock().unwrap();
+                    if let Some(index) = order.iter().position(|a| a == &sent.address) {
+                        let address = order.remove(index);
+                        order.insert(0, address);
+                    }
+                }
                 (path, result)
             });
             
@@ -286,7 +541,7 @@ impl FileSender {
                 Ok(result) => results.push(result),
                 Err(e) => {
                     error!("任务执行失败: {}", e);
-                    results.push(("unknown".to_string(), Err("任务执行失败".into())));
+                    results.push(("unknown".to_string(), Err(NearbyError::Io(e.into()))));
                 }
             }
         }
@@ -297,179 +552,24 @@ impl FileSender {
         
         info!("并发发送完成: {} 成功, {} 失败", success_count, fail_count);
         
-        Ok(results)
-    }
-    
-    #[allow(dead_code)]
-    pub async fn send_file(
-        ipv6_addr: &str,
-        file_path: &str,
-    ) -> Result<(), SendError> {
-        // 如果 IP 地址为空，默认使用本地地址 (::1)
-        let actual_ip = if ipv6_addr.is_empty() {
-            "::1"
-        } else {
-            ipv6_addr
-        };
-        
-        // 解析IPv6地址和端口
-        let socket_addr = format!("[{}]:6789", actual_ip);
-        let addr: SocketAddrV6 = match socket_addr.parse() {
-            Ok(addr) => addr,
-            Err(e) => {
-                error!("解析 IPv6 地址失败: {} - 错误: {}", socket_addr, e);
-                return Err(e.into());
-            }
-        };
-        
-        info!("正在连接到接收方: {}", addr);
-        
-        // 连接到接收方
-        let mut stream = match TcpStream::connect(addr).await {
-            Ok(s) => s,
-            Err(e) => {
-                error!("连接到接收方失败: {} - 错误: {}", addr, e);
-                return Err(e.into());
-            }
-        };
-        info!("已连接到接收方: {}", addr);
-        
-        // 进行 Noise 协议握手
-        let mut transport = match Self::perform_noise_handshake(&mut stream).await {
-            Ok(t) => t,
-            Err(e) => {
-                error!("Noise 协议握手失败: {}", e);
-                return Err(e.into());
-            }
-        };
-        
-        // 获取自己的身份码
-        let my_identity = match AddressBook::get_my_identity() {
-            Ok(id) => id,
-            Err(e) => {
-                error!("获取身份码失败: {}", e);
-                return Err(e.into());
+        // 记录连接成功的地址，下次优先使用
+        let mut succeeded: Vec<String> = results
+            .iter()
+            .filter_map(|(_, r)| r.as_ref().ok().map(|sent| sent.address.clone()))
+            .collect();
+        succeeded.sort();
+        succeeded.dedup();
+        for address in succeeded {
+            let lookup = address.clone();
+            if let Err(e) = AddressBook::call(move || AddressBook::record_address_success(&lookup)).await {
+                warn!("记录地址 {} 发送成功失败: {}", address, e);
             }
-        };
-        
-        if my_identity.len() != 64 {
-            error!("身份码长度不正确: {} (期望64字符)", my_identity.len());
-            return Err("身份码长度不正确，必须为64字符".into());
         }
         
-        info!("使用身份码: {}", my_identity);
-        
-        // 异步打开要发送的文件
-        let mut file = match File::open(file_path).await {
-            Ok(f) => f,
-            Err(e) => {
-                error!("打开文件失败: {} - 错误: {}", file_path, e);
-                return Err(e.into());
-            }
-        };
-        
-        let file_name = match std::path::Path::new(file_path).file_name() {
-            Some(name) => name.to_string_lossy().to_string(),
-            None => {
-                error!("无法从路径获取文件名: {}", file_path);
-                return Err("无效的文件路径".into());
-            }
-        };
-        
-        // 获取文件大小
-        let file_size = match file.metadata().await {
-            Ok(metadata) => metadata.len(),
-            Err(e) => {
-                error!("获取文件元数据失败: {} - 错误: {}", file_path, e);
-                return Err(e.into());
-            }
-        };
-        
-        info!("开始发送文件: {} ({} 字节)", file_name, file_size);
-        
-        // 首先发送身份码 (64字符固定长度) - 使用加密通道
-        if let Err(e) = Self::write_encrypted(&mut transport, &mut stream, my_identity.as_bytes()).await {
-            error!("发送身份码失败: {}", e);
-            return Err(e);
-        }
-        info!("已发送身份码");
-        
-        // 发送文件名长度和文件名 - 使用加密通道
-        let file_name_bytes = file_name.as_bytes();
-        let file_name_len = file_name_bytes.len() as u64;
-        
-        if let Err(e) = Self::write_encrypted(&mut transport, &mut stream, &file_name_len.to_be_bytes()).await {
-            error!("发送文件名长度失败: {}", e);
-            return Err(e);
-        }
-        
-        if let Err(e) = Self::write_encrypted(&mut transport, &mut stream, file_name_bytes).await {
-            error!("发送文件名失败: {}", e);
-            return Err(e);
-        }
-        info!("已发送文件名: {}", file_name);
-        
-        // 发送文件大小 - 使用加密通道
-        if let Err(e) = Self::write_encrypted(&mut transport, &mut stream, &file_size.to_be_bytes()).await {
-            error!("发送文件大小失败: {}", e);
-            return Err(e);
-        }
-        info!("已发送文件大小: {} 字节", file_size);
-        
-        // 使用缓冲区异步发送文件内容 - 使用加密通道
-        let mut buffer = vec![0u8; 64 * 1024]; // 64KB 缓冲区
-        let mut total_sent = 0;
-        
-        loop {
-            let bytes_read = match file.read(&mut buffer).await {
-                Ok(read) => read,
-                Err(e) => {
-                    error!("读取文件内容失败: {} - 错误: {}", file_path, e);
-                    return Err(e.into());
-                }
-            };
-            
-            if bytes_read == 0 {
-                break;
-            }
-            
-            // 使用加密通道发送数据
-            if let Err(e) = Self::write_encrypted(&mut transport, &mut stream, &buffer[..bytes_read]).await {
-                error!("发送文件数据失败: {}", e);
-                return Err(e);
-            }
-            
-            total_sent += bytes_read;
-            
-            // 每发送 1MB 打印一次进度，避免频繁打印
-            if total_sent % (1024 * 1024) < 64 * 1024 || total_sent == file_size as usize {
-                info!("已发送: {}/{} 字节 ({:.1}%)",
-                         total_sent, file_size,
-                         (total_sent as f64 / file_size as f64) * 100.0);
-            }
-        }
-        
-        // 确保所有数据都被刷新
-        if let Err(e) = stream.flush().await {
-            error!("刷新流数据失败: {}", e);
-            return Err(e.into());
-        }
-        
-        info!("文件数据发送完成，发送传输结束信号...");
-        
-        // 发送传输结束信号
-        Self::send_transfer_complete(&mut stream).await?;
-        
-        info!("等待接收方的传输完成确认...");
-        
-        // 等待接收方的传输完成确认
-        Self::wait_for_transfer_complete(&mut stream).await?;
-        
-        info!("文件传输完成: {}", file_name);
-        Ok(())
+        Ok(results)
     }
     
-    pub async fn select_files() -> Result<Vec<String>, SendError> {
+    pub async fn select_files() -> Result<Vec<String>, NearbyError> {
         // 使用 rfd 选择多个文件
         let file_handles = rfd::AsyncFileDialog::new()
             .set_title("选择要发送的文件（可多选）")
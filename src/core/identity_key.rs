@@ -0,0 +1,218 @@
+// src/core/identity_key.rs
+// 身份密钥：身份码是 Ed25519 公钥的十六进制形式，更换身份码时由旧密钥对新身份码签名，
+// 联系人据此确认新身份码属于同一个人；身份私钥转换为 X25519 后用作 Noise 握手的静态密钥，
+// 对方由此确认连接确实来自身份码的持有者
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+
+// 签名内容的前缀，避免签名被用于其他用途
+const ROTATION_CONTEXT: &[u8] = b"nearby-identity-rotation-v1";
+
+// 对方在一次连接中最多附带的轮换证明数量
+pub const MAX_ROTATION_PROOFS: usize = 10;
+
+pub struct IdentityKey;
+
+// 轮换证明：旧身份码对应的私钥对新身份码的签名
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RotationProof {
+    pub old_identity: String,
+    pub new_identity: String,
+    pub signature: String, // 十六进制
+}
+
+impl IdentityKey {
+    // 生成新的身份密钥，返回 (身份码, 私钥)
+    pub fn generate() -> (String, [u8; 32]) {
+        let mut secret = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut secret);
+        (Self::identity_of(&secret), secret)
+    }
+    
+    // 私钥对应的身份码
+    pub fn identity_of(secret: &[u8; 32]) -> String {
+        to_hex(SigningKey::from_bytes(secret).verifying_key().as_bytes())
+    }
+    
+    // 握手使用的 X25519 静态私钥，由身份私钥按 Ed25519 到 X25519 的标准方式转换
+    pub fn noise_private_key(secret: &[u8; 32]) -> [u8; 32] {
+        SigningKey::from_bytes(secret).to_scalar_bytes()
+    }
+    
+    // 身份码对应的 X25519 公钥，身份码不是有效的公钥（早期随机生成的身份码）时返回 None
+    pub fn noise_public_key(identity: &str) -> Option<[u8; 32]> {
+        let public = from_hex::<32>(identity)?;
+        Some(VerifyingKey::from_bytes(&public).ok()?.to_montgomery().to_bytes())
+    }
+    
+    // 握手得到的对方静态公钥是否属于该身份码
+    pub fn matches_noise_key(identity: &str, remote_static: Option<&[u8]>) -> bool {
+        match (Self::noise_public_key(identity), remote_static) {
+            (Some(expected), Some(remote)) => expected.as_slice() == remote,
+            _ => false,
+        }
+    }
+    
+    // 用旧身份码的私钥为新身份码签名
+    pub fn sign_rotation(old_secret: &[u8; 32], new_identity: &str) -> RotationProof {
+        let key = SigningKey::from_bytes(old_secret);
+        let old_identity = to_hex(key.verifying_key().as_bytes());
+        let signature = key.sign(&Self::rotation_message(&old_identity, new_identity));
+        RotationProof {
+            old_identity,
+            new_identity: new_identity.to_string(),
+            signature: to_hex(&signature.to_bytes()),
+        }
+    }
+    
+    // 检查证明的签名，旧身份码不是公钥（早期随机生成的身份码）时无法验证
+    pub fn verify_rotation(proof: &RotationProof) -> bool {
+        let Some(public) = from_hex::<32>(&proof.old_identity) else { return false };
+        let Some(signature) = from_hex::<64>(&proof.signature) else { return false };
+        let Ok(key) = VerifyingKey::from_bytes(&public) else { return false };
+        key.verify(
+            &Self::rotation_message(&proof.old_identity, &proof.new_identity),
+            &Signature::from_bytes(&signature),
+        ).is_ok()
+    }
+    
+    // 从对方声称的身份码沿签名有效的证明向前查找，返回第一个满足 is_known 的旧身份码
+    pub fn resolve_chain(
+        proofs: &[RotationProof],
+        claimed: &str,
+        is_known: impl Fn(&str) -> bool,
+    ) -> Option<String> {
+        let mut current = claimed.to_string();
+        // 每条证明最多使用一次，避免循环
+        for _ in 0..proofs.len().min(MAX_ROTATION_PROOFS) {
+            let proof = proofs
+                .iter()
+                .find(|p| p.new_identity == current && Self::verify_rotation(p))?;
+            if is_known(&proof.old_identity) {
+                return Some(proof.old_identity.clone());
+            }
+            current = proof.old_identity.clone();
+        }
+        None
+    }
+    
+    // 签名内容：前缀 + 带长度的旧身份码和新身份码
+    fn rotation_message(old_identity: &str, new_identity: &str) -> Vec<u8> {
+        let mut message = ROTATION_CONTEXT.to_vec();
+        for identity in [old_identity, new_identity] {
+            message.extend_from_slice(&(identity.len() as u32).to_be_bytes());
+            message.extend_from_slice(identity.as_bytes());
+        }
+        message
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex<const N: usize>(text: &str) -> Option<[u8; N]> {
+    if text.len() != N * 2 || !text.is_ascii() {
+        return None;
+    }
+    let mut bytes = [0u8; N];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&text[i * 2..i * 2 + 2], 16).ok()?;
+    }
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::protocol::NOISE_PATTERN;
+    
+    // 用身份私钥转换的静态密钥完成一次 XX 握手，双方看到的对方静态公钥应与身份码对应
+    #[test]
+    fn handshake_static_key_matches_identity() {
+        let (initiator_identity, initiator_secret) = IdentityKey::generate();
+        let (responder_identity, responder_secret) = IdentityKey::generate();
+        let initiator_key = IdentityKey::noise_private_key(&initiator_secret);
+        let responder_key = IdentityKey::noise_private_key(&responder_secret);
+        
+        let mut initiator = snow::Builder::new(NOISE_PATTERN.parse().unwrap())
+            .local_private_key(&initiator_key)
+            .build_initiator()
+            .unwrap();
+        let mut responder = snow::Builder::new(NOISE_PATTERN.parse().unwrap())
+            .local_private_key(&responder_key)
+            .build_responder()
+            .unwrap();
+        
+        let mut message = [0u8; 1024];
+        let mut payload = [0u8; 1024];
+        let len = initiator.write_message(&[], &mut message).unwrap();
+        responder.read_message(&message[..len], &mut payload).unwrap();
+        let len = responder.write_message(&[], &mut message).unwrap();
+        initiator.read_message(&message[..len], &mut payload).unwrap();
+        let len = initiator.write_message(&[], &mut message).unwrap();
+        responder.read_message(&message[..len], &mut payload).unwrap();
+        
+        let initiator = initiator.into_transport_mode().unwrap();
+        let responder = responder.into_transport_mode().unwrap();
+        assert!(IdentityKey::matches_noise_key(&initiator_identity, responder.get_remote_static()));
+        assert!(IdentityKey::matches_noise_key(&responder_identity, initiator.get_remote_static()));
+        assert!(!IdentityKey::matches_noise_key(&responder_identity, responder.get_remote_static()));
+        assert!(!IdentityKey::matches_noise_key(&initiator_identity, None));
+    }
+    
+    #[test]
+    fn rotation_proof_verifies() {
+        let (old_identity, old_secret) = IdentityKey::generate();
+        let (new_identity, _) = IdentityKey::generate();
+        let proof = IdentityKey::sign_rotation(&old_secret, &new_identity);
+        assert_eq!(proof.old_identity, old_identity);
+        assert!(IdentityKey::verify_rotation(&proof));
+        
+        // 换成其他新身份码、篡改签名或旧身份码不是公钥时都无法验证
+        let (other_identity, _) = IdentityKey::generate();
+        assert!(!IdentityKey::verify_rotation(&RotationProof { new_identity: other_identity, ..proof.clone() }));
+        let mut signature = proof.signature.clone().into_bytes();
+        signature[0] = if signature[0] == b'0' { b'1' } else { b'0' };
+        assert!(!IdentityKey::verify_rotation(&RotationProof { signature: String::from_utf8(signature).unwrap(), ..proof.clone() }));
+        assert!(!IdentityKey::verify_rotation(&RotationProof { signature: "zz".repeat(64), ..proof.clone() }));
+        assert!(!IdentityKey::verify_rotation(&RotationProof { old_identity: "legacy".to_string(), ..proof }));
+    }
+    
+    #[test]
+    fn resolve_chain_follows_proofs() {
+        let (a, secret_a) = IdentityKey::generate();
+        let (b, secret_b) = IdentityKey::generate();
+        let (c, _) = IdentityKey::generate();
+        let proofs = vec![IdentityKey::sign_rotation(&secret_b, &c), IdentityKey::sign_rotation(&secret_a, &b)];
+        
+        assert_eq!(IdentityKey::resolve_chain(&proofs, &c, |id| id == a), Some(a.clone()));
+        assert_eq!(IdentityKey::resolve_chain(&proofs, &c, |id| id == b), Some(b.clone()));
+        assert_eq!(IdentityKey::resolve_chain(&proofs, &b, |id| id == a), Some(a.clone()));
+        // 证明链不会从旧身份码走向新身份码
+        assert_eq!(IdentityKey::resolve_chain(&proofs, &a, |id| id == c), None);
+        assert_eq!(IdentityKey::resolve_chain(&proofs, &c, |_| false), None);
+        assert_eq!(IdentityKey::resolve_chain(&[], &c, |_| true), None);
+    }
+    
+    #[test]
+    fn resolve_chain_ignores_forged_proofs() {
+        let (known, _) = IdentityKey::generate();
+        let (claimed, _) = IdentityKey::generate();
+        let (_, attacker_secret) = IdentityKey::generate();
+        let forged = RotationProof {
+            old_identity: known.clone(),
+            ..IdentityKey::sign_rotation(&attacker_secret, &claimed)
+        };
+        assert_eq!(IdentityKey::resolve_chain(&[forged], &claimed, |id| id == known), None);
+    }
+    
+    #[test]
+    fn resolve_chain_stops_on_cycles() {
+        let (a, secret_a) = IdentityKey::generate();
+        let (b, secret_b) = IdentityKey::generate();
+        let proofs = vec![IdentityKey::sign_rotation(&secret_a, &b), IdentityKey::sign_rotation(&secret_b, &a)];
+        assert_eq!(IdentityKey::resolve_chain(&proofs, &a, |_| false), None);
+    }
+}
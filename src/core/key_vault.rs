@@ -0,0 +1,84 @@
+// src/core/key_vault.rs
+// 用口令保护本地的敏感数据：口令经 Argon2id 派生出密钥，数据用 ChaCha20-Poly1305 加密；
+// 解锁后派生出的密钥只保存在内存中，轮换身份码时用它加密新的私钥
+use std::sync::Mutex;
+use argon2::Argon2;
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use rand::RngCore;
+use crate::core::error::{NearbyError, Result};
+
+pub const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+
+pub struct KeyVault;
+
+// 解锁后的身份私钥加密密钥，程序退出即丢失
+static UNLOCKED_KEY: Mutex<Option<[u8; 32]>> = Mutex::new(None);
+
+impl KeyVault {
+    // 生成新的随机盐
+    pub fn new_salt() -> [u8; SALT_LEN] {
+        let mut salt = [0u8; SALT_LEN];
+        rand::thread_rng().fill_bytes(&mut salt);
+        salt
+    }
+    
+    // 由口令和盐派生加密密钥
+    pub fn derive_key(passphrase: &str, salt: &[u8]) -> Result<[u8; 32]> {
+        if passphrase.is_empty() {
+            return Err(NearbyError::validation("口令不能为空"));
+        }
+        let mut key = [0u8; 32];
+        Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+            .map_err(|e| NearbyError::validation(format!("无法由口令派生密钥: {}", e)))?;
+        Ok(key)
+    }
+    
+    // 加密数据，结果为 随机 nonce + 密文
+    pub fn seal(key: &[u8; 32], plaintext: &[u8]) -> Vec<u8> {
+        Self::seal_with_aad(key, plaintext, &[])
+    }
+    
+    // 加密数据并认证附加数据（不加密，解密时必须提供相同的内容），结果为 随机 nonce + 密文
+    pub fn seal_with_aad(key: &[u8; 32], plaintext: &[u8], aad: &[u8]) -> Vec<u8> {
+        let mut nonce = [0u8; NONCE_LEN];
+        rand::thread_rng().fill_bytes(&mut nonce);
+        let cipher = ChaCha20Poly1305::new(&Key::from(*key));
+        // 只有明文超过 ChaCha20 的长度上限时才会失败
+        let ciphertext = cipher
+            .encrypt(&Nonce::from(nonce), Payload { msg: plaintext, aad })
+            .expect("明文过长");
+        let mut sealed = nonce.to_vec();
+        sealed.extend_from_slice(&ciphertext);
+        sealed
+    }
+    
+    // 解密 seal 的结果，密钥错误或数据被篡改时返回 None
+    pub fn open(key: &[u8; 32], sealed: &[u8]) -> Option<Vec<u8>> {
+        Self::open_with_aad(key, sealed, &[])
+    }
+    
+    // 解密 seal_with_aad 的结果，附加数据不一致时同样返回 None
+    pub fn open_with_aad(key: &[u8; 32], sealed: &[u8], aad: &[u8]) -> Option<Vec<u8>> {
+        if sealed.len() < NONCE_LEN {
+            return None;
+        }
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+        let nonce: [u8; NONCE_LEN] = nonce.try_into().ok()?;
+        ChaCha20Poly1305::new(&Key::from(*key))
+            .decrypt(&Nonce::from(nonce), Payload { msg: ciphertext, aad })
+            .ok()
+    }
+    
+    // 当前已解锁的密钥
+    pub fn unlocked_key() -> Option<[u8; 32]> {
+        *UNLOCKED_KEY.lock().unwrap()
+    }
+    
+    // 保存解锁后的密钥，None 表示清除
+    pub fn set_unlocked_key(key: Option<[u8; 32]>) {
+        *UNLOCKED_KEY.lock().unwrap() = key;
+    }
+}
@@ -0,0 +1,621 @@
+// src/core/migrations.rs
+// 基于 PRAGMA user_version 的数据库迁移
+use rusqlite::{Connection, Result, Transaction};
+use std::path::{Path, PathBuf};
+use log::{info, warn};
+use crate::core::error::NearbyError;
+
+// 一个带编号的迁移步骤，在事务中执行
+pub struct Migration {
+    pub version: i64,              // 执行后的 user_version
+    pub description: &'static str, // 说明
+    pub apply: fn(&Transaction) -> Result<()>,
+}
+
+// 所有迁移步骤，按版本号递增排列；已发布的步骤不能再修改
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "初始表结构：身份标识、好友、我的身份码、文件接收记录",
+        apply: migrate_v1,
+    },
+    Migration {
+        version: 2,
+        description: "入站连接审计表",
+        apply: migrate_v2,
+    },
+    Migration {
+        version: 3,
+        description: "程序设置表",
+        apply: migrate_v3,
+    },
+    Migration {
+        version: 4,
+        description: "文件接收记录增加同名文件处理结果",
+        apply: migrate_v4,
+    },
+    Migration {
+        version: 5,
+        description: "好友和身份标识合并为联系人",
+        apply: migrate_v5,
+    },
+    Migration {
+        version: 6,
+        description: "联系人地址增加上次成功时间",
+        apply: migrate_v6,
+    },
+    Migration {
+        version: 7,
+        description: "联系人分组",
+        apply: migrate_v7,
+    },
+    Migration {
+        version: 8,
+        description: "联系人增加端口",
+        apply: migrate_v8,
+    },
+    Migration {
+        version: 9,
+        description: "联系人增加验证状态",
+        apply: migrate_v9,
+    },
+    Migration {
+        version: 10,
+        description: "屏蔽列表",
+        apply: migrate_v10,
+    },
+    Migration {
+        version: 11,
+        description: "白名单增加有效期和次数限制",
+        apply: migrate_v11,
+    },
+    Migration {
+        version: 12,
+        description: "邀请令牌",
+        apply: migrate_v12,
+    },
+    Migration {
+        version: 13,
+        description: "身份密钥、身份码历史和收到的身份码变更",
+        apply: migrate_v13,
+    },
+    Migration {
+        version: 14,
+        description: "用口令加密身份私钥",
+        apply: migrate_v14,
+    },
+    Migration {
+        version: 15,
+        description: "文件接收记录增加发送方身份码是否经握手确认",
+        apply: migrate_v15,
+    },
+    Migration {
+        version: 16,
+        description: "收到的身份码变更区分是否有签名证明",
+        apply: migrate_v16,
+    },
+];
+
+// 当前程序支持的最新版本
+pub fn latest_version() -> i64 {
+    MIGRATIONS.last().map_or(0, |m| m.version)
+}
+
+// 读取数据库的 user_version
+pub fn current_version(conn: &Connection) -> Result<i64> {
+    conn.query_row("PRAGMA user_version", [], |row| row.get(0))
+}
+
+// 将数据库迁移到最新版本，迁移前备份数据库文件
+pub fn migrate(conn: &mut Connection, db_path: &Path) -> Result<(), NearbyError> {
+    let version = current_version(conn)?;
+    let latest = latest_version();
+    
+    if version > latest {
+        return Err(NearbyError::DatabaseVersion { found: version, supported: latest });
+    }
+    if version == latest {
+        return Ok(());
+    }
+    
+    // 已有数据的数据库才需要备份（包括没有版本号的旧数据库）
+    if has_user_tables(conn)? {
+        // WAL 模式下先把日志写回主文件，备份才是完整的
+        conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))?;
+        let backup_path = backup_database(db_path, version)?;
+        info!("迁移前已备份数据库到: {}", backup_path.display());
+    }
+    
+    for migration in MIGRATIONS.iter().filter(|m| m.version > version) {
+        info!("执行数据库迁移 v{}: {}", migration.version, migration.description);
+        let tx = conn.transaction()?;
+        (migration.apply)(&tx)?;
+        tx.pragma_update(None, "user_version", migration.version)?;
+        tx.commit()?;
+    }
+    
+    info!("数据库已从 v{} 迁移到 v{}", version, latest);
+    Ok(())
+}
+
+fn has_user_tables(conn: &Connection) -> Result<bool> {
+    let count: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%'",
+        [],
+        |row| row.get(0),
+    )?;
+    Ok(count > 0)
+}
+
+// 复制数据库文件为 address_book.db.v{版本}.bak
+fn backup_database(db_path: &Path, version: i64) -> Result<PathBuf, NearbyError> {
+    let mut backup_name = db_path.file_name().unwrap_or_default().to_os_string();
+    backup_name.push(format!(".v{}.bak", version));
+    let backup_path = db_path.with_file_name(backup_name);
+    
+    if let Err(e) = std::fs::copy(db_path, &backup_path) {
+        warn!("备份数据库失败: {}", e);
+        return Err(NearbyError::Io(e));
+    }
+    Ok(backup_path)
+}
+
+// ===== 迁移步骤 =====
+
+// v1: 旧版本没有版本号，表可能已存在，因此使用 IF NOT EXISTS
+fn migrate_v1(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS identities (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            identity TEXT NOT NULL UNIQUE,
+            alias TEXT NOT NULL,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+        CREATE TABLE IF NOT EXISTS friends (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            address TEXT NOT NULL UNIQUE,
+            alias TEXT NOT NULL,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+        CREATE TABLE IF NOT EXISTS my_identity (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            identity TEXT NOT NULL UNIQUE,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+        CREATE TABLE IF NOT EXISTS file_receive_records (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            filename TEXT NOT NULL,
+            file_size INTEGER NOT NULL,
+            sender_ipv6 TEXT NOT NULL,
+            sender_identity TEXT NOT NULL,
+            save_path TEXT NOT NULL,
+            received_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );",
+    )
+}
+
+// v2: 审计表在引入版本号之前也可能已被创建
+fn migrate_v2(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS connection_events (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            source_address TEXT NOT NULL,
+            claimed_identity TEXT NOT NULL DEFAULT '',
+            authenticated_identity TEXT NOT NULL DEFAULT '',
+            outcome TEXT NOT NULL,
+            bytes INTEGER NOT NULL DEFAULT 0,
+            occurred_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );",
+    )
+}
+
+// v3: 程序设置，按键保存文本值
+fn migrate_v3(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE settings (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL,
+            updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );",
+    )
+}
+
+// v4: 记录接收时对同名文件的处理结果
+fn migrate_v4(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        "ALTER TABLE file_receive_records ADD COLUMN conflict_resolution TEXT NOT NULL DEFAULT 'none';",
+    )
+}
+
+// v5: 联系人包含名称、身份码、多个地址、白名单标记和备注
+// 白名单中的身份标识成为受信任的联系人；好友的备注与唯一一个身份标识的备注相同时，
+// 地址并入该联系人，否则单独成为一个联系人
+fn migrate_v5(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE contacts (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL,
+            identity TEXT UNIQUE,
+            trusted INTEGER NOT NULL DEFAULT 0,
+            notes TEXT NOT NULL DEFAULT '',
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+        CREATE TABLE contact_addresses (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            contact_id INTEGER NOT NULL REFERENCES contacts(id) ON DELETE CASCADE,
+            address TEXT NOT NULL,
+            position INTEGER NOT NULL DEFAULT 0,
+            UNIQUE (contact_id, address)
+        );
+        INSERT INTO contacts (name, identity, trusted, created_at)
+            SELECT alias, identity, 1, created_at FROM identities;",
+    )?;
+    
+    let friends: Vec<(String, String, Option<String>)> = {
+        let mut stmt = tx.prepare("SELECT address, alias, created_at FROM friends ORDER BY created_at, id")?;
+        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+        rows.collect::<Result<_>>()?
+    };
+    
+    for (address, alias, created_at) in friends {
+        let matched: Vec<i64> = {
+            let mut stmt = tx.prepare("SELECT id FROM contacts WHERE identity IS NOT NULL AND name = ?1")?;
+            let rows = stmt.query_map([&alias], |row| row.get(0))?;
+            rows.collect::<Result<_>>()?
+        };
+        
+        let contact_id = if matched.len() == 1 {
+            matched[0]
+        } else {
+            tx.execute(
+                "INSERT INTO contacts (name, created_at) VALUES (?1, COALESCE(?2, CURRENT_TIMESTAMP))",
+                rusqlite::params![alias, created_at],
+            )?;
+            tx.last_insert_rowid()
+        };
+        tx.execute(
+            "INSERT OR IGNORE INTO contact_addresses (contact_id, address, position)
+             VALUES (?1, ?2, (SELECT COUNT(*) FROM contact_addresses WHERE contact_id = ?1))",
+            rusqlite::params![contact_id, address],
+        )?;
+    }
+    
+    tx.execute_batch("DROP TABLE friends; DROP TABLE identities;")
+}
+
+// v6: 记录每个地址上次发送成功的时间，发送时优先尝试
+fn migrate_v6(tx: &Transaction) -> Result<()> {
+    tx.execute_batch("ALTER TABLE contact_addresses ADD COLUMN last_success_at DATETIME;")
+}
+
+// v7: 联系人分组，删除分组或联系人时级联删除成员关系
+fn migrate_v7(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE contact_groups (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT NOT NULL UNIQUE,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );
+        CREATE TABLE contact_group_members (
+            group_id INTEGER NOT NULL REFERENCES contact_groups(id) ON DELETE CASCADE,
+            contact_id INTEGER NOT NULL REFERENCES contacts(id) ON DELETE CASCADE,
+            PRIMARY KEY (group_id, contact_id)
+        );",
+    )
+}
+
+// v8: 联系人的端口，为空时使用设置中的端口
+fn migrate_v8(tx: &Transaction) -> Result<()> {
+    tx.execute_batch("ALTER TABLE contacts ADD COLUMN port INTEGER;")
+}
+
+// v9: 联系人是否已与对方核对安全码
+fn migrate_v9(tx: &Transaction) -> Result<()> {
+    tx.execute_batch("ALTER TABLE contacts ADD COLUMN verified INTEGER NOT NULL DEFAULT 0;")
+}
+
+// v10: 在所有接收模式下都拒绝的身份码和地址段
+fn migrate_v10(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE blocklist (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            kind TEXT NOT NULL,
+            value TEXT NOT NULL,
+            note TEXT NOT NULL DEFAULT '',
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            UNIQUE (kind, value)
+        );",
+    )
+}
+
+// v11: 白名单的到期时间（UTC）和剩余次数，为空表示不限
+fn migrate_v11(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        "ALTER TABLE contacts ADD COLUMN trust_expires_at DATETIME;
+        ALTER TABLE contacts ADD COLUMN trust_uses_left INTEGER;",
+    )
+}
+
+// v12: 接收方生成的邀请令牌，持有令牌的发送方在收藏模式下也会被接收
+fn migrate_v12(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE invitations (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            token TEXT NOT NULL UNIQUE,
+            note TEXT NOT NULL DEFAULT '',
+            uses_left INTEGER,
+            expires_at DATETIME,
+            add_to_whitelist INTEGER NOT NULL DEFAULT 0,
+            created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );",
+    )
+}
+
+// v13: 我的身份码对应的私钥（早期随机生成的身份码没有私钥）、更换过的身份码，
+// 以及联系人更换身份码后等待确认的新身份码
+fn migrate_v13(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        "ALTER TABLE my_identity ADD COLUMN secret_key BLOB;
+        CREATE TABLE identity_history (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            identity TEXT NOT NULL UNIQUE,
+            successor TEXT NOT NULL,
+            signature TEXT,
+            retired_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+            revoked INTEGER NOT NULL DEFAULT 0
+        );
+        CREATE TABLE identity_rotations (
+            contact_id INTEGER PRIMARY KEY REFERENCES contacts(id) ON DELETE CASCADE,
+            new_identity TEXT NOT NULL,
+            received_at DATETIME DEFAULT CURRENT_TIMESTAMP
+        );",
+    )
+}
+
+// v14: 设置口令后 secret_key 保存加密后的私钥，key_salt 为派生密钥用的盐，为空表示未加密
+fn migrate_v14(tx: &Transaction) -> Result<()> {
+    tx.execute_batch("ALTER TABLE my_identity ADD COLUMN key_salt BLOB;")
+}
+
+// v15: 之前的记录中身份码由发送方自行声明，未经握手确认，不能据此显示已验证
+fn migrate_v15(tx: &Transaction) -> Result<()> {
+    tx.execute_batch("ALTER TABLE file_receive_records ADD COLUMN sender_authenticated INTEGER NOT NULL DEFAULT 0;")
+}
+
+// v16: 早期没有私钥的身份码更换时无法签名，这类变更只提示用户核对，之前的记录都有签名
+fn migrate_v16(tx: &Transaction) -> Result<()> {
+    tx.execute_batch("ALTER TABLE identity_rotations ADD COLUMN proven INTEGER NOT NULL DEFAULT 1;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    const IDENTITY_A: &str = "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa";
+    const IDENTITY_B: &str = "bbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbbb";
+    const IDENTITY_C: &str = "cccccccccccccccccccccccccccccccccccccccccccccccccccccccccccccccc";
+    
+    // 测试用的数据库文件，结束时连同迁移前的备份一起删除
+    struct TempDb {
+        path: PathBuf,
+    }
+    
+    impl TempDb {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!("nearby-migrations-{}-{}.db", std::process::id(), name));
+            let temp = TempDb { path };
+            temp.cleanup();
+            temp
+        }
+        
+        fn backup_path(&self, version: i64) -> PathBuf {
+            let mut name = self.path.file_name().unwrap().to_os_string();
+            name.push(format!(".v{}.bak", version));
+            self.path.with_file_name(name)
+        }
+        
+        fn cleanup(&self) {
+            std::fs::remove_file(&self.path).ok();
+            for version in 0..=latest_version() {
+                std::fs::remove_file(self.backup_path(version)).ok();
+            }
+        }
+    }
+    
+    impl Drop for TempDb {
+        fn drop(&mut self) {
+            self.cleanup();
+        }
+    }
+    
+    // 各历史版本的表结构，按发布时的数据库固定下来，不通过迁移步骤生成，
+    // 这样修改已发布的迁移步骤时测试能发现；增加迁移时把上一个最新版本的表结构加到这里
+    const PAST_SCHEMAS: &[&str] = &[
+        include_str!("schema/v0.sql"),
+        include_str!("schema/v1.sql"),
+        include_str!("schema/v2.sql"),
+        include_str!("schema/v3.sql"),
+        include_str!("schema/v4.sql"),
+        include_str!("schema/v5.sql"),
+        include_str!("schema/v6.sql"),
+        include_str!("schema/v7.sql"),
+        include_str!("schema/v8.sql"),
+        include_str!("schema/v9.sql"),
+        include_str!("schema/v10.sql"),
+        include_str!("schema/v11.sql"),
+        include_str!("schema/v12.sql"),
+        include_str!("schema/v13.sql"),
+        include_str!("schema/v14.sql"),
+        include_str!("schema/v15.sql"),
+    ];
+    
+    // 建立指定版本的数据库；v0 为引入版本号之前 init_db 创建的数据库
+    fn database_at(path: &Path, version: i64) -> Connection {
+        let conn = Connection::open(path).unwrap();
+        conn.execute_batch(PAST_SCHEMAS[version as usize]).unwrap();
+        conn.pragma_update(None, "user_version", version).unwrap();
+        conn
+    }
+    
+    // 每个表的列：名称、类型、非空、默认值、主键
+    type Columns = Vec<(String, String, bool, Option<String>, i64)>;
+    
+    fn schema(conn: &Connection) -> Vec<(String, Columns)> {
+        let tables: Vec<String> = conn
+            .prepare("SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%' ORDER BY name")
+            .unwrap()
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<Result<_>>()
+            .unwrap();
+        tables
+            .into_iter()
+            .map(|table| {
+                let columns = conn
+                    .prepare(&format!("PRAGMA table_info({})", table))
+                    .unwrap()
+                    .query_map([], |row| Ok((row.get(1)?, row.get(2)?, row.get(3)?, row.get(4)?, row.get(5)?)))
+                    .unwrap()
+                    .collect::<Result<_>>()
+                    .unwrap();
+                (table, columns)
+            })
+            .collect()
+    }
+    
+    fn fresh_schema() -> Vec<(String, Columns)> {
+        let mut conn = Connection::open_in_memory().unwrap();
+        migrate(&mut conn, Path::new(":memory:")).unwrap();
+        schema(&conn)
+    }
+    
+    #[test]
+    fn migrates_every_version_to_latest() {
+        let expected = fresh_schema();
+        for version in 0..latest_version() {
+            let temp = TempDb::new(&format!("v{}", version));
+            let mut conn = database_at(&temp.path, version);
+            
+            // 每个版本都有的数据：我的身份码、一条接收记录和一个受信任的身份码
+            conn.execute("INSERT INTO my_identity (id, identity) VALUES (1, ?1)", [IDENTITY_A]).unwrap();
+            conn.execute(
+                "INSERT INTO file_receive_records (filename, file_size, sender_ipv6, sender_identity, save_path)
+                 VALUES ('a.txt', 3, '::1', ?1, '/tmp/a.txt')",
+                [IDENTITY_B],
+            ).unwrap();
+            if version < 5 {
+                conn.execute("INSERT INTO identities (identity, alias) VALUES (?1, '张三')", [IDENTITY_B]).unwrap();
+            } else {
+                conn.execute("INSERT INTO contacts (name, identity, trusted) VALUES ('张三', ?1, 1)", [IDENTITY_B]).unwrap();
+            }
+            
+            migrate(&mut conn, &temp.path).unwrap();
+            
+            assert_eq!(current_version(&conn).unwrap(), latest_version(), "v{}", version);
+            assert_eq!(schema(&conn), expected, "v{}", version);
+            assert!(temp.backup_path(version).exists(), "v{} 迁移前没有备份", version);
+            
+            let (identity, secret_key): (String, Option<Vec<u8>>) = conn
+                .query_row("SELECT identity, secret_key FROM my_identity WHERE id = 1", [], |row| Ok((row.get(0)?, row.get(1)?)))
+                .unwrap();
+            assert_eq!(identity, IDENTITY_A);
+            assert_eq!(secret_key, None);
+            
+            let (resolution, authenticated): (String, bool) = conn
+                .query_row(
+                    "SELECT conflict_resolution, sender_authenticated FROM file_receive_records",
+                    [],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )
+                .unwrap();
+            assert_eq!(resolution, "none");
+            assert!(!authenticated);
+            
+            let (name, trusted, verified, port): (String, bool, bool, Option<u16>) = conn
+                .query_row(
+                    "SELECT name, trusted, verified, port FROM contacts WHERE identity = ?1",
+                    [IDENTITY_B],
+                    |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+                )
+                .unwrap();
+            assert_eq!((name.as_str(), trusted, verified, port), ("张三", true, false, None), "v{}", version);
+        }
+    }
+    
+    #[test]
+    fn every_past_version_has_a_schema() {
+        assert_eq!(PAST_SCHEMAS.len() as i64, latest_version());
+    }
+    
+    #[test]
+    fn latest_database_is_unchanged() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        migrate(&mut conn, Path::new(":memory:")).unwrap();
+        migrate(&mut conn, Path::new(":memory:")).unwrap();
+        assert_eq!(current_version(&conn).unwrap(), latest_version());
+    }
+    
+    #[test]
+    fn newer_database_is_rejected() {
+        let mut conn = Connection::open_in_memory().unwrap();
+        conn.pragma_update(None, "user_version", latest_version() + 1).unwrap();
+        assert!(matches!(
+            migrate(&mut conn, Path::new(":memory:")),
+            Err(NearbyError::DatabaseVersion { found, supported }) if found == latest_version() + 1 && supported == latest_version()
+        ));
+    }
+    
+    // v5 合并好友和身份标识：备注与唯一一个身份标识相同的好友并入该联系人，
+    // 与多个身份标识重名或没有对应身份标识的好友单独成为联系人
+    #[test]
+    fn v5_merges_friends_into_contacts() {
+        let temp = TempDb::new("v5-merge");
+        let mut conn = database_at(&temp.path, 4);
+        conn.execute_batch(&format!(
+            "INSERT INTO identities (identity, alias) VALUES ('{IDENTITY_A}', '张三');
+            INSERT INTO identities (identity, alias) VALUES ('{IDENTITY_B}', '李四');
+            INSERT INTO identities (identity, alias) VALUES ('{IDENTITY_C}', '李四');
+            INSERT INTO friends (address, alias) VALUES ('fe80::1', '张三');
+            INSERT INTO friends (address, alias) VALUES ('fe80::2', '李四');
+            INSERT INTO friends (address, alias) VALUES ('fe80::3', '王五');
+            INSERT INTO friends (address, alias) VALUES ('fe80::4', '王五');"
+        )).unwrap();
+        
+        migrate(&mut conn, &temp.path).unwrap();
+        
+        let contacts: Vec<(String, Option<String>, bool, String)> = conn
+            .prepare(
+                "SELECT c.name, c.identity, c.trusted, COALESCE(GROUP_CONCAT(a.address, ' '), '')
+                 FROM contacts c LEFT JOIN contact_addresses a ON a.contact_id = c.id
+                 GROUP BY c.id ORDER BY c.id",
+            )
+            .unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)))
+            .unwrap()
+            .collect::<Result<_>>()
+            .unwrap();
+        
+        let expected = [
+            ("张三", Some(IDENTITY_A), true, "fe80::1"),
+            ("李四", Some(IDENTITY_B), true, ""),
+            ("李四", Some(IDENTITY_C), true, ""),
+            ("李四", None, false, "fe80::2"),
+            ("王五", None, false, "fe80::3"),
+            ("王五", None, false, "fe80::4"),
+        ];
+        let actual: Vec<(&str, Option<&str>, bool, &str)> = contacts
+            .iter()
+            .map(|(name, identity, trusted, addresses)| (name.as_str(), identity.as_deref(), *trusted, addresses.as_str()))
+            .collect();
+        assert_eq!(actual, expected);
+        
+        let legacy_tables: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name IN ('friends', 'identities')",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(legacy_tables, 0);
+    }
+}
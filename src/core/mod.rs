@@ -2,4 +2,17 @@
 pub mod filereceiver;
 pub mod filesender;
 pub mod db;
-pub mod create_identity;
+pub mod identity_key;
+pub mod key_vault;
+pub mod backup;
+pub mod profile;
+pub mod protocol;
+pub mod peer_guard;
+pub mod migrations;
+pub mod error;
+pub mod settings;
+pub mod contact_exchange;
+pub mod audit_export;
+pub mod verification;
+pub mod receive_code;
+pub mod session_ledger;
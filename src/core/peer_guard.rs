@@ -0,0 +1,278 @@
+// src/core/peer_guard.rs
+// 按来源地址限制连接频率，并临时封禁反复握手失败或被拒绝的对端；
+// IPv6 地址按 /64 前缀统计，对端在同一网段内更换地址不能绕过限制
+use std::collections::{HashMap, VecDeque};
+use std::net::{IpAddr, Ipv6Addr};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use log::{info, warn};
+
+pub struct PeerGuard;
+
+// 频率限制与封禁设置
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PeerGuardConfig {
+    pub max_attempts: usize,  // 统计窗口内允许的最大连接次数
+    pub window: Duration,     // 统计窗口
+    pub max_failures: u32,    // 连续失败多少次后封禁
+    pub ban_duration: Duration, // 封禁时长
+}
+
+impl PeerGuardConfig {
+    pub const fn default_config() -> Self {
+        PeerGuardConfig {
+            max_attempts: 30,
+            window: Duration::from_secs(60),
+            max_failures: 5,
+            ban_duration: Duration::from_secs(10 * 60),
+        }
+    }
+}
+
+impl Default for PeerGuardConfig {
+    fn default() -> Self {
+        Self::default_config()
+    }
+}
+
+// 最多跟踪的对端数，已满时先清理过期的条目，仍然已满时拒绝新的对端
+const MAX_PEERS: usize = 4096;
+
+// 被封禁的对端
+#[derive(Debug, Clone, PartialEq)]
+pub struct BannedPeer {
+    pub address: IpAddr,      // 对端地址，IPv6 为 /64 前缀的网络地址
+    pub remaining_secs: u64,  // 剩余封禁时间（秒）
+}
+
+impl BannedPeer {
+    // 显示用的地址，IPv6 带上前缀长度
+    pub fn label(&self) -> String {
+        match self.address {
+            IpAddr::V4(address) => address.to_string(),
+            IpAddr::V6(address) => format!("{}/64", address),
+        }
+    }
+}
+
+#[derive(Default)]
+struct PeerState {
+    attempts: VecDeque<Instant>,  // 窗口内的连接时间
+    failures: u32,                // 连续失败次数
+    banned_until: Option<Instant>,
+}
+
+static CONFIG: Mutex<PeerGuardConfig> = Mutex::new(PeerGuardConfig::default_config());
+static PEERS: Mutex<Option<HashMap<IpAddr, PeerState>>> = Mutex::new(None);
+
+impl PeerGuard {
+    // 获取当前设置
+    pub fn get_config() -> PeerGuardConfig {
+        *CONFIG.lock().unwrap()
+    }
+    
+    // 修改设置，对之后的连接立即生效
+    pub fn set_config(config: PeerGuardConfig) {
+        *CONFIG.lock().unwrap() = config;
+    }
+    
+    // 统计使用的键：IPv4 地址本身（包括映射到 IPv6 的 IPv4 地址），IPv6 地址的 /64 前缀
+    pub fn peer_key(peer: IpAddr) -> IpAddr {
+        match peer {
+            IpAddr::V4(_) => peer,
+            IpAddr::V6(address) => match address.to_ipv4_mapped() {
+                Some(v4) => IpAddr::V4(v4),
+                None => IpAddr::V6(Ipv6Addr::from(u128::from(address) & !((1u128 << 64) - 1))),
+            },
+        }
+    }
+    
+    // 记录一次连接尝试，被封禁或超出频率限制时返回原因
+    pub fn check_attempt(peer: IpAddr) -> Result<(), &'static str> {
+        let config = Self::get_config();
+        let now = Instant::now();
+        let key = Self::peer_key(peer);
+        let mut peers = PEERS.lock().unwrap();
+        let peers = peers.get_or_insert_with(HashMap::new);
+        if !peers.contains_key(&key) && peers.len() >= MAX_PEERS {
+            Self::prune(peers, &config, now);
+            if peers.len() >= MAX_PEERS {
+                return Err("来源地址过多");
+            }
+        }
+        let state = peers.entry(key).or_default();
+        
+        if let Some(until) = state.banned_until {
+            if until > now {
+                return Err("该地址已被临时封禁");
+            }
+            // 封禁已到期
+            state.banned_until = None;
+            state.failures = 0;
+        }
+        
+        // 丢弃统计窗口之外的记录
+        while state.attempts.front().map_or(false, |t| now.duration_since(*t) > config.window) {
+            state.attempts.pop_front();
+        }
+        
+        if state.attempts.len() >= config.max_attempts {
+            return Err("连接过于频繁");
+        }
+        state.attempts.push_back(now);
+        Ok(())
+    }
+    
+    // 记录一次握手失败或拒绝，连续失败过多时封禁该地址
+    pub fn record_failure(peer: IpAddr) {
+        let config = Self::get_config();
+        let key = Self::peer_key(peer);
+        let mut peers = PEERS.lock().unwrap();
+        let state = peers.get_or_insert_with(HashMap::new).entry(key).or_default();
+        
+        state.failures += 1;
+        if state.failures >= config.max_failures {
+            state.banned_until = Some(Instant::now() + config.ban_duration);
+            state.failures = 0;
+            warn!("{} 连续失败 {} 次，封禁 {} 秒", key, config.max_failures, config.ban_duration.as_secs());
+        }
+    }
+    
+    // 记录一次成功的传输，清零失败计数
+    pub fn record_success(peer: IpAddr) {
+        let key = Self::peer_key(peer);
+        let mut peers = PEERS.lock().unwrap();
+        if let Some(state) = peers.as_mut().and_then(|peers| peers.get_mut(&key)) {
+            state.failures = 0;
+        }
+    }
+    
+    // 获取当前被封禁的对端
+    pub fn banned_peers() -> Vec<BannedPeer> {
+        let config = Self::get_config();
+        let now = Instant::now();
+        let mut peers = PEERS.lock().unwrap();
+        let Some(peers) = peers.as_mut() else {
+            return Vec::new();
+        };
+        
+        // 顺便清理已过期、没有任何状态的条目
+        Self::prune(peers, &config, now);
+        
+        let mut banned: Vec<BannedPeer> = peers
+            .iter()
+            .filter_map(|(address, state)| {
+                let until = state.banned_until?;
+                (until > now).then(|| BannedPeer {
+                    address: *address,
+                    remaining_secs: until.duration_since(now).as_secs(),
+                })
+            })
+            .collect();
+        banned.sort_by(|a, b| b.remaining_secs.cmp(&a.remaining_secs));
+        banned
+    }
+    
+    // 清理已过期、没有任何状态的条目
+    fn prune(peers: &mut HashMap<IpAddr, PeerState>, config: &PeerGuardConfig, now: Instant) {
+        peers.retain(|_, state| {
+            state.attempts.retain(|t| now.duration_since(*t) <= config.window);
+            state.banned_until.map_or(false, |until| until > now)
+                || state.failures > 0
+                || !state.attempts.is_empty()
+        });
+    }
+    
+    // 解除对某个地址的封禁
+    pub fn unban(peer: IpAddr) {
+        let key = Self::peer_key(peer);
+        let mut peers = PEERS.lock().unwrap();
+        if let Some(state) = peers.as_mut().and_then(|peers| peers.get_mut(&key)) {
+            state.banned_until = None;
+            state.failures = 0;
+            state.attempts.clear();
+            info!("已解除对 {} 的封禁", key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    // 各测试共用全局状态，使用互不相同的地址且不修改设置
+    fn ip(text: &str) -> IpAddr {
+        text.parse().unwrap()
+    }
+    
+    #[test]
+    fn peer_key_groups_ipv6_by_prefix() {
+        assert_eq!(PeerGuard::peer_key(ip("192.0.2.1")), ip("192.0.2.1"));
+        assert_eq!(PeerGuard::peer_key(ip("::ffff:192.0.2.1")), ip("192.0.2.1"));
+        assert_eq!(PeerGuard::peer_key(ip("2001:db8:1:2:3:4:5:6")), ip("2001:db8:1:2::"));
+        assert_eq!(PeerGuard::peer_key(ip("2001:db8:1:2::ffff")), PeerGuard::peer_key(ip("2001:db8:1:2::1")));
+        assert_ne!(PeerGuard::peer_key(ip("2001:db8:1:3::1")), PeerGuard::peer_key(ip("2001:db8:1:2::1")));
+    }
+    
+    #[test]
+    fn bans_after_max_failures() {
+        let peer = ip("192.0.2.10");
+        let config = PeerGuard::get_config();
+        for _ in 0..config.max_failures - 1 {
+            PeerGuard::record_failure(peer);
+        }
+        assert!(PeerGuard::check_attempt(peer).is_ok());
+        PeerGuard::record_failure(peer);
+        assert_eq!(PeerGuard::check_attempt(peer), Err("该地址已被临时封禁"));
+        
+        let banned = PeerGuard::banned_peers();
+        let entry = banned.iter().find(|b| b.address == peer).unwrap();
+        assert_eq!(entry.label(), "192.0.2.10");
+        assert!(entry.remaining_secs <= config.ban_duration.as_secs());
+        
+        PeerGuard::unban(peer);
+        assert!(PeerGuard::check_attempt(peer).is_ok());
+        assert!(!PeerGuard::banned_peers().iter().any(|b| b.address == peer));
+    }
+    
+    #[test]
+    fn success_resets_failures() {
+        let peer = ip("192.0.2.20");
+        let config = PeerGuard::get_config();
+        for _ in 0..config.max_failures - 1 {
+            PeerGuard::record_failure(peer);
+        }
+        PeerGuard::record_success(peer);
+        for _ in 0..config.max_failures - 1 {
+            PeerGuard::record_failure(peer);
+        }
+        assert!(PeerGuard::check_attempt(peer).is_ok());
+    }
+    
+    #[test]
+    fn ban_covers_whole_prefix() {
+        let config = PeerGuard::get_config();
+        for host in 1..=config.max_failures {
+            PeerGuard::record_failure(ip(&format!("2001:db8:10::{:x}", host)));
+        }
+        assert_eq!(PeerGuard::check_attempt(ip("2001:db8:10::abcd")), Err("该地址已被临时封禁"));
+        assert!(PeerGuard::check_attempt(ip("2001:db8:11::1")).is_ok());
+        
+        let banned = PeerGuard::banned_peers();
+        let entry = banned.iter().find(|b| b.address == ip("2001:db8:10::")).unwrap();
+        assert_eq!(entry.label(), "2001:db8:10::/64");
+        
+        PeerGuard::unban(ip("2001:db8:10::ffff"));
+        assert!(PeerGuard::check_attempt(ip("2001:db8:10::1")).is_ok());
+    }
+    
+    #[test]
+    fn limits_attempts_per_window() {
+        let config = PeerGuard::get_config();
+        for host in 0..config.max_attempts {
+            assert!(PeerGuard::check_attempt(ip(&format!("2001:db8:20::{:x}", host + 1))).is_ok());
+        }
+        assert_eq!(PeerGuard::check_attempt(ip("2001:db8:20::ffff")), Err("连接过于频繁"));
+        assert!(PeerGuard::check_attempt(ip("192.0.2.30")).is_ok());
+    }
+}
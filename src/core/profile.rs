@@ -0,0 +1,174 @@
+// src/core/profile.rs
+// 同一台电脑上的多个档案：每个档案有独立的数据库（身份密钥、通讯录、设置）和默认下载目录；
+// 默认档案沿用原来的数据目录，其他档案保存在 profiles/<名称> 下。
+// 启动时不会自动打开上次使用的档案，有多个档案时由使用者选择
+use std::path::PathBuf;
+use std::sync::Mutex;
+use dirs::data_dir;
+use log::{error, info};
+use crate::core::db::AddressBook;
+use crate::core::error::{NearbyError, Result};
+use crate::core::filereceiver::FileReceiver;
+use crate::core::key_vault::KeyVault;
+use crate::core::settings::Settings;
+
+pub const DEFAULT_PROFILE: &str = "default";
+const PROFILES_DIR: &str = "profiles";
+const MAX_NAME_LEN: usize = 32;
+
+static ACTIVE_PROFILE: Mutex<Option<String>> = Mutex::new(None);
+
+pub struct Profiles;
+
+impl Profiles {
+    // 所有档案共用的数据目录
+    fn base_dir() -> PathBuf {
+        data_dir().map(|dir| dir.join("Nearby")).unwrap_or_default()
+    }
+    
+    // 档案的数据目录
+    fn dir_of(name: &str) -> PathBuf {
+        if name == DEFAULT_PROFILE {
+            Self::base_dir()
+        } else {
+            Self::base_dir().join(PROFILES_DIR).join(name)
+        }
+    }
+    
+    // 是否已经选择了档案
+    pub fn is_open() -> bool {
+        ACTIVE_PROFILE.lock().unwrap().is_some()
+    }
+    
+    // 当前档案的名称
+    pub fn active() -> String {
+        ACTIVE_PROFILE.lock().unwrap().clone().unwrap_or_else(|| DEFAULT_PROFILE.to_string())
+    }
+    
+    // 当前档案的数据目录
+    pub fn active_dir() -> PathBuf {
+        Self::dir_of(&Self::active())
+    }
+    
+    // 所有档案，默认档案在前，其余按名称排序；只差大小写的目录只保留一个
+    pub fn list() -> Vec<String> {
+        let mut names: Vec<String> = std::fs::read_dir(Self::base_dir().join(PROFILES_DIR))
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .filter(|entry| entry.path().is_dir())
+                    .filter_map(|entry| entry.file_name().into_string().ok())
+                    .filter(|name| !name.eq_ignore_ascii_case(DEFAULT_PROFILE) && Self::validate_name(name).is_ok())
+                    .collect()
+            })
+            .unwrap_or_default();
+        names.sort_by_key(|name| name.to_ascii_lowercase());
+        names.dedup_by(|a, b| a.eq_ignore_ascii_case(b));
+        names.insert(0, DEFAULT_PROFILE.to_string());
+        names
+    }
+    
+    // 检查档案名称：英文字母、数字、- 和 _，用作目录名；
+    // 不区分大小写的文件系统上只差大小写的名称是同一个目录，比较名称时忽略大小写
+    pub fn validate_name(name: &str) -> Result<String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(NearbyError::validation("档案名称不能为空"));
+        }
+        if name.chars().count() > MAX_NAME_LEN {
+            return Err(NearbyError::validation(format!("档案名称不能超过 {} 个字符", MAX_NAME_LEN)));
+        }
+        if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+            return Err(NearbyError::validation("档案名称只能包含英文字母、数字、- 和 _"));
+        }
+        Ok(name.to_string())
+    }
+    
+    // 按名称查找已有的档案，忽略大小写，返回档案的实际名称
+    pub fn find(name: &str) -> Result<String> {
+        let name = Self::validate_name(name)?;
+        Self::list()
+            .into_iter()
+            .find(|profile| profile.eq_ignore_ascii_case(&name))
+            .ok_or_else(|| NearbyError::validation(format!("档案 {} 不存在", name)))
+    }
+    
+    // 启动时使用的档案：--profile <名称> 或 --profile=<名称>，指定的档案必须已经存在；
+    // 没有指定时只有默认档案则直接使用，有多个档案时返回 None，由启动界面选择
+    pub fn startup_profile(mut args: impl Iterator<Item = String>) -> Result<Option<String>> {
+        let mut requested = None;
+        while let Some(arg) = args.next() {
+            if arg == "--profile" {
+                let name = args.next().ok_or_else(|| NearbyError::validation("--profile 需要指定档案名称"))?;
+                requested = Some(name);
+            } else if let Some(value) = arg.strip_prefix("--profile=") {
+                requested = Some(value.to_string());
+            }
+        }
+        match requested {
+            Some(name) => Self::find(&name).map(Some),
+            None if Self::list().len() == 1 => Ok(Some(DEFAULT_PROFILE.to_string())),
+            None => Ok(None),
+        }
+    }
+    
+    // 设置当前档案，档案必须已经存在
+    fn set_active(name: &str) {
+        *ACTIVE_PROFILE.lock().unwrap() = Some(name.to_string());
+        info!("当前档案: {}", name);
+    }
+    
+    // 启动时打开档案：设置为当前档案，执行数据库迁移并加载设置
+    pub fn open(name: &str) {
+        Self::set_active(name);
+        if let Err(e) = AddressBook::init_db() {
+            error!("数据库初始化失败: {}", e);
+        }
+        // 读取保存的设置，失败时使用默认设置
+        if let Err(e) = Settings::load() {
+            error!("加载设置失败: {}", e);
+        }
+    }
+    
+    // 窗口标题，默认档案不显示名称
+    pub fn window_title(name: &str) -> String {
+        if name == DEFAULT_PROFILE {
+            "Nearby".to_string()
+        } else {
+            format!("Nearby - {}", name)
+        }
+    }
+    
+    // 新建档案，返回规范化后的名称
+    pub fn create(name: &str) -> Result<String> {
+        let name = Self::validate_name(name)?;
+        if Self::find(&name).is_ok() {
+            return Err(NearbyError::validation(format!("档案 {} 已存在", name)));
+        }
+        std::fs::create_dir_all(Self::dir_of(&name))?;
+        info!("已新建档案: {}", name);
+        Ok(name)
+    }
+    
+    // 切换到另一个档案：关闭接收功能，打开该档案的数据库并加载它的设置
+    pub async fn switch(name: &str) -> Result<()> {
+        let name = Self::find(name)?;
+        if name == Self::active() {
+            return Ok(());
+        }
+        
+        // 等待进行中的传输结束，避免写入另一个档案的接收记录
+        FileReceiver::shutdown().await;
+        AddressBook::call(move || {
+            AddressBook::reopen_database(|| {
+                Self::set_active(&name);
+                Ok(())
+            })?;
+            // 解锁的是上一个档案的私钥
+            KeyVault::set_unlocked_key(None);
+            Settings::load()?;
+            Ok(())
+        }).await?;
+        Ok(())
+    }
+}
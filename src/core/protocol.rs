@@ -0,0 +1,216 @@
+// src/core/protocol.rs
+// 发送方与接收方共用的传输信号与超时设置
+use std::future::Future;
+use std::io;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, UNIX_EPOCH};
+use sha2::{Digest, Sha256};
+use tokio::fs::File;
+use tokio::io::AsyncReadExt;
+use crate::core::error::NearbyError;
+use crate::core::identity_key::{RotationProof, MAX_ROTATION_PROOFS};
+
+/// 传输结束
+pub const SIGNAL_COMPLETE: u16 = 0;
+/// 接收方正在关闭，本次传输被取消
+pub const SIGNAL_SHUTTING_DOWN: u16 = 1;
+/// 接收方连接数已满，请稍后重试
+pub const SIGNAL_BUSY: u16 = 2;
+/// 接收方已有同名同大小的文件，需要发送方提供文件哈希
+pub const SIGNAL_NEED_HASH: u16 = 3;
+// 4 到 7 为同名文件的处理结果，见 ConflictResolution::signal
+/// 接收方接受了连接，可以开始握手
+pub const SIGNAL_READY: u16 = 8;
+/// 接收方拒绝了本次传输（接收已关闭、不在白名单中、已屏蔽或口令已被使用）
+pub const SIGNAL_REJECTED: u16 = 9;
+
+/// 默认的 Noise 握手模式
+pub const NOISE_PATTERN: &str = "Noise_XX_25519_ChaChaPoly_BLAKE2s";
+/// 使用接收口令时的握手模式，预共享密钥在第三条消息中混入
+pub const NOISE_PATTERN_PSK: &str = "Noise_XXpsk3_25519_ChaChaPoly_BLAKE2s";
+
+// 接收方对同名文件的处理结果，收到文件信息后告知发送方
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConflictResolution {
+    None,        // 没有同名文件
+    Renamed,     // 以新文件名保存
+    Overwritten, // 覆盖了已有文件
+    Skipped,     // 已有相同或更新的文件，跳过传输
+}
+
+impl ConflictResolution {
+    pub const ALL: [ConflictResolution; 4] = [
+        ConflictResolution::None,
+        ConflictResolution::Renamed,
+        ConflictResolution::Overwritten,
+        ConflictResolution::Skipped,
+    ];
+    
+    /// 数据库中保存的值
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConflictResolution::None => "none",
+            ConflictResolution::Renamed => "renamed",
+            ConflictResolution::Overwritten => "overwritten",
+            ConflictResolution::Skipped => "skipped",
+        }
+    }
+    
+    /// 从数据库中的值解析，未知的值视为 None
+    pub fn from_db_value(value: &str) -> Self {
+        Self::ALL
+            .into_iter()
+            .find(|resolution| resolution.as_str() == value)
+            .unwrap_or(ConflictResolution::None)
+    }
+    
+    /// 界面上显示的名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            ConflictResolution::None => "无冲突",
+            ConflictResolution::Renamed => "已重命名",
+            ConflictResolution::Overwritten => "已覆盖",
+            ConflictResolution::Skipped => "已跳过",
+        }
+    }
+    
+    /// 发送给对方的信号值
+    pub fn signal(&self) -> u16 {
+        match self {
+            ConflictResolution::None => 4,
+            ConflictResolution::Renamed => 5,
+            ConflictResolution::Overwritten => 6,
+            ConflictResolution::Skipped => 7,
+        }
+    }
+    
+    /// 从收到的信号解析
+    pub fn from_signal(signal: u16) -> Option<Self> {
+        Self::ALL.into_iter().find(|resolution| resolution.signal() == signal)
+    }
+}
+
+// 会话标识的最大长度
+const MAX_SESSION_LEN: usize = 64;
+
+// 发送方在第三条握手消息中附带的内容：邀请令牌、身份码轮换证明、会话标识和早期身份码
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct HandshakeExtras {
+    #[serde(default)]
+    pub invitation: String,
+    #[serde(default)]
+    pub rotation: Vec<RotationProof>,
+    #[serde(default)]
+    pub session: String, // 一次发送的所有文件共用的随机标识，白名单次数和邀请令牌按会话扣除
+    #[serde(default)]
+    pub legacy_identity: String, // 更换前没有私钥的早期身份码，无法签名，接收方只提示用户核对
+}
+
+impl HandshakeExtras {
+    /// 编码为握手负载，没有轮换证明和会话标识时只发送邀请令牌本身，与旧版本接收方兼容
+    pub fn encode(&self) -> Vec<u8> {
+        if self.rotation.is_empty() && self.session.is_empty() && self.legacy_identity.is_empty() {
+            return self.invitation.as_bytes().to_vec();
+        }
+        serde_json::to_vec(self).unwrap_or_default()
+    }
+    
+    /// 解析握手负载，不是 JSON 时整体视为邀请令牌
+    pub fn decode(payload: &[u8]) -> Self {
+        let mut extras = serde_json::from_slice::<HandshakeExtras>(payload).unwrap_or_else(|_| HandshakeExtras {
+            invitation: String::from_utf8_lossy(payload).to_string(),
+            ..HandshakeExtras::default()
+        });
+        extras.invitation = extras.invitation.trim().to_string();
+        // 会话标识不符合格式时按旧版本发送方处理，每个连接单独扣除
+        if extras.session.len() > MAX_SESSION_LEN || !extras.session.is_ascii() {
+            extras.session.clear();
+        }
+        extras.rotation.truncate(MAX_ROTATION_PROOFS);
+        // 早期身份码固定为 64 个字符
+        if extras.legacy_identity.len() != 64 || !extras.legacy_identity.is_ascii() {
+            extras.legacy_identity.clear();
+        }
+        extras
+    }
+}
+
+// 各传输阶段的超时时间
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TransferTimeouts {
+    pub handshake: Duration, // Noise 握手
+    pub metadata: Duration,  // 身份码、文件名、文件大小
+    pub idle: Duration,      // 两个数据帧之间的最长空闲时间
+}
+
+impl TransferTimeouts {
+    pub const fn default_timeouts() -> Self {
+        TransferTimeouts {
+            handshake: Duration::from_secs(10),
+            metadata: Duration::from_secs(15),
+            idle: Duration::from_secs(30),
+        }
+    }
+}
+
+impl Default for TransferTimeouts {
+    fn default() -> Self {
+        Self::default_timeouts()
+    }
+}
+
+static TIMEOUTS: Mutex<TransferTimeouts> = Mutex::new(TransferTimeouts::default_timeouts());
+
+// 获取当前的超时设置
+pub fn get_timeouts() -> TransferTimeouts {
+    *TIMEOUTS.lock().unwrap()
+}
+
+// 修改超时设置，对之后的读写立即生效
+pub fn set_timeouts(timeouts: TransferTimeouts) {
+    *TIMEOUTS.lock().unwrap() = timeouts;
+}
+
+// 计算文件哈希所允许的时间，按每秒 20MB 估算并加上空闲超时
+pub fn hash_timeout(file_size: u64) -> Duration {
+    get_timeouts().idle + Duration::from_secs(file_size / (20 * 1024 * 1024))
+}
+
+// 计算文件的 SHA-256，用于判断两个文件是否相同
+pub async fn file_sha256(path: &Path) -> io::Result<[u8; 32]> {
+    let mut file = File::open(path).await?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 256 * 1024];
+    loop {
+        let n = file.read(&mut buffer).await?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buffer[..n]);
+    }
+    Ok(hasher.finalize().into())
+}
+
+// 文件的修改时间（Unix 秒），无法获取时为 0
+pub fn modified_secs(metadata: &std::fs::Metadata) -> u64 {
+    metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map_or(0, |d| d.as_secs())
+}
+
+// 为某个传输阶段加上超时，超时后返回 NearbyError::Timeout
+pub async fn with_timeout<T, F>(duration: Duration, phase: &str, future: F) -> Result<T, NearbyError>
+where
+    F: Future<Output = Result<T, NearbyError>>,
+{
+    match tokio::time::timeout(duration, future).await {
+        Ok(result) => result,
+        Err(_) => Err(NearbyError::Timeout {
+            phase: phase.to_string(),
+            secs: duration.as_secs(),
+        }),
+    }
+}
@@ -0,0 +1,244 @@
+// src/core/receive_code.rs
+// "验证码" 接收模式的一次性口令：双方以口令导出的预共享密钥进行 XXpsk3 握手，
+// 不知道口令的一方无法完成握手
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use log::info;
+use rand::Rng;
+use sha2::{Digest, Sha256};
+
+// 口令的有效期
+pub const CODE_LIFETIME: Duration = Duration::from_secs(10 * 60);
+
+// 口令只用于一次发送：会话中的文件传输成功、且没有进行中的连接后，
+// 再等待这么久同一会话的后续连接，之后口令失效
+const SESSION_GRACE: Duration = Duration::from_secs(5);
+
+// 口令使用的字符，去掉了容易混淆的 0/O、1/I/L
+const CODE_ALPHABET: &[u8] = b"ABCDEFGHJKMNPQRSTUVWXYZ23456789";
+const CODE_LENGTH: usize = 8;
+
+// 导出预共享密钥时区分用途的前缀
+const PSK_CONTEXT: &[u8] = b"nearby-receive-code-v1";
+
+pub struct ReceiveCode;
+
+// 当前口令的状态，用于界面显示
+#[derive(Debug, Clone, PartialEq)]
+pub struct CodeStatus {
+    pub code: String,       // 显示给用户的口令，例如 ABCD-EFGH
+    pub remaining: Duration, // 剩余有效时间
+    pub used: bool,         // 是否已被某个发送方使用
+}
+
+struct ActiveCode {
+    code: String,
+    psk: [u8; 32],
+    expires_at: Instant,
+    used_by: Option<(String, String)>, // 第一个使用口令的发送方身份码和会话标识
+    connections: usize,                // 该会话进行中的连接数
+    completed: bool,                   // 该会话是否已有文件传输成功
+    idle_since: Option<Instant>,       // 传输成功后没有进行中连接的起始时间
+}
+
+// 一个使用口令的连接，结束时释放；传输成功时调用 mark_succeeded
+pub struct CodeClaim {
+    code: String,
+    succeeded: bool,
+}
+
+impl CodeClaim {
+    // 本次连接的文件传输成功，会话结束后口令失效
+    pub fn mark_succeeded(&mut self) {
+        self.succeeded = true;
+    }
+}
+
+impl Drop for CodeClaim {
+    fn drop(&mut self) {
+        let mut active = ACTIVE_CODE.lock().unwrap();
+        // 口令已被替换或失效时不做处理
+        let Some(code) = active.as_mut().filter(|code| code.code == self.code) else { return };
+        code.connections = code.connections.saturating_sub(1);
+        code.completed |= self.succeeded;
+        if code.completed && code.connections == 0 {
+            code.idle_since = Some(Instant::now());
+        }
+    }
+}
+
+static ACTIVE_CODE: Mutex<Option<ActiveCode>> = Mutex::new(None);
+
+impl ReceiveCode {
+    // 生成 length 个随机字符，每 4 个一组用连字符分隔，也用于邀请令牌
+    pub fn random_code(length: usize) -> String {
+        let mut rng = rand::thread_rng();
+        let chars: Vec<char> = (0..length)
+            .map(|_| CODE_ALPHABET[rng.gen_range(0..CODE_ALPHABET.len())] as char)
+            .collect();
+        chars
+            .chunks(4)
+            .map(|group| group.iter().collect::<String>())
+            .collect::<Vec<_>>()
+            .join("-")
+    }
+    
+    // 去掉空格和连字符并转为大写，方便对方输入
+    pub fn normalize(code: &str) -> String {
+        code.chars()
+            .filter(|c| !c.is_whitespace() && *c != '-')
+            .map(|c| c.to_ascii_uppercase())
+            .collect()
+    }
+    
+    // 生成新的口令，之前的口令立即失效
+    pub fn generate() -> String {
+        let code = Self::random_code(CODE_LENGTH);
+        
+        *ACTIVE_CODE.lock().unwrap() = Some(ActiveCode {
+            code: code.clone(),
+            psk: Self::derive_psk(&code),
+            expires_at: Instant::now() + CODE_LIFETIME,
+            used_by: None,
+            connections: 0,
+            completed: false,
+            idle_since: None,
+        });
+        info!("已生成新的接收口令，{} 分钟内有效", CODE_LIFETIME.as_secs() / 60);
+        code
+    }
+    
+    // 当前口令的状态，已过期或已用于一次发送时清除并返回 None
+    pub fn current() -> Option<CodeStatus> {
+        let mut active = ACTIVE_CODE.lock().unwrap();
+        let remaining = active.as_ref()?.expires_at.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            *active = None;
+            info!("接收口令已过期");
+            return None;
+        }
+        if active.as_ref()?.idle_since.is_some_and(|since| since.elapsed() >= SESSION_GRACE) {
+            *active = None;
+            info!("接收口令已用于一次发送，已失效");
+            return None;
+        }
+        active.as_ref().map(|code| CodeStatus {
+            code: code.code.clone(),
+            remaining,
+            used: code.used_by.is_some(),
+        })
+    }
+    
+    // 使当前口令失效
+    pub fn revoke() {
+        if ACTIVE_CODE.lock().unwrap().take().is_some() {
+            info!("接收口令已失效");
+        }
+    }
+    
+    // 接收方握手使用的预共享密钥，没有有效口令时返回 None
+    pub fn active_psk() -> Option<[u8; 32]> {
+        Self::current()?;
+        ACTIVE_CODE.lock().unwrap().as_ref().map(|code| code.psk)
+    }
+    
+    // 口令只供一次发送使用：首次使用时记录发送方的身份码和会话标识，
+    // 之后只接受同一会话的连接；没有会话标识的旧版本发送方只能使用一个连接
+    pub fn claim(identity: &str, session: &str) -> Option<CodeClaim> {
+        Self::current()?;
+        let mut active = ACTIVE_CODE.lock().unwrap();
+        let code = active.as_mut()?;
+        match &code.used_by {
+            Some((used_identity, used_session)) => {
+                if session.is_empty() || used_identity != identity || used_session != session {
+                    return None;
+                }
+            }
+            None => {
+                info!("接收口令已被 {} 使用", identity);
+                code.used_by = Some((identity.to_string(), session.to_string()));
+            }
+        }
+        code.connections += 1;
+        code.idle_since = None;
+        Some(CodeClaim { code: code.code.clone(), succeeded: false })
+    }
+    
+    // 由口令导出预共享密钥，忽略大小写、空格和连字符
+    pub fn derive_psk(code: &str) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(PSK_CONTEXT);
+        hasher.update(Self::normalize(code).as_bytes());
+        hasher.finalize().into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+    
+    #[test]
+    fn normalize_ignores_case_and_separators() {
+        assert_eq!(ReceiveCode::normalize(" abcd-ef 23 "), "ABCDEF23");
+        assert_eq!(ReceiveCode::derive_psk("abcd-ef23"), ReceiveCode::derive_psk("ABCD EF23"));
+        assert_ne!(ReceiveCode::derive_psk("ABCD-EF23"), ReceiveCode::derive_psk("ABCD-EF24"));
+    }
+    
+    #[test]
+    fn random_code_is_grouped() {
+        let code = ReceiveCode::random_code(CODE_LENGTH);
+        assert_eq!(code.len(), CODE_LENGTH + 1);
+        assert_eq!(&code[4..5], "-");
+        assert!(ReceiveCode::normalize(&code).bytes().all(|b| CODE_ALPHABET.contains(&b)));
+    }
+    
+    // 口令是全局状态，整个生命周期在一个测试中按顺序检查
+    #[test]
+    fn code_is_bound_to_one_session() {
+        let code = ReceiveCode::generate();
+        assert_eq!(ReceiveCode::active_psk(), Some(ReceiveCode::derive_psk(&code)));
+        assert!(!ReceiveCode::current().unwrap().used);
+        
+        // 首次使用后只接受同一发送方的同一会话，同一会话可以有多个连接
+        let first = ReceiveCode::claim("alice", "s1").unwrap();
+        assert!(ReceiveCode::current().unwrap().used);
+        assert!(ReceiveCode::claim("bob", "s1").is_none());
+        assert!(ReceiveCode::claim("alice", "s2").is_none());
+        assert!(ReceiveCode::claim("alice", "").is_none());
+        let second = ReceiveCode::claim("alice", "s1").unwrap();
+        
+        // 传输都失败时口令仍然有效
+        drop(first);
+        drop(second);
+        thread::sleep(SESSION_GRACE);
+        assert!(ReceiveCode::current().is_some());
+        
+        // 传输成功后，宽限期内同一会话仍可继续连接，之后口令失效
+        let mut claim = ReceiveCode::claim("alice", "s1").unwrap();
+        claim.mark_succeeded();
+        drop(claim);
+        assert!(ReceiveCode::current().is_some());
+        drop(ReceiveCode::claim("alice", "s1").unwrap());
+        thread::sleep(SESSION_GRACE);
+        assert!(ReceiveCode::current().is_none());
+        assert!(ReceiveCode::active_psk().is_none());
+        assert!(ReceiveCode::claim("alice", "s1").is_none());
+        
+        // 没有会话标识的旧版本发送方只能使用一个连接
+        ReceiveCode::generate();
+        let mut legacy = ReceiveCode::claim("carol", "").unwrap();
+        assert!(ReceiveCode::claim("carol", "").is_none());
+        
+        // 新口令生成后，旧口令的连接结束不影响新口令
+        let replaced = ReceiveCode::generate();
+        legacy.mark_succeeded();
+        drop(legacy);
+        thread::sleep(SESSION_GRACE);
+        assert_eq!(ReceiveCode::current().unwrap().code, replaced);
+        assert!(!ReceiveCode::current().unwrap().used);
+        
+        ReceiveCode::revoke();
+        assert!(ReceiveCode::current().is_none());
+    }
+}
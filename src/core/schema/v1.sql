@@ -0,0 +1,32 @@
+-- v1: 初始表结构：身份标识、好友、我的身份码、文件接收记录
+-- 该版本数据库中的表结构，用于迁移测试，不随之后的迁移修改
+
+CREATE TABLE identities (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    identity TEXT NOT NULL UNIQUE,
+    alias TEXT NOT NULL,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE friends (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    address TEXT NOT NULL UNIQUE,
+    alias TEXT NOT NULL,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE my_identity (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    identity TEXT NOT NULL UNIQUE,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE file_receive_records (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    filename TEXT NOT NULL,
+    file_size INTEGER NOT NULL,
+    sender_ipv6 TEXT NOT NULL,
+    sender_identity TEXT NOT NULL,
+    save_path TEXT NOT NULL,
+    received_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
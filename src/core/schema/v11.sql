@@ -0,0 +1,72 @@
+-- v11: 白名单增加有效期和次数限制
+-- 该版本数据库中的表结构，用于迁移测试，不随之后的迁移修改
+
+CREATE TABLE my_identity (
+    id INTEGER PRIMARY KEY CHECK (id = 1),
+    identity TEXT NOT NULL UNIQUE,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE file_receive_records (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    filename TEXT NOT NULL,
+    file_size INTEGER NOT NULL,
+    sender_ipv6 TEXT NOT NULL,
+    sender_identity TEXT NOT NULL,
+    save_path TEXT NOT NULL,
+    received_at DATETIME DEFAULT CURRENT_TIMESTAMP
+, conflict_resolution TEXT NOT NULL DEFAULT 'none');
+
+CREATE TABLE connection_events (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    source_address TEXT NOT NULL,
+    claimed_identity TEXT NOT NULL DEFAULT '',
+    authenticated_identity TEXT NOT NULL DEFAULT '',
+    outcome TEXT NOT NULL,
+    bytes INTEGER NOT NULL DEFAULT 0,
+    occurred_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE settings (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL,
+    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE contacts (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL,
+    identity TEXT UNIQUE,
+    trusted INTEGER NOT NULL DEFAULT 0,
+    notes TEXT NOT NULL DEFAULT '',
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+, port INTEGER, verified INTEGER NOT NULL DEFAULT 0, trust_expires_at DATETIME, trust_uses_left INTEGER);
+
+CREATE TABLE contact_addresses (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    contact_id INTEGER NOT NULL REFERENCES contacts(id) ON DELETE CASCADE,
+    address TEXT NOT NULL,
+    position INTEGER NOT NULL DEFAULT 0, last_success_at DATETIME,
+    UNIQUE (contact_id, address)
+);
+
+CREATE TABLE contact_groups (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE contact_group_members (
+    group_id INTEGER NOT NULL REFERENCES contact_groups(id) ON DELETE CASCADE,
+    contact_id INTEGER NOT NULL REFERENCES contacts(id) ON DELETE CASCADE,
+    PRIMARY KEY (group_id, contact_id)
+);
+
+CREATE TABLE blocklist (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    kind TEXT NOT NULL,
+    value TEXT NOT NULL,
+    note TEXT NOT NULL DEFAULT '',
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    UNIQUE (kind, value)
+);
@@ -0,0 +1,148 @@
+// src/core/session_ledger.rs
+// 白名单次数和邀请令牌按会话扣除：发送方一次发送的所有文件属于同一会话，
+// 会话中第一个文件传输成功后才扣除一次，之后同一会话的连接不再扣除。
+// 会话标识由发送方提供，扣除后只在固定的时间内有效，不随使用延长
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// 扣除后同一会话可以继续连接的时间，从扣除时开始计算
+const SESSION_WINDOW: Duration = Duration::from_secs(10 * 60);
+
+// 最多记录的会话数，超出时丢弃最早的
+const MAX_SESSIONS: usize = 1024;
+
+pub struct SessionLedger;
+
+// 会话扣除的来源
+#[derive(Debug, Clone, PartialEq)]
+pub enum PaidBy {
+    Trust(String),   // 白名单中联系人的身份码
+    Invitation(i64), // 邀请令牌的ID
+}
+
+struct PaidSession {
+    source: PaidBy,
+    paid_at: Instant,
+}
+
+// 键为 (身份码, 会话标识)
+static PAID: Mutex<Option<HashMap<(String, String), PaidSession>>> = Mutex::new(None);
+
+impl SessionLedger {
+    // 该身份码的会话在有效时间内扣除过时返回扣除的来源；没有会话标识的旧版本发送方每个连接单独计算
+    pub fn paid_by(identity: &str, session: &str) -> Option<PaidBy> {
+        if session.is_empty() {
+            return None;
+        }
+        let mut paid = PAID.lock().unwrap();
+        let paid = paid.get_or_insert_with(HashMap::new);
+        let now = Instant::now();
+        paid.retain(|_, entry| now.duration_since(entry.paid_at) < SESSION_WINDOW);
+        paid.get(&(identity.to_string(), session.to_string())).map(|entry| entry.source.clone())
+    }
+    
+    // 记录会话已经扣除，本次是该会话第一次记录、需要扣除时返回 true；
+    // 没有会话标识时总是返回 true
+    pub fn mark_paid(identity: &str, session: &str, source: PaidBy) -> bool {
+        if session.is_empty() {
+            return true;
+        }
+        let key = (identity.to_string(), session.to_string());
+        let mut paid = PAID.lock().unwrap();
+        let paid = paid.get_or_insert_with(HashMap::new);
+        let now = Instant::now();
+        paid.retain(|_, entry| now.duration_since(entry.paid_at) < SESSION_WINDOW);
+        if paid.contains_key(&key) {
+            return false;
+        }
+        if paid.len() >= MAX_SESSIONS {
+            if let Some(oldest) = paid.iter().min_by_key(|(_, entry)| entry.paid_at).map(|(key, _)| key.clone()) {
+                paid.remove(&oldest);
+            }
+        }
+        paid.insert(key, PaidSession { source, paid_at: now });
+        true
+    }
+    
+    // 清除该身份码的会话，以及由该身份码的白名单扣除的会话；白名单被移除或已失效时调用
+    pub fn forget_identity(identity: &str) {
+        if let Some(paid) = PAID.lock().unwrap().as_mut() {
+            paid.retain(|(sender, _), entry| sender != identity && entry.source != PaidBy::Trust(identity.to_string()));
+        }
+    }
+    
+    // 清除由该邀请令牌扣除的会话；令牌被撤销或已失效时调用
+    pub fn forget_invitation(id: i64) {
+        if let Some(paid) = PAID.lock().unwrap().as_mut() {
+            paid.retain(|_, entry| entry.source != PaidBy::Invitation(id));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    // 各测试共用全局状态，使用互不相同的身份码
+    fn age(identity: &str, session: &str, by: Duration) {
+        let mut paid = PAID.lock().unwrap();
+        let entry = paid.as_mut().unwrap().get_mut(&(identity.to_string(), session.to_string())).unwrap();
+        entry.paid_at = Instant::now().checked_sub(by).unwrap();
+    }
+    
+    #[test]
+    fn session_is_paid_once() {
+        assert_eq!(SessionLedger::paid_by("once", "s1"), None);
+        assert!(SessionLedger::mark_paid("once", "s1", PaidBy::Trust("once".to_string())));
+        assert!(!SessionLedger::mark_paid("once", "s1", PaidBy::Trust("once".to_string())));
+        assert_eq!(SessionLedger::paid_by("once", "s1"), Some(PaidBy::Trust("once".to_string())));
+        
+        // 其他会话和其他身份码不受影响
+        assert_eq!(SessionLedger::paid_by("once", "s2"), None);
+        assert_eq!(SessionLedger::paid_by("other", "s1"), None);
+    }
+    
+    #[test]
+    fn empty_session_always_pays() {
+        assert!(SessionLedger::mark_paid("legacy", "", PaidBy::Invitation(1)));
+        assert!(SessionLedger::mark_paid("legacy", "", PaidBy::Invitation(1)));
+        assert_eq!(SessionLedger::paid_by("legacy", ""), None);
+    }
+    
+    // 反复使用同一会话不会延长有效时间，过期后需要重新扣除
+    #[test]
+    fn reuse_does_not_extend_window() {
+        SessionLedger::mark_paid("reuse", "s1", PaidBy::Invitation(7));
+        age("reuse", "s1", SESSION_WINDOW - Duration::from_secs(1));
+        assert!(SessionLedger::paid_by("reuse", "s1").is_some());
+        assert!(!SessionLedger::mark_paid("reuse", "s1", PaidBy::Invitation(7)));
+        
+        age("reuse", "s1", SESSION_WINDOW);
+        assert_eq!(SessionLedger::paid_by("reuse", "s1"), None);
+        assert!(SessionLedger::mark_paid("reuse", "s1", PaidBy::Invitation(7)));
+    }
+    
+    #[test]
+    fn forget_identity_clears_its_sessions() {
+        SessionLedger::mark_paid("forget-a", "s1", PaidBy::Trust("forget-a".to_string()));
+        // 身份码轮换后，新身份码的会话由旧身份码的白名单扣除
+        SessionLedger::mark_paid("forget-b", "s1", PaidBy::Trust("forget-a".to_string()));
+        SessionLedger::mark_paid("forget-c", "s1", PaidBy::Trust("forget-c".to_string()));
+        
+        SessionLedger::forget_identity("forget-a");
+        assert_eq!(SessionLedger::paid_by("forget-a", "s1"), None);
+        assert_eq!(SessionLedger::paid_by("forget-b", "s1"), None);
+        assert!(SessionLedger::paid_by("forget-c", "s1").is_some());
+    }
+    
+    #[test]
+    fn forget_invitation_clears_its_sessions() {
+        SessionLedger::mark_paid("invited-a", "s1", PaidBy::Invitation(41));
+        SessionLedger::mark_paid("invited-b", "s1", PaidBy::Invitation(42));
+        
+        SessionLedger::forget_invitation(41);
+        assert_eq!(SessionLedger::paid_by("invited-a", "s1"), None);
+        assert_eq!(SessionLedger::paid_by("invited-b", "s1"), Some(PaidBy::Invitation(42)));
+    }
+}
@@ -0,0 +1,291 @@
+// src/core/settings.rs
+// 持久化的程序设置，保存在 settings 表中，修改后立即生效
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::time::Duration;
+use log::{error, info};
+use crate::core::db::AddressBook;
+use crate::core::error::{NearbyError, Result};
+use crate::core::filereceiver::{
+    FileReceiver, ReceiveStatus, DEFAULT_DRAIN_GRACE_SECS, DEFAULT_MAX_CONNECTIONS,
+    DEFAULT_MAX_CONNECTIONS_PER_PEER,
+};
+use crate::core::peer_guard::{PeerGuard, PeerGuardConfig};
+use crate::core::protocol::{self, TransferTimeouts};
+
+pub const DEFAULT_PORT: u16 = 6789;
+const DEFAULT_CONCURRENT_TRANSFERS: usize = 5;
+
+// 接收到同名文件时的处理方式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConflictPolicy {
+    Rename,          // 添加数字后缀
+    Overwrite,       // 覆盖已有文件
+    SkipIfIdentical, // 大小和哈希都相同时跳过，否则重命名
+    KeepNewer,       // 保留修改时间较新的文件
+}
+
+impl ConflictPolicy {
+    pub const ALL: [ConflictPolicy; 4] = [
+        ConflictPolicy::Rename,
+        ConflictPolicy::Overwrite,
+        ConflictPolicy::SkipIfIdentical,
+        ConflictPolicy::KeepNewer,
+    ];
+    
+    /// 数据库中保存的值
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ConflictPolicy::Rename => "rename",
+            ConflictPolicy::Overwrite => "overwrite",
+            ConflictPolicy::SkipIfIdentical => "skip_if_identical",
+            ConflictPolicy::KeepNewer => "keep_newer",
+        }
+    }
+    
+    /// 从数据库中的值解析，未知的值视为 Rename
+    pub fn from_db_value(value: &str) -> Self {
+        Self::ALL
+            .into_iter()
+            .find(|policy| policy.as_str() == value)
+            .unwrap_or(ConflictPolicy::Rename)
+    }
+    
+    /// 界面上显示的名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            ConflictPolicy::Rename => "重命名（添加数字后缀）",
+            ConflictPolicy::Overwrite => "覆盖已有文件",
+            ConflictPolicy::SkipIfIdentical => "内容相同时跳过",
+            ConflictPolicy::KeepNewer => "保留较新的文件",
+        }
+    }
+}
+
+// 按发送方分文件夹的方式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SenderFolder {
+    None,     // 不按发送方分
+    Alias,    // 联系人名称，不在联系人中时使用身份码前缀
+    Identity, // 身份码
+}
+
+impl SenderFolder {
+    pub const ALL: [SenderFolder; 3] = [SenderFolder::None, SenderFolder::Alias, SenderFolder::Identity];
+    
+    /// 数据库中保存的值
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SenderFolder::None => "none",
+            SenderFolder::Alias => "alias",
+            SenderFolder::Identity => "identity",
+        }
+    }
+    
+    /// 从数据库中的值解析，未知的值视为 None
+    pub fn from_db_value(value: &str) -> Self {
+        Self::ALL
+            .into_iter()
+            .find(|folder| folder.as_str() == value)
+            .unwrap_or(SenderFolder::None)
+    }
+    
+    /// 界面上显示的名称
+    pub fn label(&self) -> &'static str {
+        match self {
+            SenderFolder::None => "不区分",
+            SenderFolder::Alias => "按联系人名称",
+            SenderFolder::Identity => "按身份码",
+        }
+    }
+}
+
+// 接收文件的分类规则，按 发送方/日期/扩展名 的顺序生成子文件夹
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RoutingRules {
+    pub by_sender: SenderFolder, // 按发送方
+    pub by_date: bool,           // 按接收日期（年-月-日）
+    pub by_extension: bool,      // 按扩展名
+}
+
+impl Default for RoutingRules {
+    fn default() -> Self {
+        RoutingRules {
+            by_sender: SenderFolder::None,
+            by_date: false,
+            by_extension: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Settings {
+    pub display_name: String,              // 显示名称
+    pub startup_receive_mode: ReceiveStatus, // 启动时的接收模式
+    pub download_dir: String,              // 下载目录，为空时使用默认目录
+    pub routing: RoutingRules,             // 下载目录下的分类规则
+    pub port: u16,                         // 监听和连接的端口
+    pub conflict_policy: ConflictPolicy,   // 同名文件的处理方式
+    pub concurrent_transfers: usize,       // 发送时同时传输的文件数
+    pub max_connections: usize,            // 接收时的最大并发连接数
+    pub max_connections_per_peer: usize,   // 每个对端地址的最大并发连接数
+    pub peer_guard: PeerGuardConfig,       // 连接频率限制与封禁
+    pub timeouts: TransferTimeouts,        // 各传输阶段的超时
+    pub drain_grace_secs: u64,             // 关闭时等待传输结束的宽限期（秒）
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            display_name: String::new(),
+            startup_receive_mode: ReceiveStatus::Closed,
+            download_dir: String::new(),
+            routing: RoutingRules::default(),
+            port: DEFAULT_PORT,
+            conflict_policy: ConflictPolicy::Rename,
+            concurrent_transfers: DEFAULT_CONCURRENT_TRANSFERS,
+            max_connections: DEFAULT_MAX_CONNECTIONS,
+            max_connections_per_peer: DEFAULT_MAX_CONNECTIONS_PER_PEER,
+            peer_guard: PeerGuardConfig::default(),
+            timeouts: TransferTimeouts::default(),
+            drain_grace_secs: DEFAULT_DRAIN_GRACE_SECS,
+        }
+    }
+}
+
+// 当前生效的设置
+static CURRENT: Mutex<Option<Settings>> = Mutex::new(None);
+
+impl Settings {
+    // 获取当前生效的设置
+    pub fn current() -> Settings {
+        CURRENT.lock().unwrap().clone().unwrap_or_default()
+    }
+    
+    // 从数据库读取设置并使之生效，缺失或无效的项使用默认值
+    pub fn load() -> Result<Settings> {
+        let values = AddressBook::get_settings()?;
+        let settings = Self::from_values(&values);
+        settings.apply();
+        *CURRENT.lock().unwrap() = Some(settings.clone());
+        info!("已加载程序设置");
+        Ok(settings)
+    }
+    
+    // 检查并保存设置，保存成功后立即生效
+    pub fn save(&self) -> Result<()> {
+        self.validate()?;
+        AddressBook::save_settings(&self.to_values())?;
+        
+        let previous = CURRENT.lock().unwrap().replace(self.clone()).unwrap_or_default();
+        self.apply();
+        
+        // 端口变化时重新启动正在运行的接收服务器
+        if previous.port != self.port {
+            tokio::spawn(async {
+                if let Err(e) = FileReceiver::restart_server().await {
+                    error!("重新启动接收服务器失败: {}", e);
+                }
+            });
+        }
+        Ok(())
+    }
+    
+    fn validate(&self) -> Result<()> {
+        if self.port == 0 {
+            return Err(NearbyError::validation("端口不能为 0"));
+        }
+        if self.concurrent_transfers == 0 || self.max_connections == 0 || self.max_connections_per_peer == 0 {
+            return Err(NearbyError::validation("并发数至少为 1"));
+        }
+        if self.peer_guard.max_attempts == 0 || self.peer_guard.max_failures == 0 {
+            return Err(NearbyError::validation("频率限制次数至少为 1"));
+        }
+        let timeouts = [self.timeouts.handshake, self.timeouts.metadata, self.timeouts.idle];
+        if timeouts.iter().any(|t| t.is_zero()) {
+            return Err(NearbyError::validation("超时时间至少为 1 秒"));
+        }
+        Ok(())
+    }
+    
+    // 把设置同步到各个模块的运行时状态
+    fn apply(&self) {
+        FileReceiver::set_max_connections(self.max_connections);
+        FileReceiver::set_max_connections_per_peer(self.max_connections_per_peer);
+        FileReceiver::set_drain_grace_period(self.drain_grace_secs);
+        PeerGuard::set_config(self.peer_guard);
+        protocol::set_timeouts(self.timeouts);
+    }
+    
+    fn to_values(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("display_name", self.display_name.clone()),
+            ("startup_receive_mode", self.startup_receive_mode.as_str().to_string()),
+            ("download_dir", self.download_dir.clone()),
+            ("route_by_sender", self.routing.by_sender.as_str().to_string()),
+            ("route_by_date", self.routing.by_date.to_string()),
+            ("route_by_extension", self.routing.by_extension.to_string()),
+            ("port", self.port.to_string()),
+            ("conflict_policy", self.conflict_policy.as_str().to_string()),
+            ("concurrent_transfers", self.concurrent_transfers.to_string()),
+            ("max_connections", self.max_connections.to_string()),
+            ("max_connections_per_peer", self.max_connections_per_peer.to_string()),
+            ("rate_max_attempts", self.peer_guard.max_attempts.to_string()),
+            ("rate_window_secs", self.peer_guard.window.as_secs().to_string()),
+            ("ban_max_failures", self.peer_guard.max_failures.to_string()),
+            ("ban_duration_secs", self.peer_guard.ban_duration.as_secs().to_string()),
+            ("handshake_timeout_secs", self.timeouts.handshake.as_secs().to_string()),
+            ("metadata_timeout_secs", self.timeouts.metadata.as_secs().to_string()),
+            ("idle_timeout_secs", self.timeouts.idle.as_secs().to_string()),
+            ("drain_grace_secs", self.drain_grace_secs.to_string()),
+        ]
+    }
+    
+    fn from_values(values: &HashMap<String, String>) -> Settings {
+        let defaults = Settings::default();
+        
+        // 读取数值或布尔值，缺失或无法解析时使用默认值
+        fn parsed<T: FromStr>(values: &HashMap<String, String>, key: &str, default: T) -> T {
+            values.get(key).and_then(|v| v.parse().ok()).unwrap_or(default)
+        }
+        let secs = |key: &str, default: Duration| Duration::from_secs(parsed(values, key, default.as_secs()));
+        
+        Settings {
+            display_name: values.get("display_name").cloned().unwrap_or(defaults.display_name),
+            startup_receive_mode: values
+                .get("startup_receive_mode")
+                .map(|v| ReceiveStatus::from_db_value(v))
+                .unwrap_or(defaults.startup_receive_mode),
+            download_dir: values.get("download_dir").cloned().unwrap_or(defaults.download_dir),
+            routing: RoutingRules {
+                by_sender: values
+                    .get("route_by_sender")
+                    .map(|v| SenderFolder::from_db_value(v))
+                    .unwrap_or(defaults.routing.by_sender),
+                by_date: parsed(values, "route_by_date", defaults.routing.by_date),
+                by_extension: parsed(values, "route_by_extension", defaults.routing.by_extension),
+            },
+            port: parsed(values, "port", defaults.port),
+            conflict_policy: values
+                .get("conflict_policy")
+                .map(|v| ConflictPolicy::from_db_value(v))
+                .unwrap_or(defaults.conflict_policy),
+            concurrent_transfers: parsed(values, "concurrent_transfers", defaults.concurrent_transfers),
+            max_connections: parsed(values, "max_connections", defaults.max_connections),
+            max_connections_per_peer: parsed(values, "max_connections_per_peer", defaults.max_connections_per_peer),
+            peer_guard: PeerGuardConfig {
+                max_attempts: parsed(values, "rate_max_attempts", defaults.peer_guard.max_attempts),
+                window: secs("rate_window_secs", defaults.peer_guard.window),
+                max_failures: parsed(values, "ban_max_failures", defaults.peer_guard.max_failures),
+                ban_duration: secs("ban_duration_secs", defaults.peer_guard.ban_duration),
+            },
+            timeouts: TransferTimeouts {
+                handshake: secs("handshake_timeout_secs", defaults.timeouts.handshake),
+                metadata: secs("metadata_timeout_secs", defaults.timeouts.metadata),
+                idle: secs("idle_timeout_secs", defaults.timeouts.idle),
+            },
+            drain_grace_secs: parsed(values, "drain_grace_secs", defaults.drain_grace_secs),
+        }
+    }
+}
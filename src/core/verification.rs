@@ -0,0 +1,62 @@
+// src/core/verification.rs
+// 安全码：由双方握手使用的静态公钥导出的短码，双方看到的安全码一致即说明添加的是正确的设备
+use sha2::{Digest, Sha256};
+use crate::core::identity_key::IdentityKey;
+
+// 区分用途的前缀，修改后所有安全码都会变化
+const SHORT_CODE_CONTEXT: &[u8] = b"nearby-short-code-v2";
+
+pub struct Verification;
+
+impl Verification {
+    // 与参数顺序无关，双方各自计算得到相同的结果，格式为 "123 456"；
+    // 使用身份码转换得到的 Noise 静态公钥，连接时握手已证明对方持有对应的私钥。
+    // 任一身份码不是有效的公钥时返回 None
+    pub fn short_code(identity_a: &str, identity_b: &str) -> Option<String> {
+        let key_a = IdentityKey::noise_public_key(identity_a)?;
+        let key_b = IdentityKey::noise_public_key(identity_b)?;
+        let (first, second) = if key_a <= key_b { (key_a, key_b) } else { (key_b, key_a) };
+        
+        let mut hasher = Sha256::new();
+        hasher.update(SHORT_CODE_CONTEXT);
+        hasher.update(first);
+        hasher.update(second);
+        let digest = hasher.finalize();
+        
+        let value = u32::from_be_bytes([digest[0], digest[1], digest[2], digest[3]]) % 1_000_000;
+        Some(format!("{:03} {:03}", value / 1000, value % 1000))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn short_code_is_symmetric() {
+        let (a, _) = IdentityKey::generate();
+        let (b, _) = IdentityKey::generate();
+        let code = Verification::short_code(&a, &b).unwrap();
+        assert_eq!(Verification::short_code(&b, &a), Some(code.clone()));
+        assert_eq!(code.len(), 7);
+        assert!(code[..3].chars().all(|c| c.is_ascii_digit()));
+        assert_eq!(&code[3..4], " ");
+        assert!(code[4..].chars().all(|c| c.is_ascii_digit()));
+    }
+    
+    #[test]
+    fn short_code_depends_on_both_keys() {
+        let (a, _) = IdentityKey::generate();
+        let (b, _) = IdentityKey::generate();
+        let (c, _) = IdentityKey::generate();
+        assert_ne!(Verification::short_code(&a, &b), Verification::short_code(&a, &c));
+    }
+    
+    #[test]
+    fn short_code_requires_public_keys() {
+        let (a, _) = IdentityKey::generate();
+        assert_eq!(Verification::short_code(&a, "not-a-key"), None);
+        assert_eq!(Verification::short_code("", &a), None);
+        assert_eq!(Verification::short_code(&a, &"g".repeat(64)), None);
+    }
+}
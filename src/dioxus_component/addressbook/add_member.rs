@@ -1,98 +1,91 @@
 // src/dioxus_component/addressbook/add_modal.rs
 use dioxus::prelude::*;
-use crate::core::db::AddressBook;
+use crate::core::contact_exchange::{ContactExchange, ExportedContact};
+use chrono::{Local, NaiveDate, NaiveDateTime, Utc};
+use crate::core::db::{AddressBook, Contact, DB_TIME_FORMAT};
+use crate::core::settings::Settings;
+use crate::core::verification::Verification;
+use crate::dioxus_component::error_message::describe_error;
+
+const LABEL_STYLE: &str = "
+    display: block;
+    margin-bottom: 6px;
+    font-weight: 500;
+    color: #374151;
+";
+
+const FIELD_STYLE: &str = "
+    width: 90%;
+    padding: 10px 12px;
+    border: 1px solid #d1d5db;
+    border-radius: 8px;
+    font-size: 14px;
+";
 
 #[component]
-pub fn AddModal(on_close: EventHandler, active_tab: &'static str, on_success: EventHandler) -> Element {
-	let mut current_step = use_signal(|| if active_tab == "friends" { "friend" } else { "identity" });
-	let mut address = use_signal(|| String::new());
-	let mut alias = use_signal(|| String::new());
-	let mut identity = use_signal(|| String::new());
-	let mut error_message = use_signal(|| String::new());
-	let success_message = use_signal(|| String::new());
+pub fn AddModal(on_close: EventHandler, on_success: EventHandler) -> Element {
+	let mut form = use_signal(Contact::default);
+	let mut error_message = use_signal(String::new);
+	let mut contact_uri = use_signal(String::new);
 	
-	let add_friend = move |_| {
-		let address_val = address.read().clone();
-		let alias_val = alias.read().clone();
-		
-		if address_val.trim().is_empty() {
-			error_message.set("IPv6地址不能为空".to_string());
-			return;
-		}
-		
-		if alias_val.trim().is_empty() {
-			error_message.set("好友昵称不能为空".to_string());
-			return;
+	// 用联系人链接中的内容填写表单
+	let mut fill_from = move |card: ExportedContact| {
+		form.set(Contact {
+			name: card.name,
+			identity: card.identity,
+			addresses: card.addresses,
+			port: card.port,
+			..Contact::default()
+		});
+		error_message.set(String::new());
+	};
+	
+	let parse_uri = move |_| {
+		match ContactExchange::parse_uri(&contact_uri.read()) {
+			Ok(card) => fill_from(card),
+			Err(e) => error_message.set(format!("无法解析联系人链接: {}", describe_error(&e))),
 		}
-		
-		let mut error_message = error_message.to_owned();
-		let mut success_message = success_message.to_owned();
-		let on_close = on_close.to_owned();
-		let on_success = on_success.to_owned();
-		
+	};
+	
+	// 从二维码图片中读取联系人链接
+	let load_image = move |_| {
 		spawn(async move {
-			match tokio::task::spawn_blocking(move || {
-				AddressBook::add_friend(&address_val, &alias_val)
-			}).await {
-				Ok(Ok(())) => {
-					success_message.set("好友添加成功".to_string());
-					error_message.set(String::new());
-					on_success.call(());
-					on_close.call(());
-				}
-				Ok(Err(e)) => {
-					error_message.set(format!("添加失败: {}", e));
-					success_message.set(String::new());
-				}
+			let file = rfd::AsyncFileDialog::new()
+				.set_title("选择联系人二维码图片")
+				.add_filter("图片", &["png", "jpg", "jpeg"])
+				.pick_file()
+				.await;
+			let Some(file) = file else { return };
+			
+			let path = file.path().to_path_buf();
+			let result = match tokio::task::spawn_blocking(move || ContactExchange::read_qr_image(&path)).await {
+				Ok(result) => result.and_then(|uri| {
+					contact_uri.set(uri.clone());
+					ContactExchange::parse_uri(&uri)
+				}),
 				Err(e) => {
-					error_message.set(format!("任务执行失败: {}", e));
-					success_message.set(String::new());
+					error_message.set(format!("读取图片失败: {}", e));
+					return;
 				}
+			};
+			match result {
+				Ok(card) => fill_from(card),
+				Err(e) => error_message.set(format!("无法识别联系人二维码: {}", describe_error(&e))),
 			}
 		});
 	};
 	
-	let add_identity = move |_| {
-		let identity_val = identity.read().clone();
-		let alias_val = alias.read().clone();
-		
-		if identity_val.trim().is_empty() {
-			error_message.set("身份标识不能为空".to_string());
-			return;
-		}
-		
-		if identity_val.trim().len() != 64 {
-			error_message.set("身份标识必须为64个字符".to_string());
-			return;
-		}
-		
-		if alias_val.trim().is_empty() {
-			error_message.set("别名不能为空".to_string());
-			return;
-		}
-		
-		let mut error_message = error_message.to_owned();
-		let mut success_message = success_message.to_owned();
-		let on_close = on_close.to_owned();
-		let on_success = on_success.to_owned();
-		
+	let add_contact = move |_| {
+		let contact = form.read().clone();
 		spawn(async move {
-			match tokio::task::spawn_blocking(move || {
-				AddressBook::add_identity(&identity_val, &alias_val)
-			}).await {
-				Ok(Ok(())) => {
-					success_message.set("身份标识添加成功".to_string());
+			match AddressBook::call(move || AddressBook::add_contact(&contact)).await {
+				Ok(_) => {
 					error_message.set(String::new());
 					on_success.call(());
 					on_close.call(());
 				}
-				Ok(Err(e)) => {
-					error_message.set(format!("添加失败: {}", e));
-					success_message.set(String::new());
-				}
 				Err(e) => {
-					error_message.set(format!("任务执行失败: {}", e));
-					success_message.set(String::new());
+					error_message.set(format!("添加失败: {}", describe_error(&e)));
 				}
 			}
 		});
@@ -121,7 +114,7 @@ pub fn AddModal(on_close: EventHandler, active_tab: &'static str, on_success: Ev
                     max-width: 500px;
                     width: 90%;
                     max-height: 90vh;
-                    overflow: hidden;
+                    overflow-y: auto;
                 ",
                 
                 // 模态窗口头部
@@ -136,11 +129,11 @@ pub fn AddModal(on_close: EventHandler, active_tab: &'static str, on_success: Ev
                     
                     h2 {
                         style: "margin: 0; font-size: 18px; font-weight: 600;",
-                        "添加新条目"
+                        "添加联系人"
                     }
                     
                     button {
-						class: "modal-close-button",
+                        class: "modal-close-button",
                         style: "
                             background: none;
                             border: none;
@@ -157,252 +150,284 @@ pub fn AddModal(on_close: EventHandler, active_tab: &'static str, on_success: Ev
                 div {
                     style: "padding: 24px;",
                     
-                    // 步骤选择
+                    // 错误消息
+                    if !error_message.read().is_empty() {
+                        div {
+                            style: "
+                                background: #fee2e2;
+                                border: 1px solid #fecaca;
+                                color: #dc2626;
+                                padding: 12px 16px;
+                                border-radius: 8px;
+                                margin-bottom: 20px;
+                            ",
+                            {error_message.read().as_str()}
+                        }
+                    }
+                    
+                    // 从联系人链接或二维码填写
                     div {
                         style: "
-                            display: flex;
-                            gap: 8px;
-                            margin-bottom: 24px;
+                            margin-bottom: 20px;
+                            padding-bottom: 20px;
+                            border-bottom: 1px solid #e5e7eb;
                         ",
-                        
-                        button {
-							class: "tab-button",
-                            style: if *current_step.read() == "friend" {
-                                "
-                                    flex: 1;
-                                    background: #3b82f6;
+                        label { style: LABEL_STYLE, "从联系人链接添加" }
+                        div {
+                            style: "display: flex; gap: 8px;",
+                            input {
+                                style: "{FIELD_STYLE} flex: 1; font-family: monospace;",
+                                r#type: "text",
+                                placeholder: "粘贴 nearby://contact?... 链接",
+                                value: "{contact_uri}",
+                                oninput: move |e| contact_uri.set(e.value())
+                            }
+                            button {
+                                class: "modal-secondary-button",
+                                style: "
+                                    padding: 8px 12px;
+                                    background: #6b7280;
                                     color: white;
                                     border: none;
-                                    padding: 12px;
                                     border-radius: 8px;
                                     cursor: pointer;
-                                    font-weight: 500;
-                                "
-                            } else {
-                                "
-                                    flex: 1;
-                                    background: #f3f4f6;
-                                    color: #374151;
-                                    border: none;
-                                    padding: 12px;
-                                    border-radius: 8px;
-                                    cursor: pointer;
-                                    font-weight: 500;
-                                "
-                            },
-                            onclick: move |_| current_step.set("friend"),
-                            "添加好友"
-                        }
-                        
-                        button {
-							class: "tab-button",
-                            style: if *current_step.read() == "identity" {
-                                "
-                                    flex: 1;
-                                    background: #3b82f6;
+                                    white-space: nowrap;
+                                ",
+                                onclick: parse_uri,
+                                "解析"
+                            }
+                            button {
+                                class: "modal-secondary-button",
+                                style: "
+                                    padding: 8px 12px;
+                                    background: #6b7280;
                                     color: white;
                                     border: none;
-                                    padding: 12px;
-                                    border-radius: 8px;
-                                    cursor: pointer;
-                                    font-weight: 500;
-                                "
-                            } else {
-                                "
-                                    flex: 1;
-                                    background: #f3f4f6;
-                                    color: #374151;
-                                    border: none;
-                                    padding: 12px;
                                     border-radius: 8px;
                                     cursor: pointer;
-                                    font-weight: 500;
-                                "
-                            },
-                            onclick: move |_| current_step.set("identity"),
-                            "添加身份"
+                                    white-space: nowrap;
+                                ",
+                                onclick: load_image,
+                                "读取二维码图片"
+                            }
                         }
                     }
                     
-                    // 错误和成功消息
-                    if !error_message.read().is_empty() {
-                        div {
-                            style: "
-                                background: #fee2e2;
-                                border: 1px solid #fecaca;
-                                color: #dc2626;
-                                padding: 12px 16px;
-                                border-radius: 8px;
-                                margin-bottom: 20px;
-                            ",
-                            {error_message.read().as_str()}
-                        }
+                    ContactForm { contact: form }
+                    
+                    button {
+                        class: "modal-button",
+                        style: "
+                            width: 100%;
+                            background: #3b82f6;
+                            color: white;
+                            border: none;
+                            border-radius: 8px;
+                            padding: 12px;
+                            font-weight: 500;
+                            cursor: pointer;
+                            margin-top: 16px;
+                        ",
+                        onclick: add_contact,
+                        "添加联系人"
                     }
+                }
+            }
+        }
+    }
+}
 
-                    if !success_message.read().is_empty() {
+// 联系人的编辑表单，添加和编辑共用
+#[component]
+pub fn ContactForm(contact: Signal<Contact>) -> Element {
+	let mut contact = contact;
+	let mut my_identity = use_signal(String::new);
+	
+	use_hook(move || {
+		spawn(async move {
+			if let Ok(identity) = AddressBook::call(AddressBook::get_my_identity).await {
+				my_identity.set(identity);
+			}
+		});
+	});
+	
+	// 身份码是有效的公钥时显示安全码，供双方当面或通过电话核对
+	let short_code = {
+		let identity = contact.read().identity.trim().to_string();
+		let mine = my_identity.read();
+		Verification::short_code(&mine, &identity)
+	};
+	
+	// 地址每行一个，空行在保存时忽略
+	let addresses = contact.read().addresses.join("\n");
+	let port = contact.read().port.map(|p| p.to_string()).unwrap_or_default();
+	let default_port = Settings::current().port;
+	let trust_expires_date = contact.read().trust_expires_at.as_deref().map(expiry_to_local_date).unwrap_or_default();
+	let trust_uses_left = contact.read().trust_uses_left.map(|n| n.to_string()).unwrap_or_default();
+	
+	rsx! {
+        div {
+            style: "display: flex; flex-direction: column; gap: 16px;",
+            
+            div {
+                label { style: LABEL_STYLE, "名称" }
+                input {
+                    style: FIELD_STYLE,
+                    r#type: "text",
+                    placeholder: "例如: 张三",
+                    value: "{contact.read().name}",
+                    oninput: move |e| contact.write().name = e.value()
+                }
+            }
+            
+            div {
+                label { style: LABEL_STYLE, "身份码 (64字符，可选)" }
+                input {
+                    style: "{FIELD_STYLE} font-family: monospace;",
+                    r#type: "text",
+                    placeholder: "输入对方的64个字符的身份码...",
+                    value: "{contact.read().identity}",
+                    oninput: move |e| {
+                        // 身份码变化后需要重新核对
+                        let mut contact = contact.write();
+                        contact.identity = e.value();
+                        contact.verified = false;
+                    }
+                }
+                if let Some(code) = short_code {
+                    div {
+                        style: "
+                            margin-top: 8px;
+                            padding: 10px 12px;
+                            background: #eff6ff;
+                            border: 1px solid #bfdbfe;
+                            border-radius: 8px;
+                        ",
+                        div {
+                            style: "font-size: 12px; color: #1e40af;",
+                            "安全码（对方添加你或查看你的接收记录时会看到相同的安全码）"
+                        }
                         div {
                             style: "
-                                background: #dcfce7;
-                                border: 1px solid #bbf7d0;
-                                color: #16a34a;
-                                padding: 12px 16px;
-                                border-radius: 8px;
-                                margin-bottom: 20px;
+                                font-family: monospace;
+                                font-size: 22px;
+                                letter-spacing: 4px;
+                                color: #1e3a8a;
+                                margin: 4px 0;
                             ",
-                            {success_message.read().as_str()}
+                            "{code}"
                         }
-                    }
-                    
-                    // 表单内容
-                    match *current_step.read() {
-                        "friend" => rsx! {
-                            div {
-                                style: "space-y-4",
-                                
-                                div {
-                                    label {
-                                        style: "
-                                            display: block;
-                                            margin-bottom: 6px;
-                                            font-weight: 500;
-                                            color: #374151;
-                                        ",
-                                        "IPv6 地址"
-                                    }
-                                    input {
-                                        style: "
-                                            width: 90%;
-                                            padding: 10px 12px;
-                                            border: 1px solid #d1d5db;
-                                            border-radius: 8px;
-                                            font-size: 14px;
-                                        ",
-                                        r#type: "text",
-                                        placeholder: "例如: ::1 或 fe80::...",
-                                        value: "{address}",
-                                        oninput: move |e| address.set(e.value().clone())
-                                    }
-                                }
-                                
-                                div {
-                                    label {
-                                        style: "
-                                            display: block;
-                                            margin-bottom: 6px;
-                                            font-weight: 500;
-                                            color: #374151;
-                                        ",
-                                        "好友昵称"
-                                    }
-                                    input {
-                                        style: "
-                                            width: 90%;
-                                            padding: 10px 12px;
-                                            border: 1px solid #d1d5db;
-                                            border-radius: 8px;
-                                            font-size: 14px;
-                                        ",
-                                        r#type: "text",
-                                        placeholder: "例如: 张三的电脑",
-                                        value: "{alias}",
-                                        oninput: move |e| alias.set(e.value().clone())
-                                    }
-                                }
-                                
-                                button {
-									class: "modal-button",
-                                    style: "
-                                        width: 100%;
-                                        background: #3b82f6;
-                                        color: white;
-                                        border: none;
-                                        border-radius: 8px;
-                                        padding: 12px;
-                                        font-weight: 500;
-                                        cursor: pointer;
-                                        margin-top: 16px;
-                                    ",
-                                    onclick: add_friend,
-                                    "添加好友"
-                                }
+                        label {
+                            style: "display: flex; align-items: center; gap: 8px; font-size: 14px; color: #374151;",
+                            input {
+                                r#type: "checkbox",
+                                checked: contact.read().verified,
+                                onchange: move |e| contact.write().verified = e.checked(),
                             }
-                        },
-                        "identity" => rsx! {
-                            div {
-                                style: "space-y-4",
-                                
-                                div {
-                                    label {
-                                        style: "
-                                            display: block;
-                                            margin-bottom: 6px;
-                                            font-weight: 500;
-                                            color: #374151;
-                                        ",
-                                        "身份标识 (64字符)"
-                                    }
-                                    input {
-                                        style: "
-                                            width: 90%;
-                                            padding: 10px 12px;
-                                            border: 1px solid #d1d5db;
-                                            border-radius: 8px;
-                                            font-size: 14px;
-                                            font-family: monospace;
-                                        ",
-                                        r#type: "text",
-                                        placeholder: "输入64个字符的身份标识...",
-                                        value: "{identity}",
-                                        oninput: move |e| identity.set(e.value().clone())
-                                    }
-                                }
-                                
-                                div {
-                                    label {
-                                        style: "
-                                            display: block;
-                                            margin-bottom: 6px;
-                                            font-weight: 500;
-                                            color: #374151;
-                                        ",
-                                        "别名"
-                                    }
-                                    input {
-                                        style: "
-                                            width: 90%;
-                                            padding: 10px 12px;
-                                            border: 1px solid #d1d5db;
-                                            border-radius: 8px;
-                                            font-size: 14px;
-                                        ",
-                                        r#type: "text",
-                                        placeholder: "例如: 我的身份",
-                                        value: "{alias}",
-                                        oninput: move |e| alias.set(e.value().clone())
-                                    }
-                                }
-                                
-                                button {
-                                    style: "
-                                        width: 100%;
-                                        background: #3b82f6;
-                                        color: white;
-                                        border: none;
-                                        border-radius: 8px;
-                                        padding: 12px;
-                                        font-weight: 500;
-                                        cursor: pointer;
-                                        margin-top: 16px;
-                                    ",
-                                    onclick: add_identity,
-                                    "添加身份"
-                                }
-                            }
-                        },
-                        _ => rsx! { div { "未知类型" } }
+                            "已与对方核对一致，标记为已验证"
+                        }
+                    }
+                }
+            }
+            
+            div {
+                label { style: LABEL_STYLE, "IPv6 地址 (每行一个，按优先顺序，可选)" }
+                textarea {
+                    style: "{FIELD_STYLE} font-family: monospace; min-height: 60px; resize: vertical;",
+                    placeholder: "例如: ::1 或 fe80::...",
+                    value: "{addresses}",
+                    oninput: move |e| {
+                        contact.write().addresses = e.value().split('\n').map(|a| a.to_string()).collect();
+                    }
+                }
+                if let Some(address) = contact.read().last_successful.clone() {
+                    div {
+                        style: "margin-top: 4px; font-size: 12px; color: #16a34a;",
+                        "上次发送成功: {address}（发送时优先尝试）"
+                    }
+                }
+            }
+            
+            div {
+                label { style: LABEL_STYLE, "端口 (留空使用设置中的端口)" }
+                input {
+                    style: FIELD_STYLE,
+                    r#type: "number",
+                    min: "1",
+                    max: "65535",
+                    placeholder: "{default_port}",
+                    value: "{port}",
+                    oninput: move |e| contact.write().port = e.value().trim().parse().ok()
+                }
+            }
+            
+            label {
+                style: "display: flex; align-items: center; gap: 8px; color: #374151;",
+                input {
+                    r#type: "checkbox",
+                    checked: contact.read().trusted,
+                    onchange: move |e| contact.write().trusted = e.checked(),
+                }
+                "加入白名单（“收藏”模式下接收其文件）"
+            }
+            
+            // 白名单的有效期和次数，留空表示不限
+            if contact.read().trusted {
+                div {
+                    style: "display: flex; gap: 12px;",
+                    div {
+                        style: "flex: 1;",
+                        label { style: LABEL_STYLE, "白名单有效至 (可选)" }
+                        input {
+                            style: FIELD_STYLE,
+                            r#type: "date",
+                            value: "{trust_expires_date}",
+                            oninput: move |e| contact.write().trust_expires_at = local_date_to_expiry(&e.value())
+                        }
+                    }
+                    div {
+                        style: "flex: 1;",
+                        label { style: LABEL_STYLE, "剩余接收次数 (可选)" }
+                        input {
+                            style: FIELD_STYLE,
+                            r#type: "number",
+                            min: "0",
+                            placeholder: "不限",
+                            value: "{trust_uses_left}",
+                            oninput: move |e| contact.write().trust_uses_left = e.value().trim().parse().ok()
+                        }
+                    }
+                }
+                if contact.read().trust_exhausted() {
+                    div {
+                        style: "font-size: 12px; color: #dc2626;",
+                        "白名单已到期或次数已用完，“收藏”模式下不再接收其文件"
                     }
                 }
             }
+            
+            div {
+                label { style: LABEL_STYLE, "备注" }
+                textarea {
+                    style: "{FIELD_STYLE} min-height: 48px; resize: vertical;",
+                    value: "{contact.read().notes}",
+                    oninput: move |e| contact.write().notes = e.value()
+                }
+            }
         }
     }
-}
\ No newline at end of file
+}
+
+// 把数据库中的到期时间（UTC）转换为本地日期，用于日期输入框
+fn expiry_to_local_date(expires_at: &str) -> String {
+	NaiveDateTime::parse_from_str(expires_at, DB_TIME_FORMAT)
+		.map(|time| time.and_utc().with_timezone(&Local).format("%Y-%m-%d").to_string())
+		.unwrap_or_default()
+}
+
+// 白名单在所选日期当天结束（本地时间）时到期
+fn local_date_to_expiry(date: &str) -> Option<String> {
+	let date = NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()?;
+	let end = date.and_hms_opt(23, 59, 59)?.and_local_timezone(Local).earliest()?;
+	Some(end.with_timezone(&Utc).format(DB_TIME_FORMAT).to_string())
+}
@@ -1,13 +1,17 @@
 // src/dioxus_component/addressbook/addressbook.rs
 use dioxus::prelude::*;
 use super::add_member::AddModal;
-use super::friends::FriendsList;
-use super::whitelist::Whitelist;
+use super::blocklist::BlocklistPanel;
+use super::contacts::ContactsList;
+use super::exchange::ExchangeModal;
+use super::groups::{GroupEditModal, GroupsList};
+use crate::core::db::ContactGroup;
 
 #[component]
 pub fn AddressBookPage() -> Element {
-    let mut active_tab = use_signal(|| "friends");
+    let mut active_tab = use_signal(|| "contacts");
     let mut show_add_modal = use_signal(|| false);
+    let mut show_exchange_modal = use_signal(|| false);
     let mut refresh_trigger = use_signal(|| 0); // 添加刷新触发器
     
     // 刷新列表的函数
@@ -47,9 +51,9 @@ pub fn AddressBookPage() -> Element {
                     ",
                     
                     TabButton {
-                        active: *active_tab.read() == "friends",
-                        onclick: move |_| active_tab.set("friends"),
-                        label: "好友列表",
+                        active: *active_tab.read() == "contacts",
+                        onclick: move |_| active_tab.set("contacts"),
+                        label: "联系人",
                         icon: rsx! {
                             img {
                                 style: "width: 20px; height: 20px;",
@@ -59,9 +63,9 @@ pub fn AddressBookPage() -> Element {
                     }
                     
                     TabButton {
-                        active: *active_tab.read() == "whitelist",
-                        onclick: move |_| active_tab.set("whitelist"),
-                        label: "白名单",
+                        active: *active_tab.read() == "groups",
+                        onclick: move |_| active_tab.set("groups"),
+                        label: "分组",
                         icon: rsx! {
                             img {
                                 style: "width: 20px; height: 20px;",
@@ -69,6 +73,18 @@ pub fn AddressBookPage() -> Element {
                             }
                         }
                     }
+                    
+                    TabButton {
+                        active: *active_tab.read() == "blocklist",
+                        onclick: move |_| active_tab.set("blocklist"),
+                        label: "屏蔽",
+                        icon: rsx! {
+                            img {
+                                style: "width: 20px; height: 20px;",
+                                src: asset!("assets/error-50.png"),
+                            }
+                        }
+                    }
                 }
 
                 // 添加按钮区域
@@ -77,8 +93,27 @@ pub fn AddressBookPage() -> Element {
                         padding: 20px;
                         margin-top: auto;
                         flex-shrink: 0;
+                        display: flex;
+                        flex-direction: column;
+                        gap: 8px;
                     ",
                     
+                    button {
+                        class: "tab-button",
+                        style: "
+                            width: 100%;
+                            background: white;
+                            color: #374151;
+                            border: 1px solid #d1d5db;
+                            border-radius: 8px;
+                            padding: 10px 16px;
+                            font-weight: 500;
+                            cursor: pointer;
+                        ",
+                        onclick: move |_| show_exchange_modal.set(true),
+                        "导入 / 导出"
+                    }
+                    
                     button {
                         class: "tab-button",
                         style: "
@@ -131,13 +166,18 @@ pub fn AddressBookPage() -> Element {
                         min-height: 400px;
                     ",
                     match *active_tab.read() {
-                        "friends" => rsx! {
-                            FriendsList {
+                        "contacts" => rsx! {
+                            ContactsList {
+                                refresh_trigger: *refresh_trigger.read()
+                            }
+                        },
+                        "groups" => rsx! {
+                            GroupsList {
                                 refresh_trigger: *refresh_trigger.read()
                             }
                         },
-                        "whitelist" => rsx! {
-                            Whitelist {
+                        "blocklist" => rsx! {
+                            BlocklistPanel {
                                 refresh_trigger: *refresh_trigger.read()
                             }
                         },
@@ -146,12 +186,29 @@ pub fn AddressBookPage() -> Element {
                 }
             }
             
-            // 添加模态窗口
+            // 添加模态窗口，按当前标签添加联系人或分组
             if *show_add_modal.read() {
-                AddModal {
-                    on_close: move |_| show_add_modal.set(false),
-                    active_tab: *active_tab.read(),
-                    on_success: move |_| refresh_list(),
+                if *active_tab.read() == "groups" {
+                    GroupEditModal {
+                        group: ContactGroup::default(),
+                        on_saved: move |_| {
+                            refresh_list();
+                            show_add_modal.set(false);
+                        },
+                        on_close: move |_| show_add_modal.set(false),
+                    }
+                } else {
+                    AddModal {
+                        on_close: move |_| show_add_modal.set(false),
+                        on_success: move |_| refresh_list(),
+                    }
+                }
+            }
+            
+            if *show_exchange_modal.read() {
+                ExchangeModal {
+                    on_close: move |_| show_exchange_modal.set(false),
+                    on_imported: move |_| refresh_list(),
                 }
             }
         }
@@ -0,0 +1,261 @@
+// src/dioxus_component/addressbook/blocklist.rs
+use dioxus::prelude::*;
+use crate::core::db::{AddressBook, BlockEntry, BlockKind};
+use crate::dioxus_component::error_message::describe_error;
+
+// 屏蔽列表：其中的身份码和地址段在所有接收模式下都会被拒绝
+#[component]
+pub fn BlocklistPanel(refresh_trigger: u32) -> Element {
+	let mut entries = use_signal(Vec::<BlockEntry>::new);
+	let mut error_message = use_signal(|| None::<String>);
+	let mut kind = use_signal(|| BlockKind::Identity);
+	let mut value = use_signal(String::new);
+	let mut note = use_signal(String::new);
+	
+	let load_entries = move || {
+		spawn(async move {
+			match AddressBook::call(AddressBook::get_block_entries).await {
+				Ok(list) => {
+					entries.set(list);
+					error_message.set(None);
+				}
+				Err(e) => error_message.set(Some(format!("加载屏蔽列表失败: {}", describe_error(&e)))),
+			}
+		});
+	};
+	
+	use_effect(use_reactive((&refresh_trigger,), move |_| {
+		load_entries();
+	}));
+	
+	let add_entry = move |_| {
+		let entry_kind = kind();
+		let entry_value = value.read().clone();
+		let entry_note = note.read().clone();
+		spawn(async move {
+			let result = AddressBook::call(move || {
+				AddressBook::add_block_entry(entry_kind, &entry_value, &entry_note)
+			}).await;
+			match result {
+				Ok(_) => {
+					value.set(String::new());
+					note.set(String::new());
+					load_entries();
+				}
+				Err(e) => error_message.set(Some(format!("添加失败: {}", describe_error(&e)))),
+			}
+		});
+	};
+	
+	let delete_entry = move |id: i64| {
+		spawn(async move {
+			match AddressBook::call(move || AddressBook::delete_block_entry(id)).await {
+				Ok(()) => load_entries(),
+				Err(e) => error_message.set(Some(format!("删除失败: {}", describe_error(&e)))),
+			}
+		});
+	};
+	
+	let placeholder = match kind() {
+		BlockKind::Identity => "64 字符的身份码",
+		BlockKind::Prefix => "例如: 2001:db8::/32 或单个地址",
+	};
+	let entries_list = entries.read().clone();
+	
+	rsx! {
+        div {
+            style: "
+                padding: 20px;
+                height: 100%;
+                display: flex;
+                flex-direction: column;
+            ",
+            
+            // 标题
+            div {
+                style: "
+                    display: flex;
+                    justify-content: space-between;
+                    align-items: center;
+                    margin-bottom: 20px;
+                    padding-bottom: 15px;
+                    border-bottom: 1px solid #e5e7eb;
+                ",
+                
+                h2 {
+                    style: "margin: 0; color: #1f2937;",
+                    "屏蔽列表"
+                }
+                
+                span {
+                    style: "color: #6b7280; font-size: 14px;",
+                    "在任何接收模式下都拒绝这些身份码和地址"
+                }
+            }
+            
+            // 添加条目
+            div {
+                style: "
+                    display: flex;
+                    gap: 8px;
+                    margin-bottom: 15px;
+                ",
+                
+                select {
+                    style: "
+                        padding: 10px 12px;
+                        border: 1px solid #d1d5db;
+                        border-radius: 6px;
+                        font-size: 14px;
+                    ",
+                    onchange: move |e| kind.set(BlockKind::from_db_value(&e.value())),
+                    for option_kind in BlockKind::ALL {
+                        option {
+                            value: option_kind.as_str(),
+                            selected: option_kind == kind(),
+                            "{option_kind.label()}"
+                        }
+                    }
+                }
+                input {
+                    style: "
+                        flex: 2;
+                        padding: 10px 12px;
+                        border: 1px solid #d1d5db;
+                        border-radius: 6px;
+                        font-size: 14px;
+                        font-family: monospace;
+                    ",
+                    placeholder: placeholder,
+                    value: "{value}",
+                    oninput: move |e| value.set(e.value())
+                }
+                input {
+                    style: "
+                        flex: 1;
+                        padding: 10px 12px;
+                        border: 1px solid #d1d5db;
+                        border-radius: 6px;
+                        font-size: 14px;
+                    ",
+                    placeholder: "备注（可选）",
+                    value: "{note}",
+                    oninput: move |e| note.set(e.value())
+                }
+                button {
+                    class: "modal-button",
+                    style: "
+                        padding: 10px 16px;
+                        background: #ef4444;
+                        color: white;
+                        border: none;
+                        border-radius: 6px;
+                        cursor: pointer;
+                        font-size: 14px;
+                        white-space: nowrap;
+                    ",
+                    disabled: value.read().trim().is_empty(),
+                    onclick: add_entry,
+                    "屏蔽"
+                }
+            }
+            
+            // 错误信息显示
+            if let Some(error) = error_message.read().as_ref() {
+                div {
+                    style: "
+                        background: #fee2e2;
+                        border: 1px solid #fecaca;
+                        color: #dc2626;
+                        padding: 12px;
+                        border-radius: 6px;
+                        margin-bottom: 15px;
+                    ",
+                    "{error}"
+                }
+            }
+            
+            if entries_list.is_empty() {
+                div {
+                    style: "
+                        text-align: center;
+                        color: #6b7280;
+                        padding: 40px;
+                    ",
+                    "屏蔽列表为空，也可以在接收记录或连接记录中直接屏蔽发送方"
+                }
+            } else {
+                div {
+                    style: "
+                        display: flex;
+                        flex-direction: column;
+                        gap: 8px;
+                    ",
+                    
+                    for entry in entries_list {
+                        div {
+                            key: "{entry.id}",
+                            class: "friend-item",
+                            style: "
+                                display: flex;
+                                align-items: center;
+                                gap: 12px;
+                                padding: 12px 16px;
+                                border: 1px solid #e5e7eb;
+                                border-radius: 8px;
+                                background: white;
+                            ",
+                            
+                            span {
+                                style: "
+                                    padding: 2px 8px;
+                                    border-radius: 10px;
+                                    font-size: 12px;
+                                    background: #fee2e2;
+                                    color: #b91c1c;
+                                    white-space: nowrap;
+                                ",
+                                "{entry.kind.label()}"
+                            }
+                            
+                            div {
+                                style: "flex: 1; min-width: 0;",
+                                div {
+                                    style: "
+                                        font-family: monospace;
+                                        font-size: 13px;
+                                        color: #1f2937;
+                                        word-break: break-all;
+                                    ",
+                                    "{entry.value}"
+                                }
+                                div {
+                                    style: "color: #6b7280; font-size: 12px;",
+                                    if entry.note.is_empty() {
+                                        "{entry.created_at}"
+                                    } else {
+                                        "{entry.created_at} · {entry.note}"
+                                    }
+                                }
+                            }
+                            
+                            button {
+                                style: "
+                                    padding: 6px 12px;
+                                    background: #6b7280;
+                                    color: white;
+                                    border: none;
+                                    border-radius: 4px;
+                                    cursor: pointer;
+                                    font-size: 12px;
+                                ",
+                                onclick: move |_| delete_entry(entry.id),
+                                "移除"
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1,108 +1,85 @@
-// src/dioxus_component/addressbook/friends.rs
+// src/dioxus_component/addressbook/contacts.rs
 use dioxus::prelude::*;
-use crate::core::db::AddressBook;
+use crate::core::db::{AddressBook, Contact};
+use crate::dioxus_component::error_message::describe_error;
+use super::add_member::ContactForm;
+use super::identity_rotations::PendingRotations;
 
 #[component]
-pub fn FriendsList(refresh_trigger: u32) -> Element {
-	let friends = use_signal(|| Vec::new());
-	let mut search_query = use_signal(|| String::new());
+pub fn ContactsList(refresh_trigger: u32) -> Element {
+	let contacts = use_signal(|| Vec::<Contact>::new());
+	let mut search_query = use_signal(String::new);
+	let mut trusted_only = use_signal(|| false);
 	let mut error_message = use_signal(|| None::<String>);
-	let mut selected_friend = use_signal(|| None::<crate::core::db::FriendEntry>);
+	let mut selected_contact = use_signal(|| None::<Contact>);
 	let mut show_edit_modal = use_signal(|| false);
 	
-	// 统一的错误处理函数
-	let handle_error = move |message: String| {
-		error_message.set(Some(message));
-	};
-	
 	// 统一的加载函数
-	let load_friends = {
-		let friends_signal = friends.clone();
-		let error_signal = error_message.clone();
-		move |query: Option<String>| {
-			let mut friends = friends_signal.clone();
-			let mut error_message = error_signal.clone();
-			spawn(async move {
-				let result = if let Some(ref q) = query {
-					AddressBook::search_friends(&q)
-				} else {
-					AddressBook::get_all_friends()
-				};
-				
-				match result {
-					Ok(friends_list) => {
-						friends.set(friends_list);
-					}
-					Err(e) => {
-						let error_msg = if query.is_some() {
-							format!("搜索失败: {}", e)
-						} else {
-							format!("加载好友列表失败: {}", e)
-						};
-						error_message.set(Some(error_msg));
-					}
+	let load_contacts = move |query: Option<String>| {
+		let mut contacts = contacts;
+		let mut error_message = error_message;
+		spawn(async move {
+			let q = query.clone();
+			let result = AddressBook::call(move || match q {
+				Some(q) => AddressBook::search_contacts(&q),
+				None => AddressBook::get_all_contacts(),
+			}).await;
+			
+			match result {
+				Ok(contacts_list) => {
+					contacts.set(contacts_list);
+					error_message.set(None);
 				}
-			});
-		}
+				Err(e) => {
+					let error_msg = if query.is_some() {
+						format!("搜索失败: {}", describe_error(&e))
+					} else {
+						format!("加载联系人失败: {}", describe_error(&e))
+					};
+					error_message.set(Some(error_msg));
+				}
+			}
+		});
 	};
 	
-	// 加载好友列表
+	// 加载联系人列表
 	use_effect(use_reactive((&refresh_trigger,), move |_| {
-		load_friends(None);
+		load_contacts(None);
 	}));
 	
 	// 处理搜索
 	let handle_search = move |_| {
 		let query = search_query.read().clone();
 		if query.is_empty() {
-			load_friends(None);
+			load_contacts(None);
 		} else {
-			load_friends(Some(query));
+			load_contacts(Some(query));
 		}
 	};
 	
-	// 处理编辑好友
-	let mut handle_edit_friend = move |friend: crate::core::db::FriendEntry| {
-		selected_friend.set(Some(friend));
+	// 处理编辑联系人
+	let mut handle_edit_contact = move |contact: Contact| {
+		selected_contact.set(Some(contact));
 		show_edit_modal.set(true);
 	};
 	
-	// 处理保存编辑
-	let handle_save_edit = move |id: i64, address: String, alias: String| {
-		let load_friends = load_friends.clone();
-		let mut handle_error = handle_error.clone();
-		spawn(async move {
-			match AddressBook::update_friend(id, &address, &alias) {
-				Ok(()) => {
-					// 使用统一的加载函数重新加载列表
-					load_friends(None);
-				}
-				Err(e) => {
-					handle_error(format!("更新好友失败: {}", e));
-				}
-			}
-		});
-	};
-	
-	// 处理删除好友
-	let handle_delete_friend = move |id: i64| {
-		let load_friends = load_friends.clone();
-		let mut handle_error = handle_error.clone();
+	// 处理删除联系人
+	let handle_delete_contact = move |id: i64| {
 		spawn(async move {
-			match AddressBook::delete_friend(id) {
-				Ok(()) => {
-					// 使用统一的加载函数重新加载列表
-					load_friends(None);
-				}
-				Err(e) => {
-					handle_error(format!("删除好友失败: {}", e));
-				}
+			match AddressBook::call(move || AddressBook::delete_contact(id)).await {
+				Ok(()) => load_contacts(None),
+				Err(e) => error_message.set(Some(format!("删除联系人失败: {}", describe_error(&e)))),
 			}
 		});
 	};
 	
-	// 克隆好友列表以避免生命周期问题
-	let friends_list = friends.read().clone();
+	// 按白名单筛选
+	let contacts_list: Vec<Contact> = contacts
+		.read()
+		.iter()
+		.filter(|c| !*trusted_only.read() || c.trusted)
+		.cloned()
+		.collect();
 	
 	rsx! {
         div {
@@ -126,12 +103,22 @@ pub fn FriendsList(refresh_trigger: u32) -> Element {
                 
                 h2 {
                     style: "margin: 0; color: #1f2937;",
-                    "好友列表"
+                    "联系人"
                 }
                 
                 div {
                     style: "display: flex; gap: 10px; align-items: center;",
                     
+                    label {
+                        style: "display: flex; align-items: center; gap: 6px; font-size: 14px; color: #374151;",
+                        input {
+                            r#type: "checkbox",
+                            checked: *trusted_only.read(),
+                            onchange: move |e| trusted_only.set(e.checked()),
+                        }
+                        "仅显示白名单"
+                    }
+                    
                     input {
                         style: "
                             padding: 8px 12px;
@@ -140,13 +127,13 @@ pub fn FriendsList(refresh_trigger: u32) -> Element {
                             font-size: 14px;
                             width: 250px;
                         ",
-                        placeholder: "搜索...",
+                        placeholder: "搜索名称、身份码或地址...",
                         value: "{search_query}",
                         oninput: move |e| search_query.set(e.value())
                     }
                     
                     button {
-						class: "search-button",
+                        class: "search-button",
                         style: "
                             padding: 8px 16px;
                             background: #3b82f6;
@@ -176,15 +163,21 @@ pub fn FriendsList(refresh_trigger: u32) -> Element {
                 }
             }
             
-            // 好友列表
-            if friends_list.is_empty() {
+            // 联系人更换身份码后等待确认的提示
+            PendingRotations {
+                refresh_trigger,
+                on_changed: move |_| load_contacts(None),
+            }
+            
+            // 联系人列表
+            if contacts_list.is_empty() {
                 div {
                     style: "
                         text-align: center;
                         color: #6b7280;
                         padding: 40px;
                     ",
-                    "暂无好友，点击左上角\"添加新条目\"按钮添加好友"
+                    "暂无联系人，点击左下角\"添加新条目\"按钮添加联系人"
                 }
             } else {
                 div {
@@ -194,10 +187,11 @@ pub fn FriendsList(refresh_trigger: u32) -> Element {
                         gap: 8px;
                     ",
                     
-                    for friend in friends_list {
-                        FriendItem {
-                            friend: friend.clone(),
-                            on_click: move || handle_edit_friend(friend.clone())
+                    for contact in contacts_list {
+                        ContactItem {
+                            key: "{contact.id}",
+                            contact: contact.clone(),
+                            on_click: move || handle_edit_contact(contact.clone())
                         }
                     }
                 }
@@ -205,15 +199,15 @@ pub fn FriendsList(refresh_trigger: u32) -> Element {
             
             // 编辑模态框
             if *show_edit_modal.read() {
-                if let Some(friend) = selected_friend.read().as_ref() {
-                    FriendEditModal {
-                        friend: friend.clone(),
-                        on_save: move |params: (i64, String, String)| {
-                            handle_save_edit(params.0, params.1, params.2);
+                if let Some(contact) = selected_contact.read().as_ref() {
+                    ContactEditModal {
+                        contact: contact.clone(),
+                        on_saved: move |_| {
+                            load_contacts(None);
                             show_edit_modal.set(false);
                         },
                         on_delete: move |id| {
-                            handle_delete_friend(id);
+                            handle_delete_contact(id);
                             show_edit_modal.set(false);
                         },
                         on_close: move |_| show_edit_modal.set(false),
@@ -225,10 +219,18 @@ pub fn FriendsList(refresh_trigger: u32) -> Element {
 }
 
 #[component]
-fn FriendItem(friend: crate::core::db::FriendEntry, on_click: EventHandler) -> Element {
+fn ContactItem(contact: Contact, on_click: EventHandler) -> Element {
+	// 上次发送成功的地址标记 ✓
+	let addresses = contact
+		.addresses
+		.iter()
+		.map(|a| if Some(a) == contact.last_successful.as_ref() { format!("{} ✓", a) } else { a.clone() })
+		.collect::<Vec<_>>()
+		.join("  ");
+	
 	rsx! {
         div {
-			class: "friend-item",
+            class: "friend-item",
             style: "
                 display: flex;
                 justify-content: space-between;
@@ -240,31 +242,84 @@ fn FriendItem(friend: crate::core::db::FriendEntry, on_click: EventHandler) -> E
                 transition: all 0.2s;
                 cursor: pointer;
             ",
-            onmouseenter: move |_| {
-                // 悬停效果
-            },
             onclick: move |_| on_click.call(()),
             
-            // 好友信息
+            // 联系人信息
             div {
-                style: "flex: 1;",
+                style: "flex: 1; min-width: 0;",
                 
                 div {
                     style: "
                         font-weight: 500;
                         color: #1f2937;
                         margin-bottom: 4px;
+                        display: flex;
+                        align-items: center;
+                        gap: 8px;
                     ",
-                    "{friend.alias}"
+                    "{contact.name}"
+                    if contact.trusted && contact.trust_exhausted() {
+                        span {
+                            style: "
+                                background: #f3f4f6;
+                                color: #6b7280;
+                                font-size: 12px;
+                                padding: 2px 8px;
+                                border-radius: 10px;
+                            ",
+                            "白名单已失效"
+                        }
+                    } else if contact.trusted {
+                        span {
+                            style: "
+                                background: #dcfce7;
+                                color: #16a34a;
+                                font-size: 12px;
+                                padding: 2px 8px;
+                                border-radius: 10px;
+                            ",
+                            if let Some(uses) = contact.trust_uses_left {
+                                "白名单（剩余 {uses} 次）"
+                            } else {
+                                "白名单"
+                            }
+                        }
+                    }
+                    if contact.verified {
+                        span {
+                            style: "
+                                background: #dbeafe;
+                                color: #2563eb;
+                                font-size: 12px;
+                                padding: 2px 8px;
+                                border-radius: 10px;
+                            ",
+                            "已验证"
+                        }
+                    }
                 }
                 
-                div {
-                    style: "
-                        font-family: monospace;
-                        color: #6b7280;
-                        font-size: 14px;
-                    ",
-                    "{friend.address}"
+                if !contact.addresses.is_empty() {
+                    div {
+                        style: "
+                            font-family: monospace;
+                            color: #6b7280;
+                            font-size: 14px;
+                        ",
+                        "{addresses}"
+                    }
+                }
+                
+                if !contact.identity.is_empty() {
+                    div {
+                        style: "
+                            font-family: monospace;
+                            color: #9ca3af;
+                            font-size: 12px;
+                            word-break: break-all;
+                        ",
+                        "{contact.identity}"
+                    }
                 }
             }
             
@@ -280,16 +335,27 @@ fn FriendItem(friend: crate::core::db::FriendEntry, on_click: EventHandler) -> E
 }
 
 #[component]
-fn FriendEditModal(
-	friend: crate::core::db::FriendEntry,
-	on_save: EventHandler<(i64, String, String)>,
+fn ContactEditModal(
+	contact: Contact,
+	on_saved: EventHandler,
 	on_delete: EventHandler<i64>,
 	on_close: EventHandler,
 ) -> Element {
-	let mut address = use_signal(|| friend.address.clone());
-	let mut alias = use_signal(|| friend.alias.clone());
+	let id = contact.id;
+	let form = use_signal(|| contact.clone());
+	let mut error_message = use_signal(String::new);
 	let mut show_confirm_delete = use_signal(|| false);
 	
+	let save = move |_| {
+		let contact = form.read().clone();
+		spawn(async move {
+			match AddressBook::call(move || AddressBook::update_contact(&contact)).await {
+				Ok(()) => on_saved.call(()),
+				Err(e) => error_message.set(format!("更新联系人失败: {}", describe_error(&e))),
+			}
+		});
+	};
+	
 	rsx! {
         div {
             style: "
@@ -312,69 +378,33 @@ fn FriendEditModal(
                     border-radius: 12px;
                     width: 90%;
                     max-width: 500px;
+                    max-height: 90vh;
+                    overflow-y: auto;
                     box-shadow: 0 10px 25px rgba(0, 0, 0, 0.2);
                 ",
                 onclick: move |e| e.stop_propagation(),
                 
                 h3 {
                     style: "margin: 0 0 20px 0; color: #1f2937;",
-                    "编辑好友"
+                    "编辑联系人"
                 }
                 
-                // 表单
-                div {
-                    style: "display: flex; flex-direction: column; gap: 16px;",
-                    
-                    // 地址输入
+                if !error_message.read().is_empty() {
                     div {
-                        label {
-                            style: "
-                                display: block;
-                                margin-bottom: 6px;
-                                font-weight: 500;
-                                color: #374151;
-                            ",
-                            "IPv6地址"
-                        }
-                        input {
-                            style: "
-                                width: 90%;
-                                padding: 10px 12px;
-                                border: 1px solid #d1d5db;
-                                border-radius: 6px;
-                                font-size: 14px;
-                                font-family: monospace;
-                            ",
-                            value: "{address}",
-                            oninput: move |e| address.set(e.value())
-                        }
-                    }
-                    
-                    // 别名输入
-                    div {
-                        label {
-                            style: "
-                                display: block;
-                                margin-bottom: 6px;
-                                font-weight: 500;
-                                color: #374151;
-                            ",
-                            "备注"
-                        }
-                        input {
-                            style: "
-                                width: 90%;
-                                padding: 10px 12px;
-                                border: 1px solid #d1d5db;
-                                border-radius: 6px;
-                                font-size: 14px;
-                            ",
-                            value: "{alias}",
-                            oninput: move |e| alias.set(e.value())
-                        }
+                        style: "
+                            background: #fee2e2;
+                            border: 1px solid #fecaca;
+                            color: #dc2626;
+                            padding: 12px 16px;
+                            border-radius: 8px;
+                            margin-bottom: 20px;
+                        ",
+                        {error_message.read().as_str()}
                     }
                 }
                 
+                ContactForm { contact: form }
+                
                 // 按钮区域
                 div {
                     style: "
@@ -387,7 +417,7 @@ fn FriendEditModal(
                     // 删除按钮
                     if !show_confirm_delete() {
                         button {
-							class: "modal-danger-button",
+                            class: "modal-danger-button",
                             style: "
                                 padding: 10px 16px;
                                 background: #ef4444;
@@ -410,7 +440,7 @@ fn FriendEditModal(
                             }
                             
                             button {
-								class: "confirm-delete-button",
+                                class: "confirm-delete-button",
                                 style: "
                                     padding: 6px 12px;
                                     background: #ef4444;
@@ -420,14 +450,12 @@ fn FriendEditModal(
                                     cursor: pointer;
                                     font-size: 12px;
                                 ",
-                                onclick: move |_| {
-                                    on_delete.call(friend.id);
-                                },
+                                onclick: move |_| on_delete.call(id),
                                 "确认删除"
                             }
                             
                             button {
-								class: "cancel-delete-button",
+                                class: "cancel-delete-button",
                                 style: "
                                     padding: 6px 12px;
                                     background: #6b7280;
@@ -448,7 +476,7 @@ fn FriendEditModal(
                         style: "display: flex; gap: 12px;",
                         
                         button {
-							class: "modal-secondary-button",
+                            class: "modal-secondary-button",
                             style: "
                                 padding: 10px 20px;
                                 background: #6b7280;
@@ -463,7 +491,7 @@ fn FriendEditModal(
                         }
                         
                         button {
-							class: "modal-button",
+                            class: "modal-button",
                             style: "
                                 padding: 10px 20px;
                                 background: #3b82f6;
@@ -473,9 +501,7 @@ fn FriendEditModal(
                                 cursor: pointer;
                                 font-size: 14px;
                             ",
-                            onclick: move |_| {
-                                on_save.call((friend.id, address.read().clone(), alias.read().clone()));
-                            },
+                            onclick: save,
                             "保存"
                         }
                     }
@@ -483,4 +509,4 @@ fn FriendEditModal(
             }
         }
     }
-}
\ No newline at end of file
+}
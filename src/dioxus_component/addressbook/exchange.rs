@@ -0,0 +1,251 @@
+// src/dioxus_component/addressbook/exchange.rs
+use dioxus::prelude::*;
+use crate::core::contact_exchange::{ContactExchange, ExchangeFormat, ImportReport};
+use crate::core::db::AddressBook;
+use crate::dioxus_component::error_message::describe_error;
+
+const SECTION_TITLE_STYLE: &str = "
+    margin: 0 0 8px 0;
+    font-size: 15px;
+    font-weight: 600;
+    color: #374151;
+";
+
+const BUTTON_STYLE: &str = "
+    padding: 8px 14px;
+    background: #3b82f6;
+    color: white;
+    border: none;
+    border-radius: 6px;
+    cursor: pointer;
+    font-size: 14px;
+";
+
+// 通讯录的导入导出，导入完成后调用 on_imported 刷新列表
+#[component]
+pub fn ExchangeModal(on_close: EventHandler, on_imported: EventHandler) -> Element {
+	let mut status_message = use_signal(String::new);
+	let mut report = use_signal(|| None::<ImportReport>);
+	
+	// 生成导出内容后让用户选择保存位置
+	let save_text = move |title: &'static str, file_name: String, text: String| {
+		spawn(async move {
+			let file = rfd::AsyncFileDialog::new()
+				.set_title(title)
+				.set_file_name(file_name)
+				.save_file()
+				.await;
+			
+			if let Some(file) = file {
+				match tokio::fs::write(file.path(), text).await {
+					Ok(()) => status_message.set(format!("已导出到 {}", file.path().display())),
+					Err(e) => status_message.set(format!("导出失败: {}", e)),
+				}
+			}
+		});
+	};
+	
+	let export = move |format: ExchangeFormat| {
+		spawn(async move {
+			match AddressBook::call(move || ContactExchange::export(format)).await {
+				Ok(text) => save_text("导出通讯录", format!("contacts.{}", format.extension()), text),
+				Err(e) => status_message.set(format!("导出失败: {}", describe_error(&e))),
+			}
+		});
+	};
+	
+	let export_my_card = move |_| {
+		spawn(async move {
+			match AddressBook::call(ContactExchange::my_card).await {
+				Ok(text) => save_text("导出我的名片", "my_card.vcf".to_string(), text),
+				Err(e) => status_message.set(format!("导出失败: {}", describe_error(&e))),
+			}
+		});
+	};
+	
+	// 按扩展名识别格式，合并到通讯录后显示结果
+	let import = move |_| {
+		spawn(async move {
+			let file = rfd::AsyncFileDialog::new()
+				.set_title("导入联系人")
+				.add_filter("联系人文件", &["json", "csv", "vcf"])
+				.pick_file()
+				.await;
+			let Some(file) = file else { return };
+			
+			let Some(format) = ExchangeFormat::from_path(file.path()) else {
+				status_message.set("无法识别的文件类型，请选择 .json、.csv 或 .vcf 文件".to_string());
+				return;
+			};
+			let text = match tokio::fs::read_to_string(file.path()).await {
+				Ok(text) => text,
+				Err(e) => {
+					status_message.set(format!("读取文件失败: {}", e));
+					return;
+				}
+			};
+			
+			let result = AddressBook::call(move || {
+				let records = ContactExchange::parse(&text, format)?;
+				ContactExchange::import(records)
+			}).await;
+			match result {
+				Ok(import_report) => {
+					status_message.set(format!("导入完成: {}", import_report.summary()));
+					report.set(Some(import_report));
+					on_imported.call(());
+				}
+				Err(e) => status_message.set(format!("导入失败: {}", describe_error(&e))),
+			}
+		});
+	};
+	
+	let conflicts = report.read().as_ref().map(|r| r.conflicts.clone()).unwrap_or_default();
+	
+	rsx! {
+        div {
+            style: "
+                position: fixed;
+                top: 0;
+                left: 0;
+                right: 0;
+                bottom: 0;
+                background: rgba(0, 0, 0, 0.5);
+                display: flex;
+                align-items: center;
+                justify-content: center;
+                z-index: 1000;
+            ",
+            
+            div {
+                style: "
+                    background: white;
+                    padding: 24px;
+                    border-radius: 12px;
+                    width: 90%;
+                    max-width: 520px;
+                    max-height: 90vh;
+                    overflow-y: auto;
+                    display: flex;
+                    flex-direction: column;
+                    gap: 20px;
+                    box-shadow: 0 10px 25px rgba(0, 0, 0, 0.2);
+                ",
+                onclick: move |e| e.stop_propagation(),
+                
+                div {
+                    style: "display: flex; justify-content: space-between; align-items: center;",
+                    
+                    h3 {
+                        style: "margin: 0; color: #1f2937;",
+                        "导入 / 导出"
+                    }
+                    
+                    button {
+                        class: "modal-close-button",
+                        style: "
+                            background: none;
+                            border: none;
+                            font-size: 24px;
+                            cursor: pointer;
+                            color: #6b7280;
+                        ",
+                        onclick: move |_| on_close.call(()),
+                        "×"
+                    }
+                }
+                
+                // 导出通讯录
+                div {
+                    h4 { style: SECTION_TITLE_STYLE, "导出通讯录" }
+                    div {
+                        style: "display: flex; gap: 8px; flex-wrap: wrap;",
+                        for format in ExchangeFormat::ALL {
+                            button {
+                                key: "{format.extension()}",
+                                class: "modal-button",
+                                style: BUTTON_STYLE,
+                                onclick: move |_| export(format),
+                                "{format.label()}"
+                            }
+                        }
+                    }
+                    div {
+                        style: "margin-top: 6px; color: #6b7280; font-size: 12px;",
+                        "JSON 和 CSV 包含分组，可用于备份或迁移到另一台设备"
+                    }
+                }
+                
+                // 导出我的名片
+                div {
+                    h4 { style: SECTION_TITLE_STYLE, "我的名片" }
+                    button {
+                        class: "modal-button",
+                        style: BUTTON_STYLE,
+                        onclick: export_my_card,
+                        "导出我的名片 (.vcf)"
+                    }
+                    div {
+                        style: "margin-top: 6px; color: #6b7280; font-size: 12px;",
+                        "包含显示名称、身份码、本机 IPv6 地址和端口，对方导入后即可向你发送文件"
+                    }
+                }
+                
+                // 导入
+                div {
+                    h4 { style: SECTION_TITLE_STYLE, "导入联系人" }
+                    button {
+                        class: "modal-button",
+                        style: BUTTON_STYLE,
+                        onclick: import,
+                        "选择文件 (.json / .csv / .vcf)"
+                    }
+                    div {
+                        style: "margin-top: 6px; color: #6b7280; font-size: 12px;",
+                        "身份码或地址相同的记录会合并到已有联系人，已有的名称和端口不会被覆盖"
+                    }
+                }
+                
+                if !status_message.read().is_empty() {
+                    div {
+                        style: "
+                            padding: 12px;
+                            background: #f3f4f6;
+                            border-radius: 6px;
+                            color: #374151;
+                            font-size: 14px;
+                            word-break: break-all;
+                        ",
+                        {status_message.read().as_str()}
+                    }
+                }
+                
+                // 导入时的冲突
+                if !conflicts.is_empty() {
+                    div {
+                        style: "
+                            background: #fef3c7;
+                            border: 1px solid #fde68a;
+                            color: #92400e;
+                            padding: 12px;
+                            border-radius: 6px;
+                            font-size: 13px;
+                            max-height: 200px;
+                            overflow-y: auto;
+                        ",
+                        div {
+                            style: "font-weight: 600; margin-bottom: 6px;",
+                            "以下记录需要手动处理："
+                        }
+                        for (index, conflict) in conflicts.into_iter().enumerate() {
+                            div {
+                                key: "{index}",
+                                "{conflict}"
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1,119 +1,52 @@
-// src/dioxus_component/addressbook/whitelist.rs
+// src/dioxus_component/addressbook/groups.rs
 use dioxus::prelude::*;
-use crate::core::db::AddressBook;
+use crate::core::db::{AddressBook, Contact, ContactGroup};
+use crate::dioxus_component::error_message::describe_error;
 
 #[component]
-pub fn Whitelist(refresh_trigger: u32) -> Element {
-	let identities = use_signal(|| Vec::new());
-	let mut search_query = use_signal(|| String::new());
+pub fn GroupsList(refresh_trigger: u32) -> Element {
+	let mut groups = use_signal(Vec::<ContactGroup>::new);
+	let mut contacts = use_signal(Vec::<Contact>::new);
 	let mut error_message = use_signal(|| None::<String>);
-	let mut selected_identity = use_signal(|| None::<crate::core::db::IdentityEntry>);
-	let mut show_edit_modal = use_signal(|| false);
+	let mut selected_group = use_signal(|| None::<ContactGroup>);
 	
-	// 统一的错误处理函数
-	let handle_error = move |message: String| {
-		error_message.set(Some(message));
-	};
-	
-	// 统一的加载函数
-	let load_identities = {
-		let identities_signal = identities.clone();
-		let error_signal = error_message.clone();
-		move |query: Option<String>| {
-			let mut identities = identities_signal.clone();
-			let mut error_message = error_signal.clone();
-			spawn(async move {
-				let result = if let Some(ref q) = query {
-					AddressBook::search_identities(&q)
-				} else {
-					AddressBook::get_all_identities()
-				};
-				
-				match result {
-					Ok(identities_list) => {
-						identities.set(identities_list);
-					}
-					Err(e) => {
-						let error_msg = if query.is_some() {
-							format!("搜索失败: {}", e)
-						} else {
-							format!("加载白名单失败: {}", e)
-						};
-						error_message.set(Some(error_msg));
-					}
-				}
-			});
-		}
-	};
-	
-	// 加载身份标识列表
-	use_effect(use_reactive((&refresh_trigger,), move |_| {
-		load_identities(None);
-	}));
-	
-	// 处理搜索
-	let handle_search = move |_| {
-		let query = search_query.read().clone();
-		if query.is_empty() {
-			load_identities(None);
-		} else {
-			load_identities(Some(query));
-		}
-	};
-	
-	// 处理编辑身份标识
-	let mut handle_edit_identity = move |identity: crate::core::db::IdentityEntry| {
-		selected_identity.set(Some(identity));
-		show_edit_modal.set(true);
-	};
-	
-	// 处理保存编辑
-	let handle_save_edit = move |id: i64, identity: String, alias: String| {
-		let load_identities = load_identities.clone();
-		let mut handle_error = handle_error.clone();
+	// 同时加载分组和联系人，用于显示成员名称
+	let load_groups = move || {
 		spawn(async move {
-			match AddressBook::update_identity(id, &identity, &alias) {
-				Ok(()) => {
-					// 使用统一的加载函数重新加载列表
-					load_identities(None);
+			let result = AddressBook::call(|| {
+				Ok((AddressBook::get_all_groups()?, AddressBook::get_all_contacts()?))
+			}).await;
+			
+			match result {
+				Ok((groups_list, contacts_list)) => {
+					groups.set(groups_list);
+					contacts.set(contacts_list);
+					error_message.set(None);
 				}
 				Err(e) => {
-					handle_error(format!("更新身份标识失败: {}", e));
+					error_message.set(Some(format!("加载分组失败: {}", describe_error(&e))));
 				}
 			}
 		});
 	};
 	
-	// 处理删除身份标识
-	let handle_delete_identity = move |id: i64| {
-		let load_identities = load_identities.clone();
-		let mut handle_error = handle_error.clone();
-		spawn(async move {
-			match AddressBook::delete_identity(id) {
-				Ok(()) => {
-					// 使用统一的加载函数重新加载列表
-					load_identities(None);
-				}
-				Err(e) => {
-					handle_error(format!("删除身份标识失败: {}", e));
-				}
-			}
-		});
-	};
+	use_effect(use_reactive((&refresh_trigger,), move |_| {
+		load_groups();
+	}));
 	
-	// 克隆身份标识列表以避免生命周期问题
-	let identities_list = identities.read().clone();
+	let groups_list = groups.read().clone();
+	let contacts_list = contacts.read().clone();
 	
 	rsx! {
         div {
             style: "
                 padding: 20px;
-                height: 90%;
+                height: 100%;
                 display: flex;
                 flex-direction: column;
             ",
             
-            // 标题和搜索栏
+            // 标题
             div {
                 style: "
                     display: flex;
@@ -126,38 +59,12 @@ pub fn Whitelist(refresh_trigger: u32) -> Element {
                 
                 h2 {
                     style: "margin: 0; color: #1f2937;",
-                    "白名单"
+                    "分组"
                 }
                 
-                div {
-                    style: "display: flex; gap: 10px; align-items: center;",
-                    
-                    input {
-                        style: "
-                            padding: 8px 12px;
-                            border: 1px solid #d1d5db;
-                            border-radius: 6px;
-                            font-size: 14px;
-                            width: 250px;
-                        ",
-                        placeholder: "搜索...",
-                        value: "{search_query}",
-                        oninput: move |e| search_query.set(e.value())
-                    }
-                    
-                    button {
-						class: "search-button",
-                        style: "
-                            padding: 8px 16px;
-                            background: #3b82f6;
-                            color: white;
-                            border: none;
-                            border-radius: 6px;
-                            cursor: pointer;
-                        ",
-                        onclick: handle_search,
-                        "搜索"
-                    }
+                span {
+                    style: "color: #6b7280; font-size: 14px;",
+                    "在发送页面选择分组即可一次发送给所有成员"
                 }
             }
             
@@ -176,15 +83,14 @@ pub fn Whitelist(refresh_trigger: u32) -> Element {
                 }
             }
             
-            // 身份标识列表
-            if identities_list.is_empty() {
+            if groups_list.is_empty() {
                 div {
                     style: "
                         text-align: center;
                         color: #6b7280;
                         padding: 40px;
                     ",
-                    "白名单为空，点击左上角\"添加新条目\"按钮添加身份标识"
+                    "暂无分组，点击左下角\"添加新条目\"按钮创建分组"
                 }
             } else {
                 div {
@@ -194,41 +100,52 @@ pub fn Whitelist(refresh_trigger: u32) -> Element {
                         gap: 8px;
                     ",
                     
-                    for identity in identities_list {
-                        IdentityItem {
-                            identity: identity.clone(),
-                            on_click: move || handle_edit_identity(identity.clone())
+                    for group in groups_list {
+                        GroupItem {
+                            key: "{group.id}",
+                            group: group.clone(),
+                            member_names: member_names(&group, &contacts_list),
+                            on_click: move || selected_group.set(Some(group.clone())),
                         }
                     }
                 }
             }
             
             // 编辑模态框
-            if *show_edit_modal.read() {
-                if let Some(identity) = selected_identity.read().as_ref() {
-                    IdentityEditModal {
-                        identity: identity.clone(),
-                        on_save: move |params: (i64, String, String)| {
-                            handle_save_edit(params.0, params.1, params.2);
-                            show_edit_modal.set(false);
-                        },
-                        on_delete: move |id| {
-                            handle_delete_identity(id);
-                            show_edit_modal.set(false);
-                        },
-                        on_close: move |_| show_edit_modal.set(false),
-                    }
+            if let Some(group) = selected_group.read().clone() {
+                GroupEditModal {
+                    group: group,
+                    on_saved: move |_| {
+                        load_groups();
+                        selected_group.set(None);
+                    },
+                    on_close: move |_| selected_group.set(None),
                 }
             }
         }
     }
 }
 
+// 按联系人列表的顺序取出分组成员的名称
+fn member_names(group: &ContactGroup, contacts: &[Contact]) -> Vec<String> {
+	contacts
+		.iter()
+		.filter(|c| group.member_ids.contains(&c.id))
+		.map(|c| c.name.clone())
+		.collect()
+}
+
 #[component]
-fn IdentityItem(identity: crate::core::db::IdentityEntry, on_click: EventHandler) -> Element {
+fn GroupItem(group: ContactGroup, member_names: Vec<String>, on_click: EventHandler) -> Element {
+	let members = if member_names.is_empty() {
+		"暂无成员".to_string()
+	} else {
+		member_names.join("、")
+	};
+	
 	rsx! {
         div {
-			class: "identity-item",
+            class: "friend-item",
             style: "
                 display: flex;
                 justify-content: space-between;
@@ -240,14 +157,10 @@ fn IdentityItem(identity: crate::core::db::IdentityEntry, on_click: EventHandler
                 transition: all 0.2s;
                 cursor: pointer;
             ",
-            onmouseenter: move |_| {
-                // 悬停效果
-            },
             onclick: move |_| on_click.call(()),
             
-            // 身份标识信息
             div {
-                style: "flex: 1;",
+                style: "flex: 1; min-width: 0;",
                 
                 div {
                     style: "
@@ -255,21 +168,15 @@ fn IdentityItem(identity: crate::core::db::IdentityEntry, on_click: EventHandler
                         color: #1f2937;
                         margin-bottom: 4px;
                     ",
-                    "{identity.alias}"
+                    "{group.name} ({member_names.len()})"
                 }
                 
                 div {
-                    style: "
-                        font-family: monospace;
-                        color: #6b7280;
-                        font-size: 12px;
-                        word-break: break-all;
-                    ",
-                    "{identity.identity}"
+                    style: "color: #6b7280; font-size: 14px;",
+                    "{members}"
                 }
             }
             
-            // 点击提示
             div {
                 img {
                     style: "width: 24px; height: 24px;",
@@ -280,17 +187,65 @@ fn IdentityItem(identity: crate::core::db::IdentityEntry, on_click: EventHandler
     }
 }
 
+// 新建或编辑分组，group.id 为 0 时表示新建
 #[component]
-fn IdentityEditModal(
-	identity: crate::core::db::IdentityEntry,
-	on_save: EventHandler<(i64, String, String)>,
-	on_delete: EventHandler<i64>,
-	on_close: EventHandler,
-) -> Element {
-	let mut identity_str = use_signal(|| identity.identity.clone());
-	let mut alias = use_signal(|| identity.alias.clone());
+pub fn GroupEditModal(group: ContactGroup, on_saved: EventHandler, on_close: EventHandler) -> Element {
+	let is_new = group.id == 0;
+	let group_id = group.id;
+	let mut name = use_signal(|| group.name.clone());
+	let mut member_ids = use_signal(|| group.member_ids.clone());
+	let mut contacts = use_signal(Vec::<Contact>::new);
+	let mut error_message = use_signal(String::new);
 	let mut show_confirm_delete = use_signal(|| false);
 	
+	use_hook(move || {
+		spawn(async move {
+			match AddressBook::call(AddressBook::get_all_contacts).await {
+				Ok(list) => contacts.set(list),
+				Err(e) => error_message.set(format!("加载联系人失败: {}", describe_error(&e))),
+			}
+		});
+	});
+	
+	let mut toggle_member = move |id: i64| {
+		let mut members = member_ids.write();
+		if members.contains(&id) {
+			members.retain(|m| *m != id);
+		} else {
+			members.push(id);
+		}
+	};
+	
+	let save = move |_| {
+		let group = ContactGroup {
+			id: group_id,
+			name: name.read().clone(),
+			member_ids: member_ids.read().clone(),
+		};
+		spawn(async move {
+			let result = if is_new {
+				AddressBook::call(move || AddressBook::add_group(&group).map(|_| ())).await
+			} else {
+				AddressBook::call(move || AddressBook::update_group(&group)).await
+			};
+			match result {
+				Ok(()) => on_saved.call(()),
+				Err(e) => error_message.set(format!("保存分组失败: {}", describe_error(&e))),
+			}
+		});
+	};
+	
+	let delete = move |_| {
+		spawn(async move {
+			match AddressBook::call(move || AddressBook::delete_group(group_id)).await {
+				Ok(()) => on_saved.call(()),
+				Err(e) => error_message.set(format!("删除分组失败: {}", describe_error(&e))),
+			}
+		});
+	};
+	
+	let contacts_list = contacts.read().clone();
+	
 	rsx! {
         div {
             style: "
@@ -313,65 +268,105 @@ fn IdentityEditModal(
                     border-radius: 12px;
                     width: 90%;
                     max-width: 500px;
+                    max-height: 90vh;
+                    display: flex;
+                    flex-direction: column;
                     box-shadow: 0 10px 25px rgba(0, 0, 0, 0.2);
                 ",
                 onclick: move |e| e.stop_propagation(),
                 
                 h3 {
                     style: "margin: 0 0 20px 0; color: #1f2937;",
-                    "编辑身份标识"
+                    if is_new { "新建分组" } else { "编辑分组" }
+                }
+                
+                if !error_message.read().is_empty() {
+                    div {
+                        style: "
+                            background: #fee2e2;
+                            border: 1px solid #fecaca;
+                            color: #dc2626;
+                            padding: 12px 16px;
+                            border-radius: 8px;
+                            margin-bottom: 20px;
+                        ",
+                        {error_message.read().as_str()}
+                    }
                 }
                 
-                // 表单
+                // 分组名称
                 div {
-                    style: "display: flex; flex-direction: column; gap: 16px;",
+                    label {
+                        style: "
+                            display: block;
+                            margin-bottom: 6px;
+                            font-weight: 500;
+                            color: #374151;
+                        ",
+                        "分组名称"
+                    }
+                    input {
+                        style: "
+                            width: 90%;
+                            padding: 10px 12px;
+                            border: 1px solid #d1d5db;
+                            border-radius: 6px;
+                            font-size: 14px;
+                        ",
+                        placeholder: "例如: 测试机",
+                        value: "{name}",
+                        oninput: move |e| name.set(e.value())
+                    }
+                }
+                
+                // 成员选择
+                div {
+                    style: "
+                        margin-top: 16px;
+                        font-weight: 500;
+                        color: #374151;
+                    ",
+                    "成员 (已选择 {member_ids.read().len()} 个)"
+                }
+                div {
+                    style: "
+                        flex: 1;
+                        overflow-y: auto;
+                        max-height: 300px;
+                        margin-top: 8px;
+                        border: 1px solid #e5e7eb;
+                        border-radius: 6px;
+                    ",
                     
-                    // 身份标识输入
-                    div {
-                        label {
-                            style: "
-                                display: block;
-                                margin-bottom: 6px;
-                                font-weight: 500;
-                                color: #374151;
-                            ",
-                            "身份标识"
-                        }
-                        input {
-                            style: "
-                                width: 90%;
-                                padding: 10px 12px;
-                                border: 1px solid #d1d5db;
-                                border-radius: 6px;
-                                font-size: 14px;
-                                font-family: monospace;
-                            ",
-                            value: "{identity_str}",
-                            oninput: move |e| identity_str.set(e.value())
+                    if contacts_list.is_empty() {
+                        div {
+                            style: "text-align: center; color: #6b7280; padding: 20px;",
+                            "暂无联系人"
                         }
                     }
-                    
-                    // 别名输入
-                    div {
+                    for contact in contacts_list {
                         label {
+                            key: "{contact.id}",
                             style: "
-                                display: block;
-                                margin-bottom: 6px;
-                                font-weight: 500;
-                                color: #374151;
-                            ",
-                            "备注"
-                        }
-                        input {
-                            style: "
-                                width: 90%;
-                                padding: 10px 12px;
-                                border: 1px solid #d1d5db;
-                                border-radius: 6px;
-                                font-size: 14px;
+                                display: flex;
+                                align-items: center;
+                                gap: 8px;
+                                padding: 8px 12px;
+                                border-bottom: 1px solid #f3f4f6;
+                                cursor: pointer;
                             ",
-                            value: "{alias}",
-                            oninput: move |e| alias.set(e.value())
+                            input {
+                                r#type: "checkbox",
+                                checked: member_ids.read().contains(&contact.id),
+                                onchange: move |_| toggle_member(contact.id),
+                            }
+                            span { "{contact.name}" }
+                            if contact.addresses.is_empty() {
+                                span {
+                                    style: "color: #9ca3af; font-size: 12px;",
+                                    "（无地址，发送时跳过）"
+                                }
+                            }
                         }
                     }
                 }
@@ -385,10 +380,12 @@ fn IdentityEditModal(
                         gap: 12px;
                     ",
                     
-                    // 删除按钮
-                    if !show_confirm_delete() {
+                    // 删除按钮，新建时不显示
+                    if is_new {
+                        div {}
+                    } else if !show_confirm_delete() {
                         button {
-							class: "modal-danger-button",
+                            class: "modal-danger-button",
                             style: "
                                 padding: 10px 16px;
                                 background: #ef4444;
@@ -411,7 +408,7 @@ fn IdentityEditModal(
                             }
                             
                             button {
-								class: "confirm-delete-button",
+                                class: "confirm-delete-button",
                                 style: "
                                     padding: 6px 12px;
                                     background: #ef4444;
@@ -421,14 +418,12 @@ fn IdentityEditModal(
                                     cursor: pointer;
                                     font-size: 12px;
                                 ",
-                                onclick: move |_| {
-                                    on_delete.call(identity.id);
-                                },
+                                onclick: delete,
                                 "确认删除"
                             }
                             
                             button {
-								class: "cancel-delete-button",
+                                class: "cancel-delete-button",
                                 style: "
                                     padding: 6px 12px;
                                     background: #6b7280;
@@ -444,12 +439,11 @@ fn IdentityEditModal(
                         }
                     }
                     
-                    // 保存和取消按钮
                     div {
                         style: "display: flex; gap: 12px;",
                         
                         button {
-							class: "modal-secondary-button",
+                            class: "modal-secondary-button",
                             style: "
                                 padding: 10px 20px;
                                 background: #6b7280;
@@ -464,7 +458,7 @@ fn IdentityEditModal(
                         }
                         
                         button {
-							class: "modal-button",
+                            class: "modal-button",
                             style: "
                                 padding: 10px 20px;
                                 background: #3b82f6;
@@ -474,9 +468,7 @@ fn IdentityEditModal(
                                 cursor: pointer;
                                 font-size: 14px;
                             ",
-                            onclick: move |_| {
-                                on_save.call((identity.id, identity_str.read().clone(), alias.read().clone()));
-                            },
+                            onclick: save,
                             "保存"
                         }
                     }
@@ -484,4 +476,4 @@ fn IdentityEditModal(
             }
         }
     }
-}
\ No newline at end of file
+}
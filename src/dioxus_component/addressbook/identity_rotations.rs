@@ -0,0 +1,144 @@
+// src/dioxus_component/addressbook/identity_rotations.rs
+use dioxus::prelude::*;
+use crate::core::db::{AddressBook, PendingRotation};
+use crate::core::verification::Verification;
+use crate::dioxus_component::error_message::describe_error;
+
+// 联系人更换身份码后的提示：旧身份码签名证明了新身份码，确认后更新通讯录；
+// 早期身份码没有私钥无法签名，只能提示用户与对方核对安全码后再更新
+#[component]
+pub fn PendingRotations(refresh_trigger: u32, on_changed: EventHandler) -> Element {
+	let mut pending = use_signal(Vec::<PendingRotation>::new);
+	let mut error_message = use_signal(|| None::<String>);
+	let mut my_identity = use_signal(String::new);
+	
+	use_hook(move || {
+		spawn(async move {
+			if let Ok(identity) = AddressBook::call(AddressBook::get_my_identity).await {
+				my_identity.set(identity);
+			}
+		});
+	});
+	
+	let load_pending = move || {
+		spawn(async move {
+			match AddressBook::call(AddressBook::get_pending_rotations).await {
+				Ok(list) => {
+					pending.set(list);
+					error_message.set(None);
+				}
+				Err(e) => error_message.set(Some(format!("加载身份码变更失败: {}", describe_error(&e)))),
+			}
+		});
+	};
+	
+	use_effect(use_reactive((&refresh_trigger,), move |_| {
+		load_pending();
+	}));
+	
+	let resolve = move |contact_id: i64, accept: bool| {
+		spawn(async move {
+			let result = AddressBook::call(move || {
+				if accept {
+					AddressBook::accept_identity_rotation(contact_id)
+				} else {
+					AddressBook::dismiss_identity_rotation(contact_id)
+				}
+			}).await;
+			match result {
+				Ok(()) => {
+					load_pending();
+					on_changed.call(());
+				}
+				Err(e) => error_message.set(Some(format!("处理身份码变更失败: {}", describe_error(&e)))),
+			}
+		});
+	};
+	
+	let pending_list = pending.read().clone();
+	
+	rsx! {
+        if let Some(error) = error_message.read().as_ref() {
+            div {
+                style: "color: #dc2626; font-size: 13px; margin-bottom: 10px;",
+                "{error}"
+            }
+        }
+        
+        for rotation in pending_list {
+            div {
+                key: "{rotation.contact.id}",
+                style: "
+                    background: #fff8e1;
+                    border: 1px solid #ffe082;
+                    border-radius: 6px;
+                    padding: 12px;
+                    margin-bottom: 10px;
+                    display: flex;
+                    align-items: center;
+                    gap: 10px;
+                ",
+                
+                div {
+                    style: "flex: 1; min-width: 0; font-size: 13px; color: #5d4037;",
+                    if rotation.proven {
+                        div {
+                            style: "font-weight: bold; margin-bottom: 4px;",
+                            "{rotation.contact.name} 更换了身份码，旧身份码已签名确认"
+                        }
+                    } else {
+                        div {
+                            style: "font-weight: bold; margin-bottom: 4px; color: #b91c1c;",
+                            "{rotation.contact.name} 的早期身份码无法签名，无法证明是本人更换"
+                        }
+                        div {
+                            style: "margin-bottom: 4px;",
+                            if let Some(code) = Verification::short_code(&my_identity.read(), &rotation.new_identity) {
+                                "请当面或通过电话与对方核对安全码 {code}，一致后再更新"
+                            } else {
+                                "请当面或通过电话与对方核对新身份码，一致后再更新"
+                            }
+                        }
+                    }
+                    div {
+                        style: "
+                            font-family: monospace;
+                            overflow: hidden;
+                            text-overflow: ellipsis;
+                            white-space: nowrap;
+                        ",
+                        title: "{rotation.new_identity}",
+                        "新身份码: {rotation.new_identity}"
+                    }
+                }
+                
+                button {
+                    style: "
+                        padding: 6px 12px;
+                        background: #3b82f6;
+                        color: white;
+                        border: none;
+                        border-radius: 6px;
+                        cursor: pointer;
+                        white-space: nowrap;
+                    ",
+                    onclick: move |_| resolve(rotation.contact.id, true),
+                    "更新"
+                }
+                button {
+                    style: "
+                        padding: 6px 12px;
+                        background: white;
+                        color: #374151;
+                        border: 1px solid #d1d5db;
+                        border-radius: 6px;
+                        cursor: pointer;
+                        white-space: nowrap;
+                    ",
+                    onclick: move |_| resolve(rotation.contact.id, false),
+                    "忽略"
+                }
+            }
+        }
+    }
+}
@@ -1,4 +1,7 @@
 pub mod addressbook;
 mod add_member;
-mod whitelist;
-mod friends;
\ No newline at end of file
+mod blocklist;
+mod contacts;
+mod exchange;
+mod groups;
+mod identity_rotations;
\ No newline at end of file
@@ -1,5 +1,13 @@
 use dioxus::prelude::*;
+use dioxus_desktop::{use_wry_event_handler, tao::event::Event, WindowEvent};
+use crate::core::db::AddressBook;
+use crate::core::error::NearbyError;
+use crate::core::filereceiver::FileReceiver;
+use crate::core::profile::Profiles;
+use log::error;
 use crate::dioxus_component::{Send, Receive, AddressBookPage, Settings};
+use crate::dioxus_component::lock_screen::LockScreen;
+use crate::dioxus_component::profile_picker::ProfilePicker;
 
 #[derive(Clone, PartialEq)]
 pub enum Page {
@@ -9,10 +17,107 @@ pub enum Page {
 	Settings,
 }
 
+// 全局界面状态：是否在选择档案、是否锁定、当前档案；恢复备份或切换档案后由设置页更新
+#[derive(Clone, Copy)]
+pub struct AppState {
+	pub picking: Signal<bool>,
+	pub locked: Signal<bool>,
+	pub profile: Signal<String>,
+}
+
+impl AppState {
+	// 启动时选择档案后打开它，身份私钥设置了口令时显示锁定界面，解锁后再进入启动时的接收模式
+	pub async fn open_profile(mut self, name: String) -> Result<(), NearbyError> {
+		let name = Profiles::find(&name)?;
+		let title = Profiles::window_title(&name);
+		let locked = AddressBook::call(move || {
+			Profiles::open(&name);
+			AddressBook::identity_key_locked()
+		}).await.unwrap_or(true);
+		dioxus_desktop::window().set_title(&title);
+		self.locked.set(locked);
+		self.profile.set(Profiles::active());
+		self.picking.set(false);
+		if !locked {
+			FileReceiver::apply_startup_mode().await?;
+		}
+		Ok(())
+	}
+	
+	// 切换档案后重新加载所有页面，新档案的身份私钥设置了口令时显示锁定界面，
+	// 解锁后再进入启动时的接收模式
+	pub async fn switch_profile(mut self, name: String) -> Result<(), NearbyError> {
+		Profiles::switch(&name).await?;
+		let locked = AddressBook::call(AddressBook::identity_key_locked).await.unwrap_or(true);
+		self.locked.set(locked);
+		self.profile.set(Profiles::active());
+		dioxus_desktop::window().set_title(&Profiles::window_title(&Profiles::active()));
+		if !locked {
+			FileReceiver::apply_startup_mode().await?;
+		}
+		Ok(())
+	}
+}
+
 #[component]
 pub fn App() -> Element {
 	let current_page = use_signal(|| Page::Receive);
 	
+	// 有多个档案且启动时没有指定时先选择档案，选择前不打开任何档案的数据库
+	let picking = use_signal(|| !Profiles::is_open());
+	
+	// 身份私钥设置了口令时先显示锁定界面
+	let mut locked = use_signal(|| {
+		if *picking.peek() {
+			return false;
+		}
+		match AddressBook::identity_key_locked() {
+			Ok(locked) => locked,
+			Err(e) => {
+				error!("读取身份密钥状态失败: {}", e);
+				false
+			}
+		}
+	});
+	let profile = use_signal(Profiles::active);
+	use_context_provider(|| AppState { picking, locked, profile });
+	
+	// 按设置进入启动时的接收模式，锁定时等解锁后再进入
+	let apply_startup_mode = || {
+		spawn(async {
+			if let Err(e) = FileReceiver::apply_startup_mode().await {
+				error!("设置启动接收模式失败: {}", e);
+			}
+		});
+	};
+	use_hook(|| {
+		if !*picking.peek() && !*locked.peek() {
+			apply_startup_mode();
+		}
+	});
+	
+	// 关闭窗口时先等待进行中的传输结束再退出；等待期间再次关闭则不再等待
+	let mut exiting = use_signal(|| false);
+	use_wry_event_handler(move |event, _| {
+		if let Event::WindowEvent { event: WindowEvent::CloseRequested, .. } = event {
+			if *exiting.peek() {
+				FileReceiver::cancel_drain();
+				return;
+			}
+			exiting.set(true);
+			spawn(async move {
+				// 关闭时窗口会被隐藏，仍有传输时重新显示，让用户看到排空进度
+				if FileReceiver::active_transfer_count() > 0 {
+					let window = dioxus_desktop::window();
+					window.set_visible(true);
+					window.set_focus();
+				}
+				FileReceiver::shutdown().await;
+				std::process::exit(0);
+			});
+		}
+	});
+	
 	rsx! {
         style { {include_str!("../../assets/main.css")} }
         
@@ -28,24 +133,195 @@ pub fn App() -> Element {
                 left: 0;
             ",
             
-            // 主内容区域 - 占90%高度
+            if picking() {
+                ProfilePicker {}
+            } else if locked() {
+                LockScreen {
+                    on_unlocked: move |_| {
+                        locked.set(false);
+                        apply_startup_mode();
+                    }
+                }
+            } else {
+                // 主内容区域 - 占90%高度；以档案名称为 key，切换档案后所有页面重新加载
+                for profile in [profile()] {
+                    div {
+                        key: "{profile}",
+                        style: "
+                            flex: 1;
+                            overflow: hidden;
+                            display: flex;
+                            flex-direction: column;
+                        ",
+                        match current_page() {
+                            Page::Send => rsx! { Send {} },
+                            Page::Receive => rsx! { Receive {} },
+                            Page::AddressBook => rsx! { AddressBookPage {} },
+                            Page::Settings => rsx! { Settings {} },
+                        }
+                        
+                        LegacyIdentityNotice {}
+                    }
+                }
+                
+                // 底部导航栏 - 占10%高度
+                BottomNav { current_page: current_page }
+            }
+            
+            if exiting() {
+                ExitOverlay {}
+            }
+        }
+    }
+}
+
+// 退出时等待进行中的传输结束，显示剩余数量，可以选择不再等待
+#[component]
+fn ExitOverlay() -> Element {
+	let mut remaining = use_signal(FileReceiver::active_transfer_count);
+	let mut cancelling = use_signal(|| false);
+	
+	use_future(move || async move {
+		loop {
+			let count = FileReceiver::active_transfer_count();
+			if *remaining.peek() != count {
+				remaining.set(count);
+			}
+			tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+		}
+	});
+	
+	rsx! {
+        div {
+            style: "
+                position: fixed;
+                inset: 0;
+                z-index: 1000;
+                display: flex;
+                align-items: center;
+                justify-content: center;
+                background-color: rgba(0, 0, 0, 0.4);
+            ",
+            
             div {
                 style: "
-                    flex: 1;
-                    overflow: hidden;
+                    width: 300px;
+                    padding: 24px;
+                    background: white;
+                    border-radius: 12px;
                     display: flex;
                     flex-direction: column;
+                    gap: 15px;
+                    text-align: center;
                 ",
-                match current_page() {
-                    Page::Send => rsx! { Send {} },
-                    Page::Receive => rsx! { Receive {} },
-                    Page::AddressBook => rsx! { AddressBookPage {} },
-                    Page::Settings => rsx! { Settings {} },
+                
+                h3 {
+                    style: "margin: 0; color: #333;",
+                    "正在退出"
+                }
+                
+                p {
+                    style: "margin: 0; color: #666; font-size: 14px;",
+                    if cancelling() {
+                        "正在取消剩余的 {remaining} 个传输..."
+                    } else {
+                        "等待 {remaining} 个进行中的传输结束，最长 {FileReceiver::get_drain_grace_period()} 秒"
+                    }
+                }
+                
+                button {
+                    style: "
+                        padding: 10px;
+                        background: white;
+                        color: #c62828;
+                        border: 1px solid #ef9a9a;
+                        border-radius: 6px;
+                        cursor: pointer;
+                        font-size: 14px;
+                    ",
+                    disabled: cancelling(),
+                    onclick: move |_| {
+                        cancelling.set(true);
+                        FileReceiver::cancel_drain();
+                    },
+                    "取消传输并立即退出"
                 }
             }
+        }
+    }
+}
+
+// 早期身份码没有私钥，打开档案时已更换为新的身份码，提示用户联系人会看到无法证明的变更
+#[component]
+fn LegacyIdentityNotice() -> Element {
+	let mut replaced = use_signal(AddressBook::take_legacy_replacement);
+	let Some(old_identity) = replaced() else {
+		return rsx! {};
+	};
+	
+	rsx! {
+        div {
+            style: "
+                position: fixed;
+                inset: 0;
+                z-index: 1000;
+                display: flex;
+                align-items: center;
+                justify-content: center;
+                background-color: rgba(0, 0, 0, 0.4);
+            ",
             
-            // 底部导航栏 - 占10%高度
-            BottomNav { current_page: current_page }
+            div {
+                style: "
+                    width: 360px;
+                    padding: 24px;
+                    background: white;
+                    border-radius: 12px;
+                    display: flex;
+                    flex-direction: column;
+                    gap: 15px;
+                ",
+                
+                h3 {
+                    style: "margin: 0; color: #333;",
+                    "身份码已更换"
+                }
+                
+                p {
+                    style: "margin: 0; color: #666; font-size: 14px;",
+                    "原来的身份码是早期版本生成的，没有对应的私钥，无法证明你的身份，已更换为新的身份码。"
+                }
+                
+                p {
+                    style: "
+                        margin: 0;
+                        color: #666;
+                        font-size: 12px;
+                        font-family: monospace;
+                        word-break: break-all;
+                    ",
+                    "原身份码: {old_identity}"
+                }
+                
+                p {
+                    style: "margin: 0; color: #666; font-size: 14px;",
+                    "旧身份码无法签名，联系人收到你的文件时会看到一个无法证明的身份码变更。请通知对方，并当面或通过电话核对安全码后再让对方更新。"
+                }
+                
+                button {
+                    style: "
+                        padding: 10px;
+                        background: #3b82f6;
+                        color: white;
+                        border: none;
+                        border-radius: 6px;
+                        cursor: pointer;
+                        font-size: 14px;
+                    ",
+                    onclick: move |_| replaced.set(None),
+                    "知道了"
+                }
+            }
         }
     }
 }
@@ -0,0 +1,111 @@
+// src/dioxus_component/error_message.rs
+// 把核心模块的错误转换为界面上显示的提示，尽量告诉用户下一步该怎么做
+use std::io::ErrorKind;
+use crate::core::error::{NearbyError, RejectReason};
+use crate::core::settings::Settings;
+
+pub fn describe_error(error: &NearbyError) -> String {
+    match error {
+        NearbyError::Io(e) => match e.kind() {
+            ErrorKind::NotFound => "找不到文件，请确认文件没有被移动或删除".to_string(),
+            ErrorKind::PermissionDenied => "没有访问该文件的权限，请检查文件或文件夹权限".to_string(),
+            ErrorKind::StorageFull => "磁盘空间不足，请清理后重试".to_string(),
+            _ => format!("文件读写失败: {}", e),
+        },
+        NearbyError::Network(e) => match e.kind() {
+            ErrorKind::ConnectionRefused => format!(
+                "无法连接到对方，请确认对方已开启接收且防火墙允许对方的接收端口（默认 {}）",
+                Settings::current().port,
+            ),
+            ErrorKind::HostUnreachable | ErrorKind::NetworkUnreachable => {
+                "找不到对方，请确认地址正确且双方网络都支持 IPv6".to_string()
+            }
+            ErrorKind::ConnectionReset | ErrorKind::ConnectionAborted
+            | ErrorKind::BrokenPipe | ErrorKind::UnexpectedEof => {
+                "连接被对方中断，请重试".to_string()
+            }
+            _ => format!("网络错误: {}", e),
+        },
+        NearbyError::Timeout { phase, secs } => {
+            format!("{}超时 ({} 秒)，对方可能已离线或网络不稳定", phase, secs)
+        }
+        NearbyError::Handshake(_) => "加密握手失败，请确认对方使用的是兼容版本的 Nearby".to_string(),
+        NearbyError::Protocol(_) => "收到无法识别的数据，请确认双方使用相同版本的 Nearby".to_string(),
+        NearbyError::Rejected(RejectReason::Busy) => "对方正忙，请稍后再试".to_string(),
+        NearbyError::Rejected(RejectReason::ShuttingDown) => "对方正在关闭接收，请稍后再试".to_string(),
+        NearbyError::Rejected(RejectReason::CodeUnavailable) => {
+            "对方的接收口令已过期或已被使用，请让对方生成新的口令".to_string()
+        }
+        NearbyError::Rejected(RejectReason::Refused) => {
+            "对方拒绝了本次传输，请确认对方已开启接收并把你加入白名单".to_string()
+        }
+        NearbyError::IdentityMismatch => {
+            "对方不是该联系人的设备（身份密钥不符），已停止发送；如果对方更换过身份码，请与对方核对安全码后更新联系人".to_string()
+        }
+        NearbyError::Validation(message) => message.clone(),
+        NearbyError::Database(e) => match e.sqlite_error_code() {
+            Some(rusqlite::ErrorCode::ConstraintViolation) => "该记录已存在".to_string(),
+            Some(rusqlite::ErrorCode::DatabaseBusy) | Some(rusqlite::ErrorCode::DatabaseLocked) => {
+                "数据库正忙，请稍后重试".to_string()
+            }
+            _ => format!("数据库操作失败: {}", e),
+        },
+        NearbyError::DatabaseVersion { .. } => "数据库由更新版本的 Nearby 创建，请升级程序".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io;
+    
+    #[test]
+    fn io_errors_by_kind() {
+        let io_error = |kind| NearbyError::Io(io::Error::from(kind));
+        assert_eq!(describe_error(&io_error(ErrorKind::NotFound)), "找不到文件，请确认文件没有被移动或删除");
+        assert_eq!(describe_error(&io_error(ErrorKind::StorageFull)), "磁盘空间不足，请清理后重试");
+        assert!(describe_error(&io_error(ErrorKind::Other)).starts_with("文件读写失败"));
+        // 未特别说明的 I/O 错误视为本地文件错误
+        assert!(matches!(NearbyError::from(io::Error::from(ErrorKind::NotFound)), NearbyError::Io(_)));
+    }
+    
+    #[test]
+    fn network_errors_by_kind() {
+        let network_error = |kind| NearbyError::Network(io::Error::from(kind));
+        let refused = describe_error(&network_error(ErrorKind::ConnectionRefused));
+        assert!(refused.contains(&format!("默认 {}", Settings::current().port)));
+        assert_eq!(
+            describe_error(&network_error(ErrorKind::NetworkUnreachable)),
+            "找不到对方，请确认地址正确且双方网络都支持 IPv6",
+        );
+        assert_eq!(describe_error(&network_error(ErrorKind::UnexpectedEof)), "连接被对方中断，请重试");
+    }
+    
+    #[test]
+    fn rejections_and_protocol_errors() {
+        assert_eq!(describe_error(&NearbyError::Rejected(RejectReason::Busy)), "对方正忙，请稍后再试");
+        assert!(describe_error(&NearbyError::Rejected(RejectReason::Refused)).contains("白名单"));
+        assert!(describe_error(&NearbyError::Rejected(RejectReason::CodeUnavailable)).contains("口令"));
+        assert!(describe_error(&NearbyError::IdentityMismatch).contains("安全码"));
+        assert!(describe_error(&NearbyError::Handshake("bad".to_string())).starts_with("加密握手失败"));
+        assert!(describe_error(&NearbyError::protocol("bad")).starts_with("收到无法识别的数据"));
+        assert_eq!(
+            describe_error(&NearbyError::Timeout { phase: "握手".to_string(), secs: 10 }),
+            "握手超时 (10 秒)，对方可能已离线或网络不稳定",
+        );
+    }
+    
+    #[test]
+    fn validation_and_database_errors() {
+        assert_eq!(describe_error(&NearbyError::validation("端口无效")), "端口无效");
+        assert_eq!(
+            describe_error(&NearbyError::DatabaseVersion { found: 99, supported: 15 }),
+            "数据库由更新版本的 Nearby 创建，请升级程序",
+        );
+        
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE t (v TEXT UNIQUE); INSERT INTO t VALUES ('a');").unwrap();
+        let duplicate = conn.execute("INSERT INTO t VALUES ('a')", []).unwrap_err();
+        assert_eq!(describe_error(&NearbyError::from(duplicate)), "该记录已存在");
+    }
+}
@@ -0,0 +1,143 @@
+use dioxus::prelude::*;
+use crate::core::db::AddressBook;
+use crate::core::profile::Profiles;
+use crate::dioxus_component::app::AppState;
+use crate::dioxus_component::error_message::describe_error;
+
+// 身份私钥设置了口令时，启动后先输入口令解锁；也可以切换到其他档案
+#[component]
+pub fn LockScreen(on_unlocked: EventHandler) -> Element {
+	let app_state = use_context::<AppState>();
+	let mut passphrase = use_signal(String::new);
+	let mut error_message = use_signal(String::new);
+	let mut unlocking = use_signal(|| false);
+	let profiles = use_signal(Profiles::list);
+	
+	let mut unlock = move || {
+		if unlocking() {
+			return;
+		}
+		let value = passphrase.read().clone();
+		unlocking.set(true);
+		spawn(async move {
+			match AddressBook::call(move || AddressBook::unlock_identity_key(&value)).await {
+				Ok(()) => on_unlocked.call(()),
+				Err(e) => {
+					error_message.set(describe_error(&e));
+					passphrase.set(String::new());
+				}
+			}
+			unlocking.set(false);
+		});
+	};
+	
+	let switch_profile = move |name: String| {
+		spawn(async move {
+			error_message.set(String::new());
+			if let Err(e) = app_state.switch_profile(name).await {
+				error_message.set(format!("切换档案失败: {}", describe_error(&e)));
+			}
+		});
+	};
+	
+	let active = Profiles::active();
+	let profile_list = profiles.read().clone();
+	
+	rsx! {
+        div {
+            style: "
+                height: 100%;
+                display: flex;
+                align-items: center;
+                justify-content: center;
+                background-color: #f5f5f5;
+            ",
+            
+            div {
+                style: "
+                    width: 320px;
+                    padding: 30px;
+                    background: white;
+                    border-radius: 12px;
+                    border: 1px solid #e0e0e0;
+                    display: flex;
+                    flex-direction: column;
+                    gap: 15px;
+                ",
+                
+                h2 {
+                    style: "margin: 0; color: #333; text-align: center;",
+                    "Nearby 已锁定"
+                }
+                
+                p {
+                    style: "margin: 0; color: #666; font-size: 14px; text-align: center;",
+                    "输入口令以解锁档案 {active} 的身份密钥"
+                }
+                
+                if profile_list.len() > 1 {
+                    select {
+                        style: "
+                            padding: 8px;
+                            border: 1px solid #ddd;
+                            border-radius: 6px;
+                            font-size: 14px;
+                        ",
+                        value: "{active}",
+                        disabled: unlocking(),
+                        onchange: move |e| switch_profile(e.value()),
+                        for name in profile_list {
+                            option {
+                                key: "{name}",
+                                value: "{name}",
+                                selected: name == active,
+                                "档案: {name}"
+                            }
+                        }
+                    }
+                }
+                
+                input {
+                    r#type: "password",
+                    style: "
+                        padding: 10px;
+                        border: 1px solid #ddd;
+                        border-radius: 6px;
+                        font-size: 14px;
+                    ",
+                    placeholder: "口令",
+                    autofocus: true,
+                    value: "{passphrase}",
+                    oninput: move |e| passphrase.set(e.value()),
+                    onkeydown: move |e| {
+                        if e.key() == Key::Enter {
+                            unlock();
+                        }
+                    },
+                }
+                
+                if !error_message.read().is_empty() {
+                    div {
+                        style: "color: #c62828; font-size: 13px; text-align: center;",
+                        "{error_message}"
+                    }
+                }
+                
+                button {
+                    style: "
+                        padding: 10px;
+                        background-color: #007bff;
+                        color: white;
+                        border: none;
+                        border-radius: 6px;
+                        cursor: pointer;
+                        font-size: 14px;
+                    ",
+                    disabled: unlocking() || passphrase.read().is_empty(),
+                    onclick: move |_| unlock(),
+                    if unlocking() { "正在解锁..." } else { "解锁" }
+                }
+            }
+        }
+    }
+}
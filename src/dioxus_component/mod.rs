@@ -3,6 +3,9 @@ mod receive;
 mod addressbook;
 mod settings;
 mod app;
+mod error_message;
+mod lock_screen;
+mod profile_picker;
 // 新增app模块
 
 // 导出页面组件
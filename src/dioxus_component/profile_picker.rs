@@ -0,0 +1,87 @@
+use dioxus::prelude::*;
+use crate::core::profile::Profiles;
+use crate::dioxus_component::app::AppState;
+use crate::dioxus_component::error_message::describe_error;
+
+// 有多个档案且启动时没有用 --profile 指定时，先选择要使用的档案，不会自动打开上次的档案
+#[component]
+pub fn ProfilePicker() -> Element {
+	let app_state = use_context::<AppState>();
+	let profiles = use_signal(Profiles::list);
+	let mut error_message = use_signal(String::new);
+	let mut opening = use_signal(|| false);
+	
+	let mut open = move |name: String| {
+		if opening() {
+			return;
+		}
+		opening.set(true);
+		spawn(async move {
+			if let Err(e) = app_state.open_profile(name).await {
+				error_message.set(format!("打开档案失败: {}", describe_error(&e)));
+			}
+			opening.set(false);
+		});
+	};
+	
+	let profile_list = profiles.read().clone();
+	
+	rsx! {
+        div {
+            style: "
+                height: 100%;
+                display: flex;
+                align-items: center;
+                justify-content: center;
+                background-color: #f5f5f5;
+            ",
+            
+            div {
+                style: "
+                    width: 320px;
+                    padding: 30px;
+                    background: white;
+                    border-radius: 12px;
+                    border: 1px solid #e0e0e0;
+                    display: flex;
+                    flex-direction: column;
+                    gap: 10px;
+                ",
+                
+                h2 {
+                    style: "margin: 0 0 5px 0; color: #333; text-align: center;",
+                    "选择档案"
+                }
+                
+                for name in profile_list {
+                    button {
+                        key: "{name}",
+                        style: "
+                            padding: 10px;
+                            background: white;
+                            color: #333;
+                            border: 1px solid #ddd;
+                            border-radius: 6px;
+                            cursor: pointer;
+                            font-size: 14px;
+                            text-align: left;
+                        ",
+                        disabled: opening(),
+                        onclick: {
+                            let name = name.clone();
+                            move |_| open(name.clone())
+                        },
+                        "{name}"
+                    }
+                }
+                
+                if !error_message.read().is_empty() {
+                    div {
+                        style: "color: #c62828; font-size: 13px; text-align: center;",
+                        "{error_message}"
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,163 @@
+// src/dioxus_component/receive/banned.rs
+use dioxus::prelude::*;
+use crate::core::peer_guard::{BannedPeer, PeerGuard};
+
+#[component]
+pub fn BannedPeersWindow(on_close: EventHandler) -> Element {
+    let mut banned_peers = use_signal(|| PeerGuard::banned_peers());
+    
+    // 每秒刷新一次剩余封禁时间
+    use_future(move || async move {
+        loop {
+            tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+            banned_peers.set(PeerGuard::banned_peers());
+        }
+    });
+    
+    // 格式化剩余时间
+    fn format_remaining(secs: u64) -> String {
+        if secs >= 60 {
+            format!("{} 分 {} 秒", secs / 60, secs % 60)
+        } else {
+            format!("{} 秒", secs)
+        }
+    }
+    
+    rsx! {
+        div {
+            style: "
+                position: fixed;
+                top: 0;
+                left: 0;
+                width: 100vw;
+                height: 100vh;
+                background-color: white;
+                z-index: 1000;
+                display: flex;
+                flex-direction: column;
+                overflow: hidden;
+            ",
+            
+            // 标题栏
+            div {
+                style: "
+                    display: flex;
+                    justify-content: space-between;
+                    align-items: center;
+                    padding: 20px 24px;
+                    border-bottom: 1px solid #e0e0e0;
+                    background-color: #fafafa;
+                    flex-shrink: 0;
+                ",
+                
+                button {
+                    class: "back-item",
+                    style: "
+                        background: none;
+                        border: none;
+                        cursor: pointer;
+                        padding: 6px 12px;
+                        border-radius: 6px;
+                        width: 48px;
+                        height: 48px;
+                        display: flex;
+                        align-items: center;
+                        justify-content: center;
+                    ",
+                    onclick: move |_| on_close.call(()),
+                    img {
+                        style: "width: 30px; height: 30px;",
+                        src: asset!("assets/back-100.png")
+                    }
+                }
+                
+                h2 {
+                    style: "margin: 0; font-size: 24px; color: #333;",
+                    "封禁列表"
+                }
+                
+                // 占位，保持标题居中
+                div { style: "width: 48px;" }
+            }
+            
+            // 封禁列表内容
+            div {
+                style: "
+                    flex: 1;
+                    overflow-y: auto;
+                ",
+                
+                if banned_peers.read().is_empty() {
+                    p {
+                        style: "
+                            text-align: center;
+                            color: #999;
+                            margin-top: 50px;
+                            font-size: 16px;
+                        ",
+                        "当前没有被封禁的地址"
+                    }
+                } else {
+                    for peer in banned_peers.read().iter().cloned() {
+                        BannedPeerItem {
+                            key: "{peer.address}",
+                            peer: peer.clone(),
+                            remaining: format_remaining(peer.remaining_secs),
+                            on_unban: move |_| {
+                                PeerGuard::unban(peer.address);
+                                banned_peers.set(PeerGuard::banned_peers());
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn BannedPeerItem(peer: BannedPeer, remaining: String, on_unban: EventHandler) -> Element {
+    rsx! {
+        div {
+            style: "
+                display: flex;
+                align-items: center;
+                justify-content: space-between;
+                padding: 16px 24px;
+                border-bottom: 1px solid #f0f0f0;
+            ",
+            
+            div {
+                style: "flex: 1;",
+                div {
+                    style: "
+                        font-family: monospace;
+                        font-size: 15px;
+                        color: #333;
+                        margin-bottom: 4px;
+                        word-break: break-all;
+                    ",
+                    "{peer.label()}"
+                }
+                div {
+                    style: "font-size: 12px; color: #666;",
+                    "剩余封禁时间: {remaining}"
+                }
+            }
+            
+            button {
+                style: "
+                    background: #3b82f6;
+                    color: white;
+                    border: none;
+                    padding: 8px 16px;
+                    border-radius: 6px;
+                    cursor: pointer;
+                    font-size: 14px;
+                ",
+                onclick: move |_| on_unban.call(()),
+                "解除封禁"
+            }
+        }
+    }
+}
@@ -0,0 +1,66 @@
+// src/dioxus_component/receive/block_actions.rs
+use dioxus::prelude::*;
+use crate::core::db::{AddressBook, BlockKind};
+use crate::dioxus_component::error_message::describe_error;
+
+const BLOCK_BUTTON_STYLE: &str = "
+    background: white;
+    color: #c62828;
+    border: 1px solid #ef9a9a;
+    padding: 4px 10px;
+    border-radius: 6px;
+    cursor: pointer;
+    font-size: 12px;
+    white-space: nowrap;
+";
+
+// 把记录中的身份码或来源地址加入屏蔽列表，source 写入备注说明来源
+#[component]
+pub fn BlockButtons(identity: String, address: String, source: &'static str) -> Element {
+    let mut status_message = use_signal(String::new);
+    
+    let block = move |kind: BlockKind, value: String| {
+        spawn(async move {
+            let note = format!("从{}添加", source);
+            let result = AddressBook::call(move || AddressBook::add_block_entry(kind, &value, &note)).await;
+            match result {
+                Ok(_) => status_message.set(format!("已屏蔽该{}", kind.label())),
+                Err(e) => status_message.set(describe_error(&e)),
+            }
+        });
+    };
+    
+    rsx! {
+        div {
+            style: "display: flex; align-items: center; gap: 8px;",
+            
+            // 身份码不完整时无法屏蔽
+            if identity.len() == 64 {
+                button {
+                    style: BLOCK_BUTTON_STYLE,
+                    onclick: {
+                        let identity = identity.clone();
+                        move |_| block(BlockKind::Identity, identity.clone())
+                    },
+                    "屏蔽身份码"
+                }
+            }
+            if !address.is_empty() {
+                button {
+                    style: BLOCK_BUTTON_STYLE,
+                    onclick: {
+                        let address = address.clone();
+                        move |_| block(BlockKind::Prefix, address.clone())
+                    },
+                    "屏蔽地址"
+                }
+            }
+            if !status_message.read().is_empty() {
+                span {
+                    style: "font-size: 12px; color: #666;",
+                    "{status_message}"
+                }
+            }
+        }
+    }
+}
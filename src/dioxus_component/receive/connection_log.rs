@@ -0,0 +1,313 @@
+// src/dioxus_component/receive/connection_log.rs
+use dioxus::prelude::*;
+use crate::core::audit_export::AuditExport;
+use crate::core::db::{AddressBook, ConnectionEvent, ConnectionOutcome};
+use humansize::{format_size, DECIMAL};
+use super::block_actions::BlockButtons;
+
+#[component]
+pub fn ConnectionLogWindow(on_close: EventHandler) -> Element {
+    let mut events = use_signal(|| Vec::<ConnectionEvent>::new());
+    let mut outcome_filter = use_signal(|| None::<ConnectionOutcome>);
+    let mut search_query = use_signal(|| String::new());
+    let mut status_message = use_signal(|| String::new());
+    
+    // 筛选条件变化时重新加载
+    use_effect(move || {
+        let outcome = outcome_filter();
+        let query = search_query();
+        spawn(async move {
+            match AddressBook::call(move || AddressBook::get_connection_events(outcome, &query)).await {
+                Ok(list) => events.set(list),
+                Err(e) => log::error!("加载连接记录失败: {}", e),
+            }
+        });
+    });
+    
+    // 导出当前筛选结果为 CSV
+    let export_csv = move |_| {
+        let csv = match AuditExport::to_csv(&events.read()) {
+            Ok(csv) => csv,
+            Err(e) => {
+                status_message.set(format!("导出失败: {}", e));
+                return;
+            }
+        };
+        spawn(async move {
+            let file = rfd::AsyncFileDialog::new()
+                .set_title("导出连接记录")
+                .set_file_name("connection_events.csv")
+                .save_file()
+                .await;
+            
+            if let Some(file) = file {
+                match tokio::fs::write(file.path(), csv).await {
+                    Ok(()) => status_message.set(format!("已导出到 {}", file.path().display())),
+                    Err(e) => status_message.set(format!("导出失败: {}", e)),
+                }
+            }
+        });
+    };
+    
+    // 清除所有记录
+    let clear_all = move |_| {
+        spawn(async move {
+            match AddressBook::call(AddressBook::delete_all_connection_events).await {
+                Ok(()) => events.set(Vec::new()),
+                Err(e) => log::error!("清除连接记录失败: {}", e),
+            }
+        });
+    };
+    
+    rsx! {
+        div {
+            style: "
+                position: fixed;
+                top: 0;
+                left: 0;
+                width: 100vw;
+                height: 100vh;
+                background-color: white;
+                z-index: 1000;
+                display: flex;
+                flex-direction: column;
+                overflow: hidden;
+            ",
+            
+            // 标题栏
+            div {
+                style: "
+                    display: flex;
+                    justify-content: space-between;
+                    align-items: center;
+                    padding: 20px 24px;
+                    border-bottom: 1px solid #e0e0e0;
+                    background-color: #fafafa;
+                    flex-shrink: 0;
+                ",
+                
+                button {
+                    class: "back-item",
+                    style: "
+                        background: none;
+                        border: none;
+                        cursor: pointer;
+                        padding: 6px 12px;
+                        border-radius: 6px;
+                        width: 48px;
+                        height: 48px;
+                        display: flex;
+                        align-items: center;
+                        justify-content: center;
+                    ",
+                    onclick: move |_| on_close.call(()),
+                    img {
+                        style: "width: 30px; height: 30px;",
+                        src: asset!("assets/back-100.png")
+                    }
+                }
+                
+                h2 {
+                    style: "margin: 0; font-size: 24px; color: #333;",
+                    "连接记录"
+                }
+                
+                div {
+                    style: "display: flex; gap: 8px;",
+                    button {
+                        style: "
+                            background: #3b82f6;
+                            color: white;
+                            border: none;
+                            padding: 8px 16px;
+                            border-radius: 6px;
+                            cursor: pointer;
+                            font-size: 14px;
+                        ",
+                        onclick: export_csv,
+                        "导出 CSV"
+                    }
+                    button {
+                        style: "
+                            background: #ff4444;
+                            color: white;
+                            border: none;
+                            padding: 8px 16px;
+                            border-radius: 6px;
+                            cursor: pointer;
+                            font-size: 14px;
+                        ",
+                        onclick: clear_all,
+                        "清除全部"
+                    }
+                }
+            }
+            
+            // 筛选栏
+            div {
+                style: "
+                    display: flex;
+                    gap: 12px;
+                    padding: 16px 24px;
+                    border-bottom: 1px solid #e0e0e0;
+                    background-color: #f8f8f8;
+                    flex-shrink: 0;
+                ",
+                select {
+                    style: "
+                        padding: 10px 12px;
+                        border: 1px solid #ddd;
+                        border-radius: 8px;
+                        font-size: 14px;
+                    ",
+                    onchange: move |e| {
+                        let value = e.value();
+                        outcome_filter.set(
+                            ConnectionOutcome::ALL.into_iter().find(|o| o.as_str() == value)
+                        );
+                    },
+                    option { value: "", "全部结果" }
+                    for outcome in ConnectionOutcome::ALL {
+                        option {
+                            value: outcome.as_str(),
+                            selected: outcome_filter() == Some(outcome),
+                            "{outcome.label()}"
+                        }
+                    }
+                }
+                input {
+                    style: "
+                        flex: 1;
+                        padding: 10px 16px;
+                        border: 1px solid #ddd;
+                        border-radius: 8px;
+                        font-size: 14px;
+                        outline: none;
+                    ",
+                    r#type: "text",
+                    placeholder: "搜索地址或身份码...",
+                    value: "{search_query}",
+                    oninput: move |e| search_query.set(e.value())
+                }
+            }
+            
+            if !status_message.read().is_empty() {
+                div {
+                    style: "
+                        padding: 8px 24px;
+                        font-size: 13px;
+                        color: #16a34a;
+                        background: #f0fdf4;
+                        flex-shrink: 0;
+                    ",
+                    "{status_message}"
+                }
+            }
+            
+            // 记录列表
+            div {
+                style: "
+                    flex: 1;
+                    overflow-y: auto;
+                ",
+                
+                if events.read().is_empty() {
+                    p {
+                        style: "
+                            text-align: center;
+                            color: #999;
+                            margin-top: 50px;
+                            font-size: 16px;
+                        ",
+                        "暂无连接记录"
+                    }
+                } else {
+                    for event in events.read().iter().cloned() {
+                        ConnectionEventItem {
+                            key: "{event.id}",
+                            event: event
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[component]
+fn ConnectionEventItem(event: ConnectionEvent) -> Element {
+    let (bg_color, text_color) = match event.outcome {
+        ConnectionOutcome::Accepted => ("#e8f5e8", "#2e7d32"),
+        ConnectionOutcome::Closed => ("#f0f0f0", "#666"),
+        _ => ("#ffebee", "#c62828"),
+    };
+    let identity = if event.claimed_identity.is_empty() {
+        "未提供身份码".to_string()
+    } else {
+        event.claimed_identity.clone()
+    };
+    
+    rsx! {
+        div {
+            style: "
+                display: flex;
+                align-items: center;
+                gap: 16px;
+                padding: 12px 24px;
+                border-bottom: 1px solid #f0f0f0;
+            ",
+            
+            span {
+                style: "
+                    padding: 4px 10px;
+                    border-radius: 12px;
+                    font-size: 12px;
+                    white-space: nowrap;
+                    background-color: {bg_color};
+                    color: {text_color};
+                ",
+                "{event.outcome.label()}"
+            }
+            
+            div {
+                style: "flex: 1; min-width: 0;",
+                div {
+                    style: "
+                        font-family: monospace;
+                        font-size: 14px;
+                        color: #333;
+                        word-break: break-all;
+                    ",
+                    "{event.source_address}"
+                }
+                div {
+                    style: "
+                        font-family: monospace;
+                        font-size: 12px;
+                        color: #666;
+                        overflow: hidden;
+                        text-overflow: ellipsis;
+                        white-space: nowrap;
+                    ",
+                    title: "{event.authenticated_identity}",
+                    "{identity}"
+                }
+            }
+            
+            div {
+                style: "text-align: right; font-size: 12px; color: #666; white-space: nowrap;",
+                div { "{event.occurred_at}" }
+                div { "{format_size(event.bytes, DECIMAL)}" }
+            }
+            
+            // 已屏蔽的连接不再显示屏蔽按钮
+            if event.outcome != ConnectionOutcome::RejectedBlocked {
+                BlockButtons {
+                    identity: event.claimed_identity.clone(),
+                    address: event.source_address.clone(),
+                    source: "连接记录",
+                }
+            }
+        }
+    }
+}
@@ -1,8 +1,11 @@
 // src/dioxus_component/receive/history
 use dioxus::prelude::*;
 use crate::core::db::{AddressBook, FileReceiveRecord};
+use crate::core::protocol::ConflictResolution;
+use crate::core::verification::Verification;
 use chrono::{DateTime, Local, NaiveDateTime};
 use humansize::{format_size, DECIMAL};
+use super::block_actions::BlockButtons;
 
 #[component]
 pub fn HistoryWindow(on_close: EventHandler) -> Element {
@@ -10,11 +13,15 @@ pub fn HistoryWindow(on_close: EventHandler) -> Element {
     let mut selected_record = use_signal(|| None);
     let mut search_query = use_signal(|| String::new());
     let mut show_detail_dialog = use_signal(|| false);
+    let mut my_identity = use_signal(|| String::new());
     
     // 加载历史记录
     use_effect(move || {
         spawn(async move {
-            match AddressBook::get_all_file_receive_records() {
+            if let Ok(identity) = AddressBook::call(AddressBook::get_my_identity).await {
+                my_identity.set(identity);
+            }
+            match AddressBook::call(AddressBook::get_all_file_receive_records).await {
                 Ok(records) => {
                     file_records.set(records);
                 }
@@ -36,6 +43,7 @@ pub fn HistoryWindow(on_close: EventHandler) -> Element {
                 .into_iter()
                 .filter(|record| {
                     record.filename.to_lowercase().contains(&query) ||
+                        record.contact_name.as_ref().is_some_and(|name| name.to_lowercase().contains(&query)) ||
                         record.sender_identity.to_lowercase().contains(&query) ||
                         record.sender_ipv6.to_lowercase().contains(&query)
                 })
@@ -62,11 +70,11 @@ pub fn HistoryWindow(on_close: EventHandler) -> Element {
     // 处理删除记录
     let handle_delete_record = move |id: i64| {
         spawn(async move {
-            if let Err(e) = AddressBook::delete_file_receive_record(id) {
+            if let Err(e) = AddressBook::call(move || AddressBook::delete_file_receive_record(id)).await {
                 log::error!("删除记录失败: {}", e);
             } else {
                 // 重新加载记录
-                match AddressBook::get_all_file_receive_records() {
+                match AddressBook::call(AddressBook::get_all_file_receive_records).await {
                     Ok(records) => {
                         file_records.set(records);
                     }
@@ -78,10 +86,29 @@ pub fn HistoryWindow(on_close: EventHandler) -> Element {
         });
     };
     
+    // 核对安全码后把发送方联系人标记为已验证
+    let handle_mark_verified = move |identity: String| {
+        spawn(async move {
+            let lookup = identity.clone();
+            match AddressBook::call(move || AddressBook::mark_identity_verified(&lookup)).await {
+                Ok(true) => {
+                    for record in file_records.write().iter_mut().filter(|r| r.sender_identity == identity && r.sender_authenticated) {
+                        record.sender_verified = true;
+                    }
+                    if let Some(record) = selected_record.write().as_mut().filter(|r| r.sender_authenticated) {
+                        record.sender_verified = true;
+                    }
+                }
+                Ok(false) => log::warn!("身份码 {} 不在联系人中，无法标记为已验证", identity),
+                Err(e) => log::error!("标记已验证失败: {}", e),
+            }
+        });
+    };
+    
     // 清除所有记录
     let handle_clear_all = move || {
         spawn(async move {
-            if let Err(e) = AddressBook::delete_all_file_receive_records() {
+            if let Err(e) = AddressBook::call(AddressBook::delete_all_file_receive_records).await {
                 log::error!("清除所有记录失败: {}", e);
             } else {
                 file_records.set(Vec::new());
@@ -178,7 +205,7 @@ pub fn HistoryWindow(on_close: EventHandler) -> Element {
                         outline: none;
                     ",
                     r#type: "text",
-                    placeholder: "搜索文件名、身份码或IP地址...",
+                    placeholder: "搜索文件名、联系人、身份码或IP地址...",
                     value: "{search_query}",
                     oninput: move |e| search_query.set(e.value())
                 }
@@ -253,6 +280,23 @@ pub fn HistoryWindow(on_close: EventHandler) -> Element {
                                             span {
                                                 "{format_size(record.file_size, DECIMAL)}"
                                             }
+                                            if let Some(name) = record.contact_name.as_ref() {
+                                                span {
+                                                    "来自 {name}"
+                                                }
+                                                if record.sender_verified {
+                                                    span {
+                                                        style: "color: #2563eb;",
+                                                        "已验证"
+                                                    }
+                                                }
+                                            }
+                                            if record.resolution != ConflictResolution::None {
+                                                span {
+                                                    style: "color: #b45309;",
+                                                    "{record.resolution.label()}"
+                                                }
+                                            }
                                         }
                                     }
                         
@@ -353,10 +397,21 @@ pub fn HistoryWindow(on_close: EventHandler) -> Element {
                                     label: "文件大小".to_string(),
                                     value: format_size(record.file_size, DECIMAL)
                                 }
+                                DetailItem {
+                                    label: "发送方".to_string(),
+                                    value: record.contact_name.clone().unwrap_or_else(|| "未知（不在联系人中）".to_string())
+                                }
                                 DetailItem {
                                     label: "发送方身份码".to_string(),
                                     value: record.sender_identity.clone()
                                 }
+                                // 只有经握手确认的身份码才能核对安全码
+                                if let Some(code) = Verification::short_code(&my_identity.read(), &record.sender_identity).filter(|_| record.sender_authenticated) {
+                                    DetailItem {
+                                        label: "安全码（与对方设备上显示的核对）".to_string(),
+                                        value: code
+                                    }
+                                }
                                 DetailItem {
                                     label: "发送方IP地址".to_string(),
                                     value: record.sender_ipv6.clone()
@@ -369,6 +424,17 @@ pub fn HistoryWindow(on_close: EventHandler) -> Element {
                                     label: "保存路径".to_string(),
                                     value: record.save_path.clone()
                                 }
+                                DetailItem {
+                                    label: "同名文件处理".to_string(),
+                                    value: record.resolution.label().to_string()
+                                }
+                                
+                                // 屏蔽后该发送方在任何接收模式下都会被拒绝
+                                BlockButtons {
+                                    identity: record.sender_identity.clone(),
+                                    address: record.sender_ipv6.clone(),
+                                    source: "接收记录",
+                                }
                             }
                             
                             // 操作按钮
@@ -394,6 +460,25 @@ pub fn HistoryWindow(on_close: EventHandler) -> Element {
                                     onclick: move |_| show_detail_dialog.set(false),
                                     "关闭"
                                 }
+                                // 只有身份码经握手确认的联系人可以标记为已验证
+                                if record.contact_name.is_some() && record.sender_authenticated && !record.sender_verified {
+                                    button {
+                                        style: "
+                                            background: #2563eb;
+                                            color: white;
+                                            border: none;
+                                            padding: 10px 20px;
+                                            border-radius: 6px;
+                                            cursor: pointer;
+                                            font-size: 14px;
+                                        ",
+                                        onclick: {
+                                            let identity = record.sender_identity.clone();
+                                            move |_| handle_mark_verified(identity.clone())
+                                        },
+                                        "安全码一致，标记已验证"
+                                    }
+                                }
                                 button {
                                     style: "
                                         background: #ff4444;
@@ -0,0 +1,328 @@
+// src/dioxus_component/receive/invitations.rs
+use dioxus::prelude::*;
+use arboard::Clipboard;
+use chrono::{Local, NaiveDateTime};
+use crate::core::db::{AddressBook, Invitation, DB_TIME_FORMAT};
+use crate::dioxus_component::error_message::describe_error;
+
+const INPUT_STYLE: &str = "
+    padding: 10px 12px;
+    border: 1px solid #ddd;
+    border-radius: 8px;
+    font-size: 14px;
+";
+
+// 邀请令牌：发送方在发送页面填写令牌后，收藏模式下也会接收其文件
+#[component]
+pub fn InvitationsWindow(on_close: EventHandler) -> Element {
+    let mut invitations = use_signal(Vec::<Invitation>::new);
+    let mut note = use_signal(String::new);
+    let mut uses = use_signal(|| "1".to_string());
+    let mut days = use_signal(String::new);
+    let mut add_to_whitelist = use_signal(|| false);
+    let mut status_message = use_signal(String::new);
+    
+    let load_invitations = move || {
+        spawn(async move {
+            match AddressBook::call(AddressBook::get_invitations).await {
+                Ok(list) => invitations.set(list),
+                Err(e) => status_message.set(format!("加载邀请令牌失败: {}", describe_error(&e))),
+            }
+        });
+    };
+    
+    use_hook(load_invitations);
+    
+    let create = move |_| {
+        let note = note.read().clone();
+        let uses = uses.read().trim().parse::<u32>().ok();
+        let days = days.read().trim().parse::<u32>().ok();
+        let add_to_whitelist = add_to_whitelist();
+        spawn(async move {
+            let result = AddressBook::call(move || {
+                AddressBook::create_invitation(&note, uses, days, add_to_whitelist)
+            }).await;
+            match result {
+                Ok(invitation) => {
+                    status_message.set(format!("已生成邀请令牌 {}", invitation.token));
+                    load_invitations();
+                }
+                Err(e) => status_message.set(format!("生成失败: {}", describe_error(&e))),
+            }
+        });
+    };
+    
+    let revoke = move |id: i64| {
+        spawn(async move {
+            match AddressBook::call(move || AddressBook::delete_invitation(id)).await {
+                Ok(()) => load_invitations(),
+                Err(e) => status_message.set(format!("撤销失败: {}", describe_error(&e))),
+            }
+        });
+    };
+    
+    let copy_token = move |token: String| {
+        if let Ok(mut clipboard) = Clipboard::new() {
+            if clipboard.set_text(token).is_ok() {
+                status_message.set("已复制邀请令牌".to_string());
+            }
+        }
+    };
+    
+    rsx! {
+        div {
+            style: "
+                position: fixed;
+                top: 0;
+                left: 0;
+                width: 100vw;
+                height: 100vh;
+                background-color: white;
+                z-index: 1000;
+                display: flex;
+                flex-direction: column;
+                overflow: hidden;
+            ",
+            
+            // 标题栏
+            div {
+                style: "
+                    display: flex;
+                    justify-content: space-between;
+                    align-items: center;
+                    padding: 20px 24px;
+                    border-bottom: 1px solid #e0e0e0;
+                    background-color: #fafafa;
+                    flex-shrink: 0;
+                ",
+                
+                button {
+                    class: "back-item",
+                    style: "
+                        background: none;
+                        border: none;
+                        cursor: pointer;
+                        padding: 6px 12px;
+                        border-radius: 6px;
+                        width: 48px;
+                        height: 48px;
+                        display: flex;
+                        align-items: center;
+                        justify-content: center;
+                    ",
+                    onclick: move |_| on_close.call(()),
+                    img {
+                        style: "width: 30px; height: 30px;",
+                        src: asset!("assets/back-100.png")
+                    }
+                }
+                
+                h2 {
+                    style: "margin: 0; font-size: 24px; color: #333;",
+                    "邀请令牌"
+                }
+                
+                // 占位，保持标题居中
+                div { style: "width: 48px;" }
+            }
+            
+            // 生成令牌
+            div {
+                style: "
+                    display: flex;
+                    flex-wrap: wrap;
+                    align-items: center;
+                    gap: 12px;
+                    padding: 16px 24px;
+                    border-bottom: 1px solid #e0e0e0;
+                    background-color: #f8f8f8;
+                    flex-shrink: 0;
+                ",
+                input {
+                    style: "{INPUT_STYLE} flex: 1; min-width: 160px;",
+                    placeholder: "备注，例如对方的名字",
+                    value: "{note}",
+                    oninput: move |e| note.set(e.value())
+                }
+                input {
+                    style: "{INPUT_STYLE} width: 90px;",
+                    r#type: "number",
+                    min: "1",
+                    placeholder: "次数",
+                    value: "{uses}",
+                    oninput: move |e| uses.set(e.value())
+                }
+                span { style: "color: #666; font-size: 14px;", "次 /" }
+                input {
+                    style: "{INPUT_STYLE} width: 90px;",
+                    r#type: "number",
+                    min: "1",
+                    placeholder: "天数",
+                    value: "{days}",
+                    oninput: move |e| days.set(e.value())
+                }
+                span { style: "color: #666; font-size: 14px;", "天内有效" }
+                label {
+                    style: "display: flex; align-items: center; gap: 6px; color: #333; font-size: 14px;",
+                    input {
+                        r#type: "checkbox",
+                        checked: add_to_whitelist(),
+                        onchange: move |e| add_to_whitelist.set(e.checked()),
+                    }
+                    "使用后长期加入白名单"
+                }
+                button {
+                    style: "
+                        padding: 10px 18px;
+                        background: #2563eb;
+                        color: white;
+                        border: none;
+                        border-radius: 8px;
+                        cursor: pointer;
+                        font-size: 14px;
+                    ",
+                    onclick: create,
+                    "生成"
+                }
+            }
+            
+            if !status_message.read().is_empty() {
+                div {
+                    style: "
+                        padding: 8px 24px;
+                        font-size: 13px;
+                        color: #374151;
+                        background: #f3f4f6;
+                        flex-shrink: 0;
+                    ",
+                    "{status_message}"
+                }
+            }
+            
+            // 令牌列表
+            div {
+                style: "
+                    flex: 1;
+                    overflow-y: auto;
+                ",
+                
+                if invitations.read().is_empty() {
+                    p {
+                        style: "
+                            text-align: center;
+                            color: #999;
+                            margin-top: 50px;
+                            font-size: 16px;
+                        ",
+                        "暂无邀请令牌，次数和天数留空表示不限，但至少需要设置一项"
+                    }
+                } else {
+                    for invitation in invitations.read().iter().cloned() {
+                        InvitationItem {
+                            key: "{invitation.id}",
+                            invitation: invitation.clone(),
+                            on_copy: copy_token,
+                            on_revoke: move |_| revoke(invitation.id),
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+// 到期时间（UTC）转换为本地时间显示
+fn format_expiry(expires_at: &str) -> String {
+    NaiveDateTime::parse_from_str(expires_at, DB_TIME_FORMAT)
+        .map(|time| time.and_utc().with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|_| expires_at.to_string())
+}
+
+#[component]
+fn InvitationItem(invitation: Invitation, on_copy: EventHandler<String>, on_revoke: EventHandler) -> Element {
+    let exhausted = invitation.is_exhausted();
+    let mut limits = Vec::new();
+    if let Some(uses) = invitation.uses_left {
+        limits.push(format!("剩余 {} 次", uses));
+    }
+    if let Some(expires_at) = &invitation.expires_at {
+        limits.push(format!("{} 到期", format_expiry(expires_at)));
+    }
+    if invitation.add_to_whitelist {
+        limits.push("使用后加入白名单".to_string());
+    }
+    let limits = limits.join(" · ");
+    let token_color = if exhausted { "#999" } else { "#1e3a8a" };
+    
+    rsx! {
+        div {
+            style: "
+                display: flex;
+                align-items: center;
+                gap: 16px;
+                padding: 14px 24px;
+                border-bottom: 1px solid #f0f0f0;
+            ",
+            
+            div {
+                style: "flex: 1; min-width: 0;",
+                div {
+                    style: "
+                        font-family: monospace;
+                        font-size: 18px;
+                        letter-spacing: 2px;
+                        color: {token_color};
+                        user-select: all;
+                    ",
+                    "{invitation.token}"
+                    if exhausted {
+                        span {
+                            style: "margin-left: 8px; font-size: 12px; letter-spacing: 0; color: #c62828;",
+                            "已失效"
+                        }
+                    }
+                }
+                div {
+                    style: "font-size: 12px; color: #666; margin-top: 4px;",
+                    if invitation.note.is_empty() {
+                        "{limits}"
+                    } else {
+                        "{invitation.note} · {limits}"
+                    }
+                }
+            }
+            
+            if !exhausted {
+                button {
+                    style: "
+                        padding: 6px 12px;
+                        background: #f0f0f0;
+                        color: #333;
+                        border: none;
+                        border-radius: 6px;
+                        cursor: pointer;
+                        font-size: 13px;
+                    ",
+                    onclick: {
+                        let token = invitation.token.clone();
+                        move |_| on_copy.call(token.clone())
+                    },
+                    "复制"
+                }
+            }
+            button {
+                style: "
+                    padding: 6px 12px;
+                    background: #ff4444;
+                    color: white;
+                    border: none;
+                    border-radius: 6px;
+                    cursor: pointer;
+                    font-size: 13px;
+                ",
+                onclick: move |_| on_revoke.call(()),
+                if exhausted { "删除" } else { "撤销" }
+            }
+        }
+    }
+}
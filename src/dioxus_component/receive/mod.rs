@@ -1,3 +1,7 @@
 pub mod receive;
 mod help;
-mod history;
\ No newline at end of file
+mod history;
+mod banned;
+mod connection_log;
+mod block_actions;
+mod invitations;
\ No newline at end of file
@@ -1,15 +1,35 @@
 // src/dioxus_component/receive/receive.rs
 use dioxus::prelude::*;
-use crate::core::filereceiver::{FileReceiver, ReceiveStatus};
+use std::time::Duration;
+use crate::core::filereceiver::{FileReceiver, ReceiveStatus, TIMED_OPEN_MINUTES};
+use crate::core::receive_code::{CodeStatus, ReceiveCode};
 use super::help::HelpButton;
 use super::history::HistoryWindow;
+use super::banned::BannedPeersWindow;
+use super::connection_log::ConnectionLogWindow;
+use super::invitations::InvitationsWindow;
 use log::{info, error};
 
+const TIMED_BUTTON_STYLE: &str = "
+    padding: 4px 12px;
+    border: 1px solid #c8e6c9;
+    background-color: #e8f5e8;
+    color: #2e7d32;
+    border-radius: 14px;
+    cursor: pointer;
+    font-size: 13px;
+";
+
 #[component]
 pub fn Receive() -> Element {
     let mut status = use_signal(|| ReceiveStatus::Closed);
     let show_help_window = use_signal(|| false);
     let mut show_history_window = use_signal(|| false);
+    let mut show_banned_window = use_signal(|| false);
+    let mut show_connection_log = use_signal(|| false);
+    let mut show_invitations = use_signal(|| false);
+    let mut draining_count = use_signal(|| 0usize);
+    let mut open_timer = use_signal(FileReceiver::open_timer);
     
     // 初始化状态
     use_effect(move || {
@@ -17,6 +37,44 @@ pub fn Receive() -> Element {
         status.set(current_status);
     });
     
+    // 定期刷新关闭时仍在排空的传输数量
+    use_future(move || async move {
+        loop {
+            let count = if FileReceiver::is_draining() {
+                FileReceiver::active_transfer_count()
+            } else {
+                0
+            };
+            if *draining_count.peek() != count {
+                draining_count.set(count);
+            }
+            // 限时开启在后台到期，需要同步状态和倒计时
+            let timer = FileReceiver::open_timer();
+            if *open_timer.peek() != timer {
+                open_timer.set(timer);
+            }
+            let current_status = FileReceiver::get_receive_status();
+            if *status.peek() != current_status {
+                status.set(current_status);
+            }
+            tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
+        }
+    });
+    
+    // 切换接收模式；检查私钥状态需要访问数据库，在后台完成后再更新界面
+    let change_status = move |target: ReceiveStatus| {
+        spawn(async move {
+            match FileReceiver::set_receive_status(target.clone()).await {
+                Ok(()) => {
+                    info!("状态改为: {}", target.label());
+                    status.set(target);
+                    open_timer.set(None);
+                }
+                Err(e) => error!("设置状态失败: {}", e),
+            }
+        });
+    };
+    
     // 关闭历史窗口的处理函数
     let close_history = move |_| {
         show_history_window.set(false);
@@ -43,6 +101,42 @@ pub fn Receive() -> Element {
                     z-index: 10;
                 ",
                 
+                // 邀请令牌按钮
+                button {
+                    class: "icon-button",
+                    onclick: move |_| {
+                        show_invitations.set(true);
+                    },
+                    img {
+                        src: asset!("assets/add-user-100.png"),
+                        class: "button-icon",
+                    }
+                }
+                
+                // 连接记录按钮
+                button {
+                    class: "icon-button",
+                    onclick: move |_| {
+                        show_connection_log.set(true);
+                    },
+                    img {
+                        src: asset!("assets/list-100.png"),
+                        class: "button-icon",
+                    }
+                }
+                
+                // 封禁列表按钮
+                button {
+                    class: "icon-button",
+                    onclick: move |_| {
+                        show_banned_window.set(true);
+                    },
+                    img {
+                        src: asset!("assets/notice-100.png"),
+                        class: "button-icon",
+                    }
+                }
+                
                 // 历史按钮
                 button {
                     class: "icon-button",
@@ -85,13 +179,9 @@ pub fn Receive() -> Element {
                         target_status: ReceiveStatus::Open,
                         label: "开启",
                         on_click: move |_| {
-                            if *status.read() != ReceiveStatus::Open {
-                                if let Err(e) = FileReceiver::set_receive_status(ReceiveStatus::Open) {
-                                    error!("设置状态失败: {}", e);
-                                } else {
-                                    status.set(ReceiveStatus::Open);
-                                    info!("状态改为: 开启");
-                                }
+                            // 限时开启时再次点击改为一直开启
+                            if *status.read() != ReceiveStatus::Open || open_timer.read().is_some() {
+                                change_status(ReceiveStatus::Open);
                             }
                         }
                     }
@@ -102,12 +192,18 @@ pub fn Receive() -> Element {
                         label: "收藏",
                         on_click: move |_| {
                             if *status.read() != ReceiveStatus::Collect {
-                                if let Err(e) = FileReceiver::set_receive_status(ReceiveStatus::Collect) {
-                                    error!("设置状态失败: {}", e);
-                                } else {
-                                    status.set(ReceiveStatus::Collect);
-                                    info!("状态改为: 收藏");
-                                }
+                                change_status(ReceiveStatus::Collect);
+                            }
+                        }
+                    }
+                    
+                    StatusButton {
+                        current_status: status,
+                        target_status: ReceiveStatus::Code,
+                        label: "口令",
+                        on_click: move |_| {
+                            if *status.read() != ReceiveStatus::Code {
+                                change_status(ReceiveStatus::Code);
                             }
                         }
                     }
@@ -118,14 +214,81 @@ pub fn Receive() -> Element {
                         label: "关闭",
                         on_click: move |_| {
                             if *status.read() != ReceiveStatus::Closed {
-                                if let Err(e) = FileReceiver::set_receive_status(ReceiveStatus::Closed) {
-                                    error!("设置状态失败: {}", e);
-                                } else {
-                                    status.set(ReceiveStatus::Closed);
-                                    info!("状态改为: 关闭");
+                                change_status(ReceiveStatus::Closed);
+                            }
+                        }
+                    }
+                }
+                
+                // 限时开启
+                match open_timer() {
+                    Some((remaining, previous)) => rsx! {
+                        div {
+                            style: "
+                                display: flex;
+                                align-items: center;
+                                gap: 10px;
+                                margin-bottom: 20px;
+                                color: #2e7d32;
+                                font-size: 14px;
+                            ",
+                            span {
+                                "限时开启，{remaining.as_secs() / 60}:{remaining.as_secs() % 60:02} 后恢复为{previous.label()}"
+                            }
+                            button {
+                                style: TIMED_BUTTON_STYLE,
+                                onclick: move |_| change_status(previous.clone()),
+                                "立即恢复"
+                            }
+                        }
+                    },
+                    None => rsx! {
+                        div {
+                            style: "
+                                display: flex;
+                                align-items: center;
+                                gap: 8px;
+                                margin-bottom: 20px;
+                                color: #666;
+                                font-size: 14px;
+                            ",
+                            span { "限时开启：" }
+                            for minutes in TIMED_OPEN_MINUTES {
+                                button {
+                                    key: "{minutes}",
+                                    style: TIMED_BUTTON_STYLE,
+                                    onclick: move |_| {
+                                        spawn(async move {
+                                            if let Err(e) = FileReceiver::open_for(Duration::from_secs(minutes * 60)).await {
+                                                error!("设置状态失败: {}", e);
+                                            } else {
+                                                status.set(ReceiveStatus::Open);
+                                                open_timer.set(FileReceiver::open_timer());
+                                                info!("状态改为: 开启 {} 分钟", minutes);
+                                            }
+                                        });
+                                    },
+                                    "{minutes} 分钟"
                                 }
                             }
                         }
+                    },
+                }
+                
+                // 口令模式下显示当前口令
+                if *status.read() == ReceiveStatus::Code {
+                    ReceiveCodePanel {}
+                }
+                
+                // 关闭后仍在进行的传输
+                if draining_count() > 0 {
+                    p {
+                        style: "
+                            margin: 0;
+                            color: #ef6c00;
+                            font-size: 14px;
+                        ",
+                        "正在等待 {draining_count} 个传输完成..."
                     }
                 }
             }
@@ -136,6 +299,27 @@ pub fn Receive() -> Element {
                     on_close: close_history
                 }
             }
+            
+            // 全屏连接记录窗口
+            if *show_connection_log.read() {
+                ConnectionLogWindow {
+                    on_close: move |_| show_connection_log.set(false)
+                }
+            }
+            
+            // 全屏邀请令牌窗口
+            if *show_invitations.read() {
+                InvitationsWindow {
+                    on_close: move |_| show_invitations.set(false)
+                }
+            }
+            
+            // 全屏封禁列表窗口
+            if *show_banned_window.read() {
+                BannedPeersWindow {
+                    on_close: move |_| show_banned_window.set(false)
+                }
+            }
         }
     }
 }
@@ -152,6 +336,7 @@ fn StatusButton(
         match target_status {
             ReceiveStatus::Open => "#e8f5e8",
             ReceiveStatus::Collect => "#fff3e0",
+            ReceiveStatus::Code => "#e3f2fd",
             ReceiveStatus::Closed => "#ffebee",
         }
     } else {
@@ -162,6 +347,7 @@ fn StatusButton(
         match target_status {
             ReceiveStatus::Open => "#2e7d32",
             ReceiveStatus::Collect => "#ef6c00",
+            ReceiveStatus::Code => "#1565c0",
             ReceiveStatus::Closed => "#c62828",
         }
     } else {
@@ -187,4 +373,91 @@ fn StatusButton(
             "{label}"
         }
     }
-}
\ No newline at end of file
+}
+// 口令模式的口令和剩余有效时间，口令过期或被使用后可以重新生成
+#[component]
+fn ReceiveCodePanel() -> Element {
+    let mut code_status = use_signal(ReceiveCode::current);
+    
+    // 每秒刷新剩余时间
+    use_future(move || async move {
+        loop {
+            let current = ReceiveCode::current();
+            if *code_status.peek() != current {
+                code_status.set(current);
+            }
+            tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+        }
+    });
+    
+    let regenerate = move |_| {
+        ReceiveCode::generate();
+        code_status.set(ReceiveCode::current());
+    };
+    
+    rsx! {
+        div {
+            style: "
+                display: flex;
+                flex-direction: column;
+                align-items: center;
+                gap: 10px;
+                padding: 16px 24px;
+                margin-bottom: 20px;
+                background-color: #e3f2fd;
+                border-radius: 12px;
+                min-width: 280px;
+            ",
+            
+            match code_status() {
+                Some(CodeStatus { code, remaining, used }) => rsx! {
+                    span {
+                        style: "color: #1565c0; font-size: 14px;",
+                        "请将口令告知发送方"
+                    }
+                    span {
+                        style: "
+                            font-family: monospace;
+                            font-size: 32px;
+                            font-weight: bold;
+                            letter-spacing: 4px;
+                            color: #0d47a1;
+                            user-select: all;
+                        ",
+                        "{code}"
+                    }
+                    span {
+                        style: "color: #666; font-size: 13px;",
+                        "剩余 {remaining.as_secs() / 60}:{remaining.as_secs() % 60:02}"
+                    }
+                    if used {
+                        span {
+                            style: "color: #ef6c00; font-size: 13px;",
+                            "口令已被使用，只接受该发送方本次发送的文件，发送完成后失效"
+                        }
+                    }
+                },
+                None => rsx! {
+                    span {
+                        style: "color: #c62828; font-size: 14px;",
+                        "口令已过期"
+                    }
+                },
+            }
+            
+            button {
+                style: "
+                    padding: 6px 16px;
+                    border: none;
+                    background-color: #1565c0;
+                    color: white;
+                    border-radius: 16px;
+                    cursor: pointer;
+                    font-size: 13px;
+                ",
+                onclick: regenerate,
+                "重新生成口令"
+            }
+        }
+    }
+}
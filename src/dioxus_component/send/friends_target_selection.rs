@@ -1,11 +1,12 @@
 // src/dioxus_component/send/friends_target_selection.rs
 use std::rc::Rc;
 use dioxus::prelude::*;
-use crate::core::db::AddressBook;
+use crate::core::db::{AddressBook, Contact, ContactGroup};
+use crate::dioxus_component::error_message::describe_error;
 
 #[component]
 pub fn FriendsTargetSelection(
-	selected_targets: Signal<Vec<String>>,
+	selected_targets: Signal<Vec<Contact>>,
 	disabled: bool,
 ) -> Element {
 	let friends = use_signal(|| Vec::new());
@@ -13,7 +14,7 @@ pub fn FriendsTargetSelection(
 	let error_message = use_signal(|| None::<String>);
 	let mut show_friends_modal = use_signal(|| false);
 	
-	// 加载好友列表
+	// 加载联系人列表
 	let load_friends = {
 		let friends_signal = friends.clone();
 		let error_signal = error_message.clone();
@@ -21,21 +22,26 @@ pub fn FriendsTargetSelection(
 			let mut friends = friends_signal.clone();
 			let mut error_message = error_signal.clone();
 			spawn(async move {
-				let result = if let Some(ref q) = query {
-					AddressBook::search_friends(&q)
-				} else {
-					AddressBook::get_all_friends()
-				};
+				let q = query.clone();
+				let result = AddressBook::call(move || match q {
+					Some(q) => AddressBook::search_contacts(&q),
+					None => AddressBook::get_all_contacts(),
+				}).await;
 				
 				match result {
-					Ok(friends_list) => {
-						friends.set(friends_list);
+					Ok(contacts) => {
+						// 只有填写了地址的联系人才能作为发送目标
+						let reachable: Vec<Contact> = contacts
+							.into_iter()
+							.filter(|c| !c.addresses.is_empty())
+							.collect();
+						friends.set(reachable);
 					}
 					Err(e) => {
 						let error_msg = if query.is_some() {
-							format!("搜索好友失败: {}", e)
+							format!("搜索联系人失败: {}", describe_error(&e))
 						} else {
-							format!("加载好友列表失败: {}", e)
+							format!("加载联系人失败: {}", describe_error(&e))
 						};
 						error_message.set(Some(error_msg));
 					}
@@ -44,11 +50,54 @@ pub fn FriendsTargetSelection(
 		}
 	};
 	
-	// 初始加载好友列表
+	// 初始加载联系人列表
 	use_effect(use_reactive((), move |_| {
 		load_friends(None);
 	}));
 	
+	// 加载分组，并展开为有地址的成员
+	let mut groups = use_signal(Vec::<(ContactGroup, Vec<Contact>)>::new);
+	use_hook(move || {
+		let mut error_message = error_message;
+		spawn(async move {
+			let result = AddressBook::call(|| {
+				Ok((AddressBook::get_all_groups()?, AddressBook::get_all_contacts()?))
+			}).await;
+			match result {
+				Ok((group_list, contacts)) => {
+					let expanded = group_list
+						.into_iter()
+						.map(|group| {
+							let members = contacts
+								.iter()
+								.filter(|c| group.member_ids.contains(&c.id) && !c.addresses.is_empty())
+								.cloned()
+								.collect();
+							(group, members)
+						})
+						.collect();
+					groups.set(expanded);
+				}
+				Err(e) => error_message.set(Some(format!("加载分组失败: {}", describe_error(&e)))),
+			}
+		});
+	});
+	
+	// 选择分组：成员已全部选中时取消选择，否则补齐未选中的成员
+	let mut toggle_group = move |members: Vec<Contact>| {
+		let mut current_targets = selected_targets.write();
+		let all_selected = members.iter().all(|m| current_targets.iter().any(|c| c.id == m.id));
+		if all_selected {
+			current_targets.retain(|c| !members.iter().any(|m| m.id == c.id));
+		} else {
+			for member in members {
+				if !current_targets.iter().any(|c| c.id == member.id) {
+					current_targets.push(member);
+				}
+			}
+		}
+	};
+	
 	// 处理搜索
 	let handle_search = {
 		let load_friends = load_friends.clone();
@@ -62,27 +111,27 @@ pub fn FriendsTargetSelection(
 		}
 	};
 	
-	// 切换好友选择状态
+	// 切换联系人选择状态
 	let mut toggle_friend_selection = {
 		let mut selected_targets = selected_targets.clone();
-		move |address: String| {
+		move |contact: Contact| {
 			let mut current_targets = selected_targets.write();
-			if current_targets.contains(&address) {
+			if current_targets.iter().any(|c| c.id == contact.id) {
 				// 如果已经选中，则移除
-				current_targets.retain(|addr| addr != &address);
+				current_targets.retain(|c| c.id != contact.id);
 			} else {
 				// 如果未选中，则添加
-				current_targets.push(address);
+				current_targets.push(contact);
 			}
 		}
 	};
 	
-	// 检查好友是否被选中
-	let is_friend_selected: Rc<dyn Fn(&str) -> bool> = Rc::new(move |address: &str| -> bool {
-		selected_targets.read().contains(&address.to_string())
+	// 检查联系人是否被选中
+	let is_friend_selected: Rc<dyn Fn(i64) -> bool> = Rc::new(move |id: i64| -> bool {
+		selected_targets.read().iter().any(|c| c.id == id)
 	});
 	
-	// 获取选中好友数量
+	// 获取选中联系人数量
 	let _selected_count = selected_targets.read().len();
 	
 	// 清除所有选择
@@ -93,14 +142,10 @@ pub fn FriendsTargetSelection(
 		}
 	};
 	
-	let selected_targets_owned: Vec<String> = selected_targets.read().clone();
-	let selected_targets_pairs: Vec<(String, String)> = selected_targets_owned
+	let selected_targets_owned: Vec<Contact> = selected_targets.read().clone();
+	let selected_targets_pairs: Vec<(String, Contact)> = selected_targets_owned
 		.into_iter()
-		.map(|t| {
-			// t 是 String（owned），这里再 clone 一份用于事件闭包
-			let for_click = t.clone();
-			(t, for_click)
-		})
+		.map(|c| (c.name.clone(), c))
 		.collect();
 	
 	let selected_count = selected_targets_pairs.len();
@@ -125,7 +170,7 @@ pub fn FriendsTargetSelection(
                         color: #374151;
                         font-size: 16px;
                     ",
-                    "选择好友作为目标"
+                    "选择联系人作为目标"
                 }
                 
                 div {
@@ -138,7 +183,7 @@ pub fn FriendsTargetSelection(
                                 font-size: 14px;
                                 font-weight: 500;
                             ",
-                            "已选择 {selected_count} 个好友"
+                            "已选择 {selected_count} 个联系人"
                         }
                         
                         button {
@@ -160,7 +205,36 @@ pub fn FriendsTargetSelection(
                 }
             }
             
-            // 选择好友按钮
+            // 分组快捷选择
+            if !groups.read().is_empty() {
+                div {
+                    style: "
+                        display: flex;
+                        flex-wrap: wrap;
+                        align-items: center;
+                        gap: 8px;
+                        margin-bottom: 12px;
+                    ",
+                    
+                    span {
+                        style: "font-size: 14px; color: #6b7280;",
+                        "分组:"
+                    }
+                    
+                    for (group, members) in groups.read().clone() {
+                        GroupChip {
+                            key: "{group.id}",
+                            name: group.name.clone(),
+                            member_count: members.len(),
+                            all_selected: !members.is_empty() && members.iter().all(|m| selected_targets.read().iter().any(|c| c.id == m.id)),
+                            disabled: disabled || members.is_empty(),
+                            on_click: move || toggle_group(members.clone()),
+                        }
+                    }
+                }
+            }
+            
+            // 选择联系人按钮
             button {
                 class: "select-friends-button",
                 style: "
@@ -188,22 +262,22 @@ pub fn FriendsTargetSelection(
                     span {
                         style: "font-size: 14px;",
                         if selected_count == 0 {
-                            "点击选择好友"
+                            "点击选择联系人"
                         } else {
-                            "点击管理已选择的好友"
+                            "点击管理已选择的联系人"
                         }
                     }
                     
                     if selected_count > 0 {
                         span {
                             style: "font-size: 12px; color: #6b7280;",
-                            "已选择 {selected_count} 个好友"
+                            "已选择 {selected_count} 个联系人"
                         }
                     }
                 }
             }
             
-            // 选中的好友预览
+            // 选中的联系人预览
             if selected_count > 0 {
                 div {
                     style: "
@@ -219,7 +293,7 @@ pub fn FriendsTargetSelection(
                             font-size: 14px;
                             color: #0369a1;
                         ",
-                        "已选择的好友:"
+                        "已选择的联系人:"
                     }
                     
                     div {
@@ -231,7 +305,7 @@ pub fn FriendsTargetSelection(
                         
                         for (display, click_val) in selected_targets_pairs.into_iter() {
                             div {
-                                key: "{display}",
+                                key: "{click_val.id}",
                                 style: "
                                     display: inline-flex;
                                     align-items: center;
@@ -271,7 +345,7 @@ pub fn FriendsTargetSelection(
                 }
             }
             
-            // 好友选择模态框
+            // 联系人选择模态框
             if *show_friends_modal.read() {
                 FriendsSelectionModal {
                     friends: friends.read().clone(),
@@ -290,12 +364,12 @@ pub fn FriendsTargetSelection(
 
 #[derive(Props, Clone)]
 struct FriendsSelectionModalProps {
-	friends: Vec<crate::core::db::FriendEntry>,
-	selected_targets: Signal<Vec<String>>,
+	friends: Vec<Contact>,
+	selected_targets: Signal<Vec<Contact>>,
 	search_query: Signal<String>,
 	on_search: EventHandler,
-	on_toggle_selection: EventHandler<String>,
-	is_friend_selected: Rc<dyn Fn(&str) -> bool>,
+	on_toggle_selection: EventHandler<Contact>,
+	is_friend_selected: Rc<dyn Fn(i64) -> bool>,
 	on_close: EventHandler,
 	disabled: bool,
 }
@@ -320,17 +394,13 @@ fn FriendsSelectionModal(props: FriendsSelectionModalProps) -> Element {
 	let toggle_select_all = move |_| {
 		if disabled { return; }
 		
-		let all_addresses: Vec<String> = friends_clone.iter()
-			.map(|f| f.address.clone())
-			.collect();
-		
 		let mut current_selection = selected_targets.write();
 		
 		// 如果已经全选，则清空；否则选择所有
-		if current_selection.len() == all_addresses.len() {
+		if current_selection.len() == friends_clone.len() {
 			current_selection.clear();
 		} else {
-			*current_selection = all_addresses;
+			*current_selection = friends_clone.clone();
 		}
 	};
 	
@@ -369,7 +439,7 @@ fn FriendsSelectionModal(props: FriendsSelectionModalProps) -> Element {
                     
                     h3 {
                         style: "margin: 0 0 16px 0; color: #1f2937;",
-                        "选择好友"
+                        "选择联系人"
                     }
                     
                     // 搜索栏
@@ -384,7 +454,7 @@ fn FriendsSelectionModal(props: FriendsSelectionModalProps) -> Element {
                                 border-radius: 6px;
                                 font-size: 14px;
                             ",
-                            placeholder: "搜索好友...",
+                            placeholder: "搜索联系人...",
                             value: "{search_query}",
                             oninput: move |e| search_query.set(e.value())
                         }
@@ -418,9 +488,9 @@ fn FriendsSelectionModal(props: FriendsSelectionModalProps) -> Element {
                         span {
                             style: "color: #6b7280; font-size: 14px;",
                             if selected_count == 0 {
-                                "未选择任何好友"
+                                "未选择任何联系人"
                             } else {
-                                "已选择 {selected_count} 个好友"
+                                "已选择 {selected_count} 个联系人"
                             }
                         }
                         
@@ -446,7 +516,7 @@ fn FriendsSelectionModal(props: FriendsSelectionModalProps) -> Element {
                     }
                 }
                 
-                // 好友列表
+                // 联系人列表
                 div {
                     style: "
                         flex: 1;
@@ -461,7 +531,7 @@ fn FriendsSelectionModal(props: FriendsSelectionModalProps) -> Element {
                                 color: #6b7280;
                                 padding: 40px;
                             ",
-                            "暂无好友"
+                            "暂无可发送的联系人，请先在地址簿中为联系人填写地址"
                         }
                     } else {
                         div {
@@ -469,9 +539,10 @@ fn FriendsSelectionModal(props: FriendsSelectionModalProps) -> Element {
                             
                             for friend in friends {
                                 FriendSelectionItem {
+                                    key: "{friend.id}",
                                     friend: friend.clone(),
-                                    is_selected: is_friend_selected(&friend.address),
-                                    on_toggle: move || on_toggle_selection.call(friend.address.clone()),
+                                    is_selected: is_friend_selected(friend.id),
+                                    on_toggle: move || on_toggle_selection.call(friend.clone()),
                                     disabled: disabled,
                                 }
                             }
@@ -511,7 +582,7 @@ fn FriendsSelectionModal(props: FriendsSelectionModalProps) -> Element {
 
 #[derive(Props, Clone, PartialEq)]
 struct FriendSelectionItemProps {
-	friend: crate::core::db::FriendEntry,
+	friend: Contact,
 	is_selected: bool,
 	on_toggle: EventHandler,
 	disabled: bool,
@@ -526,6 +597,13 @@ fn FriendSelectionItem(props: FriendSelectionItemProps) -> Element {
 		disabled,
 	} = props;
 	
+	// 发送时首先尝试的地址，其余地址作为备用
+	let dial_order = friend.dial_order();
+	let address_summary = match dial_order.len() {
+		0 | 1 => dial_order.join(""),
+		n => format!("{} 等 {} 个地址", dial_order[0], n),
+	};
+	
 	rsx! {
         div {
             style: "
@@ -562,7 +640,7 @@ fn FriendSelectionItem(props: FriendSelectionItemProps) -> Element {
                 disabled: disabled,
             }
             
-            // 好友信息
+            // 联系人信息
             div {
                 style: "flex: 1;",
                 
@@ -571,8 +649,24 @@ fn FriendSelectionItem(props: FriendSelectionItemProps) -> Element {
                         font-weight: 500;
                         color: #1f2937;
                         margin-bottom: 4px;
+                        display: flex;
+                        align-items: center;
+                        gap: 8px;
                     ",
-                    "{friend.alias}"
+                    "{friend.name}"
+                    if friend.verified {
+                        span {
+                            style: "
+                                background: #dbeafe;
+                                color: #2563eb;
+                                font-size: 12px;
+                                font-weight: normal;
+                                padding: 2px 8px;
+                                border-radius: 10px;
+                            ",
+                            "已验证"
+                        }
+                    }
                 }
                 
                 div {
@@ -581,7 +675,7 @@ fn FriendSelectionItem(props: FriendSelectionItemProps) -> Element {
                         color: #6b7280;
                         font-size: 14px;
                     ",
-                    "{friend.address}"
+                    "{address_summary}"
                 }
             }
             
@@ -607,4 +701,30 @@ impl PartialEq for FriendsSelectionModalProps {
 			self.disabled == other.disabled
 		// 注意：我们不比较 is_friend_selected，因为函数指针不能比较
 	}
-}
\ No newline at end of file
+}
+
+#[component]
+fn GroupChip(name: String, member_count: usize, all_selected: bool, on_click: EventHandler, disabled: bool) -> Element {
+	let (background, color, border) = if all_selected {
+		("#3b82f6", "white", "#3b82f6")
+	} else {
+		("white", "#374151", "#d1d5db")
+	};
+	
+	rsx! {
+        button {
+            style: "
+                padding: 4px 12px;
+                background: {background};
+                color: {color};
+                border: 1px solid {border};
+                border-radius: 16px;
+                cursor: pointer;
+                font-size: 13px;
+            ",
+            onclick: move |_| on_click.call(()),
+            disabled: disabled,
+            "{name} ({member_count})"
+        }
+    }
+}
@@ -1,10 +1,14 @@
 // src/dioxus_component/send/send.rs
 use dioxus::prelude::*;
+use crate::core::db::Contact;
 use crate::core::filesender::FileSender;
+use crate::core::protocol::ConflictResolution;
+use crate::core::settings::Settings;
 use log::{info, error};
 
 use super::manual_target_selection::ManualTargetSelect;
 use super::friends_target_selection::FriendsTargetSelection;
+use crate::dioxus_component::error_message::describe_error;
 
 #[component]
 pub fn Send() -> Element {
@@ -13,7 +17,11 @@ pub fn Send() -> Element {
     let mut selected_files = use_signal(|| Vec::<String>::new());
     let is_sending = use_signal(|| false);
     let mut manual_selection_enabled = use_signal(|| false);
-    let selected_targets = use_signal(|| Vec::<String>::new());
+    let selected_targets = use_signal(|| Vec::<Contact>::new());
+    let target_results = use_signal(Vec::<TargetResult>::new);
+    let mut receive_code = use_signal(String::new);
+    let mut invitation = use_signal(String::new);
+    let default_port = Settings::current().port;
     
     rsx! {
         div {
@@ -49,7 +57,7 @@ pub fn Send() -> Element {
                     }
                     p {
                         style: "margin: 0; opacity: 0.9; font-size: 14px;",
-                        "目标端口: 6789 • 支持多文件传输"
+                        "默认目标端口: {default_port} • 支持多文件传输"
                     }
                 }
                 
@@ -125,7 +133,7 @@ pub fn Send() -> Element {
                                                     status_message.set("未选择任何文件".to_string());
                                                 }
                                             }
-                                            Err(e) => status_message.set(format!("选择文件失败: {}", e)),
+                                            Err(e) => status_message.set(format!("选择文件失败: {}", describe_error(&e))),
                                         }
                                     }
                                 },
@@ -255,8 +263,75 @@ pub fn Send() -> Element {
                         }
                     }
                     
+                    // 接收口令，对方处于口令模式时需要填写
+                    div {
+                        style: "margin-bottom: 24px;",
+                        label {
+                            style: "
+                                display: block;
+                                font-weight: 600;
+                                margin-bottom: 8px;
+                                color: #374151;
+                                font-size: 16px;
+                            ",
+                            "接收口令（对方使用口令模式时填写，可选）"
+                        }
+                        input {
+                            style: "
+                                width: 100%;
+                                padding: 12px;
+                                border: 1px solid #d1d5db;
+                                border-radius: 8px;
+                                box-sizing: border-box;
+                                outline: none;
+                                font-size: 14px;
+                                font-family: monospace;
+                                letter-spacing: 2px;
+                            ",
+                            r#type: "text",
+                            placeholder: "例如: ABCD-EFGH",
+                            value: "{receive_code}",
+                            disabled: *is_sending.read(),
+                            oninput: move |e| receive_code.set(e.value()),
+                        }
+                        p {
+                            style: "margin: 8px 0 0 0; color: #6b7280; font-size: 12px;",
+                            "口令错误或已过期时握手会失败，请向对方确认口令"
+                        }
+                    }
                     
-                    
+                    // 邀请令牌，对方处于收藏模式且未把自己加入白名单时使用
+                    div {
+                        style: "margin-bottom: 24px;",
+                        label {
+                            style: "
+                                display: block;
+                                font-weight: 600;
+                                margin-bottom: 8px;
+                                color: #374151;
+                                font-size: 16px;
+                            ",
+                            "邀请令牌（对方使用收藏模式时填写，可选）"
+                        }
+                        input {
+                            style: "
+                                width: 100%;
+                                padding: 12px;
+                                border: 1px solid #d1d5db;
+                                border-radius: 8px;
+                                box-sizing: border-box;
+                                outline: none;
+                                font-size: 14px;
+                                font-family: monospace;
+                                letter-spacing: 2px;
+                            ",
+                            r#type: "text",
+                            placeholder: "例如: ABCD-EFGH-JKMN",
+                            value: "{invitation}",
+                            disabled: *is_sending.read(),
+                            oninput: move |e| invitation.set(e.value()),
+                        }
+                    }
 
                     // 发送按钮
                     button {
@@ -276,8 +351,10 @@ pub fn Send() -> Element {
                         ",
                         disabled: selected_files.read().is_empty() || *is_sending.read(),
                         onclick: move |_| {
-                            to_owned![target_ip, selected_files, status_message, is_sending, manual_selection_enabled, selected_targets];
+                            to_owned![target_ip, selected_files, status_message, is_sending, manual_selection_enabled, selected_targets, target_results];
                             let ip = target_ip.read().clone();
+                            let code = receive_code.read().trim().to_string();
+                            let token = invitation.read().trim().to_string();
                             let files = selected_files.read().clone();
                             let is_manual = *manual_selection_enabled.read();
                             let targets = selected_targets.read().clone();
@@ -291,60 +368,91 @@ pub fn Send() -> Element {
                                     return;
                                 }
 
-                                // 根据模式选择目标
+                                // 根据模式选择目标，每个目标为 (显示名称, 候选地址, 端口, 身份码)
                                 let target_list = if is_manual {
-                                    // 手动模式：使用单个目标IP
-                                    if ip.is_empty() {
-                                        vec!["::1".to_string()]
-                                    } else {
-                                        vec![ip.clone()]
-                                    }
+                                    // 手动模式：使用单个目标IP，无法确认对方身份
+                                    let ip = if ip.is_empty() { "::1".to_string() } else { ip.clone() };
+                                    vec![(ip.clone(), vec![ip], None, None)]
                                 } else {
-                                    // 朋友模式：使用选中的多个目标
+                                    // 联系人模式：依次尝试联系人的各个地址
                                     if targets.is_empty() {
-                                        status_message.set("请先选择目标朋友".to_string());
+                                        status_message.set("请先选择目标联系人".to_string());
                                         is_sending.set(false);
                                         return;
                                     }
-                                    targets.clone()
+                                    targets
+                                        .iter()
+                                        .map(|c| (c.name.clone(), c.dial_order(), c.port, (!c.identity.is_empty()).then(|| c.identity.clone())))
+                                        .collect()
                                 };
 
                                 status_message.set(format!("📦 准备发送 {} 个文件到 {} 个目标...", files.len(), target_list.len()));
 
                                 let mut success_count = 0;
                                 let mut fail_count = 0;
+                                let mut skipped_count = 0;
+                                let mut last_error = None;
+                                target_results.write().clear();
 
                                 // 对每个目标和每个文件进行发送
-                                for target in &target_list {
+                                for (target, addresses, port, identity) in &target_list {
                                     status_message.set(format!("正在并发发送 {} 个文件到 {}...", files.len(), target));
+                                    let mut outcome = TargetResult {
+                                        name: target.clone(),
+                                        ..TargetResult::default()
+                                    };
             
                                     // 使用并发发送函数
-                                    match FileSender::send_files(target, &files).await {
+                                    match FileSender::send_files(addresses, *port, identity.as_deref(), (!code.is_empty()).then_some(code.as_str()), (!token.is_empty()).then_some(token.as_str()), &files).await {
                                         Ok(results) => {
                                             for (file_path, result) in results {
                                                 match result {
-                                                    Ok(_) => {
-                                                        info!("发送成功: {} 到 {}", file_path, target);
-                                                        success_count += 1;
+                                                    Ok(sent) => {
+                                                        if sent.resolution == ConflictResolution::Skipped {
+                                                            info!("对方已有相同文件，跳过: {} 到 {}", file_path, target);
+                                                            outcome.skipped += 1;
+                                                        } else {
+                                                            info!("发送成功: {} 到 {} ({})", file_path, target, sent.address);
+                                                            outcome.succeeded += 1;
+                                                        }
+                                                        outcome.address = Some(sent.address);
                                                     },
                                                     Err(e) => {
                                                         error!("发送失败: {} 到 {} - {}", file_path, target, e);
-                                                        fail_count += 1;
+                                                        outcome.error = Some(describe_error(&e));
+                                                        outcome.failed += 1;
                                                     },
                                                 }
                                             }
                                         },
                                         Err(e) => {
                                             error!("并发发送到 {} 失败: {}", target, e);
-                                            fail_count += files.len(); // 所有文件都失败
+                                            outcome.error = Some(describe_error(&e));
+                                            outcome.failed = files.len(); // 所有文件都失败
                                         }
                                     }
+
+                                    success_count += outcome.succeeded;
+                                    skipped_count += outcome.skipped;
+                                    fail_count += outcome.failed;
+                                    if outcome.error.is_some() {
+                                        last_error = outcome.error.clone();
+                                    }
+                                    target_results.write().push(outcome);
                                 }
 
+                                // 被接收方跳过的文件单独统计
+                                let skipped_note = if skipped_count > 0 {
+                                    format!(", {} 个已存在被跳过", skipped_count)
+                                } else {
+                                    String::new()
+                                };
                                 if fail_count == 0 {
-                                    status_message.set(format!("✅ 所有文件发送完成 ({} 个文件, {} 个目标)", success_count, target_list.len()));
+                                    status_message.set(format!("✅ 所有文件发送完成 ({} 个文件, {} 个目标{})", success_count, target_list.len(), skipped_note));
+                                } else if let Some(reason) = last_error {
+                                    status_message.set(format!("⚠️ 发送完成: {} 成功{}, {} 失败 ({})", success_count, skipped_note, fail_count, reason));
                                 } else {
-                                    status_message.set(format!("⚠️ 发送完成: {} 成功, {} 失败", success_count, fail_count));
+                                    status_message.set(format!("⚠️ 发送完成: {} 成功{}, {} 失败", success_count, skipped_note, fail_count));
                                 }
         
                                 is_sending.set(false);
@@ -384,8 +492,65 @@ pub fn Send() -> Element {
                         ",
                         "{status_message}"
                     }
+
+                    // 多个目标时逐个显示结果
+                    if target_results.read().len() > 1 {
+                        div {
+                            style: "
+                                margin-top: 12px;
+                                display: flex;
+                                flex-direction: column;
+                                gap: 4px;
+                            ",
+                            for (index, result) in target_results.read().iter().enumerate() {
+                                TargetResultRow {
+                                    key: "{index}",
+                                    result: result.clone(),
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+// 发送给单个目标的结果
+#[derive(Debug, Clone, PartialEq, Default)]
+struct TargetResult {
+    name: String,            // 目标名称或地址
+    succeeded: usize,        // 成功的文件数
+    skipped: usize,          // 对方已有而跳过的文件数
+    failed: usize,           // 失败的文件数
+    address: Option<String>, // 实际连接成功的地址
+    error: Option<String>,   // 最后一个错误
+}
+
+#[component]
+fn TargetResultRow(result: TargetResult) -> Element {
+    let (icon, color) = if result.failed == 0 { ("✅", "#15803d") } else { ("⚠️", "#b45309") };
+    let mut summary = format!("{} 成功", result.succeeded);
+    if result.skipped > 0 {
+        summary.push_str(&format!(", {} 跳过", result.skipped));
+    }
+    if result.failed > 0 {
+        summary.push_str(&format!(", {} 失败", result.failed));
+    }
+    if let Some(address) = &result.address {
+        summary.push_str(&format!(" · 通过 {}", address));
+    }
+    
+    rsx! {
+        div {
+            style: "font-size: 13px; color: {color};",
+            "{icon} {result.name}: {summary}"
+            if let Some(error) = &result.error {
+                span {
+                    style: "color: #64748b;",
+                    " ({error})"
                 }
             }
         }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,201 @@
+// src/dioxus_component/settings/backup.rs
+use dioxus::prelude::*;
+use chrono::Local;
+use crate::core::backup::{ProfileBackup, BACKUP_EXTENSION};
+use crate::core::db::AddressBook;
+use crate::dioxus_component::app::AppState;
+use crate::dioxus_component::error_message::describe_error;
+
+const INPUT_STYLE: &str = "
+    padding: 8px 10px;
+    border: 1px solid #ddd;
+    border-radius: 6px;
+    font-size: 14px;
+    box-sizing: border-box;
+";
+
+const BUTTON_STYLE: &str = "
+    padding: 8px 14px;
+    background-color: #007bff;
+    color: white;
+    border: none;
+    border-radius: 6px;
+    cursor: pointer;
+    font-size: 14px;
+";
+
+const RESTORE_BUTTON_STYLE: &str = "
+    padding: 8px 14px;
+    background: white;
+    color: #c62828;
+    border: 1px solid #ef9a9a;
+    border-radius: 6px;
+    cursor: pointer;
+    font-size: 14px;
+";
+
+// 档案的加密备份与恢复：身份密钥、联系人、白名单、接收记录和设置都在同一个文件中
+#[component]
+pub fn BackupSection(my_identity: Signal<String>) -> Element {
+    let mut passphrase = use_signal(String::new);
+    let mut confirm = use_signal(String::new);
+    let mut restore_passphrase = use_signal(String::new);
+    let mut status_message = use_signal(String::new);
+    let mut busy = use_signal(|| false);
+    let mut app_state = use_context::<AppState>();
+    
+    let export = move |_| {
+        let value = passphrase.read().clone();
+        if value != *confirm.read() {
+            status_message.set("两次输入的口令不一致".to_string());
+            return;
+        }
+        spawn(async move {
+            let file_name = format!("nearby-{}.{}", Local::now().format("%Y%m%d"), BACKUP_EXTENSION);
+            let file = rfd::AsyncFileDialog::new()
+                .set_title("导出备份")
+                .set_file_name(file_name)
+                .save_file()
+                .await;
+            let Some(file) = file else { return };
+            
+            busy.set(true);
+            match AddressBook::call(move || ProfileBackup::export(&value)).await {
+                Ok(archive) => match tokio::fs::write(file.path(), archive).await {
+                    Ok(()) => {
+                        passphrase.set(String::new());
+                        confirm.set(String::new());
+                        status_message.set(format!("已导出到 {}", file.path().display()));
+                    }
+                    Err(e) => status_message.set(format!("导出失败: {}", e)),
+                },
+                Err(e) => status_message.set(format!("导出失败: {}", describe_error(&e))),
+            }
+            busy.set(false);
+        });
+    };
+    
+    let restore = move |_| {
+        let value = restore_passphrase.read().clone();
+        spawn(async move {
+            let file = rfd::AsyncFileDialog::new()
+                .set_title("从备份恢复")
+                .add_filter("Nearby 备份", &[BACKUP_EXTENSION])
+                .pick_file()
+                .await;
+            let Some(file) = file else { return };
+            
+            busy.set(true);
+            match ProfileBackup::restore(file.path(), &value).await {
+                Ok(manifest) => {
+                    restore_passphrase.set(String::new());
+                    status_message.set(format!("已恢复 {} 的备份，原数据库已另存为 address_book.db.before-restore", manifest.created_at));
+                    if let Ok(identity) = AddressBook::call(AddressBook::get_my_identity).await {
+                        my_identity.set(identity);
+                    }
+                    // 恢复的身份私钥设置了口令时需要重新解锁
+                    if let Ok(true) = AddressBook::call(AddressBook::identity_key_locked).await {
+                        app_state.locked.set(true);
+                    }
+                }
+                Err(e) => status_message.set(format!("恢复失败: {}", describe_error(&e))),
+            }
+            busy.set(false);
+        });
+    };
+    
+    rsx! {
+        div {
+            class: "settings-item",
+            style: "
+                padding: 20px;
+                margin-bottom: 15px;
+                background-color: white;
+                border-radius: 8px;
+                border: 1px solid #e0e0e0;
+            ",
+            
+            div {
+                style: "
+                    color: #333;
+                    font-weight: bold;
+                    font-size: 16px;
+                    margin-bottom: 10px;
+                ",
+                "备份与恢复"
+            }
+            
+            p {
+                style: "margin: 0 0 12px 0; font-size: 13px; color: #666;",
+                "备份包含身份码和密钥、联系人、白名单、接收记录和设置，用口令加密，可在新电脑上恢复。"
+            }
+            
+            div {
+                style: "display: flex; flex-direction: column; gap: 8px; max-width: 320px;",
+                
+                input {
+                    style: INPUT_STYLE,
+                    r#type: "password",
+                    placeholder: "备份口令",
+                    value: "{passphrase}",
+                    oninput: move |e| passphrase.set(e.value()),
+                }
+                input {
+                    style: INPUT_STYLE,
+                    r#type: "password",
+                    placeholder: "确认备份口令",
+                    value: "{confirm}",
+                    oninput: move |e| confirm.set(e.value()),
+                }
+                button {
+                    style: BUTTON_STYLE,
+                    disabled: busy() || passphrase.read().is_empty(),
+                    onclick: export,
+                    "导出备份"
+                }
+            }
+            
+            div {
+                style: "
+                    display: flex;
+                    flex-direction: column;
+                    gap: 8px;
+                    max-width: 320px;
+                    margin-top: 15px;
+                    padding-top: 15px;
+                    border-top: 1px solid #f0f0f0;
+                ",
+                
+                div {
+                    style: "font-size: 13px; color: #c62828;",
+                    "恢复会替换当前的身份码、通讯录和设置。"
+                }
+                input {
+                    style: INPUT_STYLE,
+                    r#type: "password",
+                    placeholder: "备份口令",
+                    value: "{restore_passphrase}",
+                    oninput: move |e| restore_passphrase.set(e.value()),
+                }
+                button {
+                    style: RESTORE_BUTTON_STYLE,
+                    disabled: busy() || restore_passphrase.read().is_empty(),
+                    onclick: restore,
+                    "选择备份文件并恢复"
+                }
+            }
+            
+            if busy() {
+                div {
+                    style: "color: #666; font-size: 13px; margin-top: 10px;",
+                    "正在处理..."
+                }
+            } else if !status_message.read().is_empty() {
+                div {
+                    style: "color: #666; font-size: 13px; margin-top: 10px; word-break: break-all;",
+                    "{status_message}"
+                }
+            }
+        }
+    }
+}
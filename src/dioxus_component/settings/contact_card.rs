@@ -0,0 +1,155 @@
+// src/dioxus_component/settings/contact_card.rs
+use dioxus::prelude::*;
+use arboard::Clipboard;
+use crate::core::contact_exchange::ContactExchange;
+use crate::core::db::AddressBook;
+use crate::dioxus_component::error_message::describe_error;
+
+// 本机联系人二维码，对方扫描或导入链接后即可添加联系人
+#[component]
+pub fn ContactCardSection(my_identity: Signal<String>) -> Element {
+    let mut uri = use_signal(String::new);
+    let mut qr_svg = use_signal(String::new);
+    let mut error_message = use_signal(String::new);
+    let mut copied = use_signal(|| false);
+    
+    // 生成链接和二维码，身份码重置后重新生成
+    let refresh = move || {
+        spawn(async move {
+            let result = AddressBook::call(|| {
+                let uri = ContactExchange::my_uri()?;
+                let svg = ContactExchange::qr_code_svg(&uri)?;
+                Ok((uri, svg))
+            }).await;
+            match result {
+                Ok((new_uri, svg)) => {
+                    uri.set(new_uri);
+                    qr_svg.set(svg);
+                    error_message.set(String::new());
+                }
+                Err(e) => error_message.set(format!("生成联系人二维码失败: {}", describe_error(&e))),
+            }
+        });
+    };
+    
+    use_effect(move || {
+        my_identity.read();
+        refresh();
+    });
+    
+    let copy_uri = move |_| {
+        let text = uri.read().clone();
+        if let Ok(mut clipboard) = Clipboard::new() {
+            if clipboard.set_text(text).is_ok() {
+                copied.set(true);
+                spawn(async move {
+                    tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
+                    copied.set(false);
+                });
+            }
+        }
+    };
+    
+    rsx! {
+        div {
+            class: "settings-item",
+            style: "
+                padding: 20px;
+                margin-bottom: 15px;
+                background-color: white;
+                border-radius: 8px;
+                border: 1px solid #e0e0e0;
+            ",
+            
+            div {
+                style: "
+                    display: flex;
+                    justify-content: space-between;
+                    align-items: center;
+                    margin-bottom: 15px;
+                ",
+                
+                span {
+                    style: "
+                        color: #333;
+                        font-weight: bold;
+                        font-size: 16px;
+                    ",
+                    "联系人二维码"
+                }
+                
+                span {
+                    style: "color: #999;",
+                    "对方在通讯录中扫描图片或粘贴链接即可添加你"
+                }
+            }
+            
+            if !error_message.read().is_empty() {
+                div {
+                    style: "color: #d33; margin-bottom: 10px;",
+                    "{error_message}"
+                }
+            }
+            
+            div {
+                style: "display: flex; gap: 20px; align-items: center;",
+                
+                // 二维码
+                div {
+                    style: "width: 200px; height: 200px; flex-shrink: 0;",
+                    dangerous_inner_html: "{qr_svg}",
+                }
+                
+                div {
+                    style: "flex: 1; min-width: 0; display: flex; flex-direction: column; gap: 10px;",
+                    
+                    div {
+                        style: "
+                            background-color: #f5f5f5;
+                            padding: 10px;
+                            border-radius: 6px;
+                            border: 1px solid #e0e0e0;
+                            font-family: monospace;
+                            font-size: 12px;
+                            word-break: break-all;
+                            color: #333;
+                        ",
+                        "{uri}"
+                    }
+                    
+                    div {
+                        style: "display: flex; gap: 10px;",
+                        
+                        button {
+                            class: "settings-primary-button",
+                            style: "
+                                padding: 8px 14px;
+                                background-color: #007bff;
+                                color: white;
+                                border: none;
+                                border-radius: 6px;
+                                cursor: pointer;
+                            ",
+                            onclick: copy_uri,
+                            if copied() { "已复制" } else { "复制链接" }
+                        }
+                        
+                        // 网络变化后地址可能改变
+                        button {
+                            style: "
+                                padding: 8px 14px;
+                                background-color: #6c757d;
+                                color: white;
+                                border: none;
+                                border-radius: 6px;
+                                cursor: pointer;
+                            ",
+                            onclick: move |_| refresh(),
+                            "刷新地址"
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
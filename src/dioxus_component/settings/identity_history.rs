@@ -0,0 +1,165 @@
+// src/dioxus_component/settings/identity_history.rs
+use dioxus::prelude::*;
+use chrono::{Local, NaiveDateTime, TimeZone, Utc};
+use crate::core::db::{AddressBook, IdentityHistoryEntry, DB_TIME_FORMAT};
+use crate::dioxus_component::error_message::describe_error;
+
+// 更换过的身份码：未撤销的签名会在发送文件时出示给对方，证明新身份码属于同一个人
+#[component]
+pub fn IdentityHistorySection(my_identity: Signal<String>) -> Element {
+    let mut entries = use_signal(Vec::<IdentityHistoryEntry>::new);
+    let mut error_message = use_signal(String::new);
+    
+    let load_entries = move || {
+        spawn(async move {
+            match AddressBook::call(AddressBook::get_identity_history).await {
+                Ok(list) => {
+                    entries.set(list);
+                    error_message.set(String::new());
+                }
+                Err(e) => error_message.set(format!("加载历史身份码失败: {}", describe_error(&e))),
+            }
+        });
+    };
+    
+    // 身份码轮换后重新加载
+    use_effect(move || {
+        my_identity.read();
+        load_entries();
+    });
+    
+    let revoke = move |id: i64| {
+        spawn(async move {
+            match AddressBook::call(move || AddressBook::revoke_identity(id)).await {
+                Ok(()) => load_entries(),
+                Err(e) => error_message.set(format!("撤销失败: {}", describe_error(&e))),
+            }
+        });
+    };
+    
+    let entries_list = entries.read().clone();
+    
+    rsx! {
+        div {
+            class: "settings-item",
+            style: "
+                padding: 20px;
+                margin-bottom: 15px;
+                background-color: white;
+                border-radius: 8px;
+                border: 1px solid #e0e0e0;
+            ",
+            
+            div {
+                style: "
+                    display: flex;
+                    justify-content: space-between;
+                    align-items: center;
+                    margin-bottom: 10px;
+                ",
+                
+                span {
+                    style: "
+                        color: #333;
+                        font-weight: bold;
+                        font-size: 16px;
+                    ",
+                    "历史身份码"
+                }
+                
+                span {
+                    style: "color: #999;",
+                    "{entries_list.len()} 个"
+                }
+            }
+            
+            p {
+                style: "margin: 0 0 12px 0; font-size: 13px; color: #666;",
+                "撤销后不再向联系人证明该身份码的更换，尚未更新的联系人需要手动修改你的身份码。"
+            }
+            
+            if !error_message.read().is_empty() {
+                div {
+                    style: "color: #c62828; font-size: 13px; margin-bottom: 10px;",
+                    "{error_message}"
+                }
+            }
+            
+            if entries_list.is_empty() {
+                div {
+                    style: "color: #999; font-size: 13px;",
+                    "还没有更换过身份码"
+                }
+            }
+            
+            for entry in entries_list {
+                div {
+                    key: "{entry.id}",
+                    style: "
+                        display: flex;
+                        align-items: center;
+                        gap: 10px;
+                        padding: 10px 0;
+                        border-top: 1px solid #f0f0f0;
+                    ",
+                    
+                    div {
+                        style: "flex: 1; min-width: 0;",
+                        div {
+                            style: "
+                                font-family: monospace;
+                                font-size: 13px;
+                                color: #333;
+                                overflow: hidden;
+                                text-overflow: ellipsis;
+                                white-space: nowrap;
+                            ",
+                            title: "{entry.identity}",
+                            "{entry.identity}"
+                        }
+                        div {
+                            style: "font-size: 12px; color: #999; margin-top: 4px;",
+                            "更换于 {format_retired_at(&entry.retired_at)}"
+                        }
+                    }
+                    
+                    if entry.revoked {
+                        span {
+                            style: "font-size: 12px; color: #999; white-space: nowrap;",
+                            "已撤销"
+                        }
+                    } else if !entry.signed {
+                        span {
+                            style: "font-size: 12px; color: #e65100; white-space: nowrap;",
+                            title: "早期的身份码没有密钥，无法为新身份码签名",
+                            "未签名"
+                        }
+                    } else {
+                        button {
+                            style: "
+                                background: white;
+                                color: #c62828;
+                                border: 1px solid #ef9a9a;
+                                padding: 4px 10px;
+                                border-radius: 6px;
+                                cursor: pointer;
+                                font-size: 12px;
+                                white-space: nowrap;
+                            ",
+                            onclick: move |_| revoke(entry.id),
+                            "撤销"
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+// 数据库中的 UTC 时间转为本地时间显示
+fn format_retired_at(retired_at: &str) -> String {
+    match NaiveDateTime::parse_from_str(retired_at, DB_TIME_FORMAT) {
+        Ok(naive) => Utc.from_utc_datetime(&naive).with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string(),
+        Err(_) => retired_at.to_string(),
+    }
+}
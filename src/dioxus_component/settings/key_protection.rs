@@ -0,0 +1,184 @@
+// src/dioxus_component/settings/key_protection.rs
+use dioxus::prelude::*;
+use crate::core::db::AddressBook;
+use crate::dioxus_component::error_message::describe_error;
+
+const INPUT_STYLE: &str = "
+    padding: 8px 10px;
+    border: 1px solid #ddd;
+    border-radius: 6px;
+    font-size: 14px;
+    box-sizing: border-box;
+";
+
+const BUTTON_STYLE: &str = "
+    padding: 8px 14px;
+    background-color: #007bff;
+    color: white;
+    border: none;
+    border-radius: 6px;
+    cursor: pointer;
+    font-size: 14px;
+";
+
+const REMOVE_BUTTON_STYLE: &str = "
+    padding: 8px 14px;
+    background: white;
+    color: #c62828;
+    border: 1px solid #ef9a9a;
+    border-radius: 6px;
+    cursor: pointer;
+    font-size: 14px;
+";
+
+// 身份私钥的口令：设置后启动时需要输入口令解锁，私钥加密保存
+#[component]
+pub fn KeyProtectionSection() -> Element {
+    let mut protected = use_signal(|| false);
+    let mut current = use_signal(String::new);
+    let mut new_passphrase = use_signal(String::new);
+    let mut confirm = use_signal(String::new);
+    let mut error_message = use_signal(String::new);
+    let mut success_message = use_signal(String::new);
+    
+    let load_status = move || {
+        spawn(async move {
+            match AddressBook::call(AddressBook::identity_key_protected).await {
+                Ok(value) => protected.set(value),
+                Err(e) => error_message.set(format!("读取口令状态失败: {}", describe_error(&e))),
+            }
+        });
+    };
+    
+    use_hook(load_status);
+    
+    // new 为空表示移除口令
+    let mut submit = move |remove: bool| {
+        let current_value = current.read().clone();
+        let new_value = if remove { String::new() } else { new_passphrase.read().clone() };
+        if !remove && new_value != *confirm.read() {
+            error_message.set("两次输入的新口令不一致".to_string());
+            success_message.set(String::new());
+            return;
+        }
+        spawn(async move {
+            let result = AddressBook::call(move || {
+                AddressBook::set_identity_passphrase(&current_value, &new_value)
+            }).await;
+            match result {
+                Ok(()) => {
+                    current.set(String::new());
+                    new_passphrase.set(String::new());
+                    confirm.set(String::new());
+                    error_message.set(String::new());
+                    success_message.set(if remove { "已移除口令".to_string() } else { "口令已保存".to_string() });
+                    load_status();
+                }
+                Err(e) => {
+                    error_message.set(describe_error(&e));
+                    success_message.set(String::new());
+                }
+            }
+        });
+    };
+    
+    rsx! {
+        div {
+            class: "settings-item",
+            style: "
+                padding: 20px;
+                margin-bottom: 15px;
+                background-color: white;
+                border-radius: 8px;
+                border: 1px solid #e0e0e0;
+            ",
+            
+            div {
+                style: "
+                    display: flex;
+                    justify-content: space-between;
+                    align-items: center;
+                    margin-bottom: 10px;
+                ",
+                
+                span {
+                    style: "
+                        color: #333;
+                        font-weight: bold;
+                        font-size: 16px;
+                    ",
+                    "口令保护"
+                }
+                
+                span {
+                    style: if protected() { "color: #2e7d32;" } else { "color: #999;" },
+                    if protected() { "已启用" } else { "未启用" }
+                }
+            }
+            
+            p {
+                style: "margin: 0 0 12px 0; font-size: 13px; color: #666;",
+                "设置口令后身份私钥加密保存，每次启动需要输入口令解锁。口令遗忘后无法找回，只能轮换身份码。"
+            }
+            
+            div {
+                style: "display: flex; flex-direction: column; gap: 8px; max-width: 320px;",
+                
+                if protected() {
+                    input {
+                        style: INPUT_STYLE,
+                        r#type: "password",
+                        placeholder: "当前口令",
+                        value: "{current}",
+                        oninput: move |e| current.set(e.value()),
+                    }
+                }
+                input {
+                    style: INPUT_STYLE,
+                    r#type: "password",
+                    placeholder: "新口令",
+                    value: "{new_passphrase}",
+                    oninput: move |e| new_passphrase.set(e.value()),
+                }
+                input {
+                    style: INPUT_STYLE,
+                    r#type: "password",
+                    placeholder: "确认新口令",
+                    value: "{confirm}",
+                    oninput: move |e| confirm.set(e.value()),
+                }
+                
+                div {
+                    style: "display: flex; gap: 10px;",
+                    button {
+                        style: BUTTON_STYLE,
+                        disabled: new_passphrase.read().is_empty(),
+                        onclick: move |_| submit(false),
+                        if protected() { "修改口令" } else { "设置口令" }
+                    }
+                    if protected() {
+                        button {
+                            style: REMOVE_BUTTON_STYLE,
+                            disabled: current.read().is_empty(),
+                            onclick: move |_| submit(true),
+                            "移除口令"
+                        }
+                    }
+                }
+            }
+            
+            if !error_message.read().is_empty() {
+                div {
+                    style: "color: #c62828; font-size: 13px; margin-top: 10px;",
+                    "{error_message}"
+                }
+            }
+            if !success_message.read().is_empty() {
+                div {
+                    style: "color: #2e7d32; font-size: 13px; margin-top: 10px;",
+                    "{success_message}"
+                }
+            }
+        }
+    }
+}
@@ -1 +1,7 @@
 pub mod settings;
+mod preferences;
+mod contact_card;
+mod identity_history;
+mod key_protection;
+mod backup;
+mod profiles;
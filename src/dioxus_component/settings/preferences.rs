@@ -0,0 +1,504 @@
+// src/dioxus_component/settings/preferences.rs
+use dioxus::prelude::*;
+use std::time::Duration;
+use crate::core::db::AddressBook;
+use crate::core::filereceiver::{FileReceiver, ReceiveStatus};
+use crate::core::settings::{ConflictPolicy, SenderFolder, Settings as AppSettings};
+use crate::dioxus_component::error_message::describe_error;
+
+// 程序设置的各个分组，每次修改后立即保存并生效
+#[component]
+pub fn PreferenceSections() -> Element {
+    let mut settings = use_signal(AppSettings::current);
+    let mut error_message = use_signal(String::new);
+    let mut saved = use_signal(|| false);
+    
+    // 保存修改后的设置，失败时恢复为当前生效的设置
+    let mut save = move |new_settings: AppSettings| {
+        settings.set(new_settings.clone());
+        spawn(async move {
+            match AddressBook::call(move || new_settings.save()).await {
+                Ok(()) => {
+                    error_message.set(String::new());
+                    saved.set(true);
+                    tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
+                    saved.set(false);
+                }
+                Err(e) => {
+                    error_message.set(format!("保存设置失败: {}", describe_error(&e)));
+                    settings.set(AppSettings::current());
+                }
+            }
+        });
+    };
+    
+    let current = settings();
+    let download_dir = if current.download_dir.is_empty() {
+        FileReceiver::default_downloads_dir()
+            .map(|dir| format!("默认: {}", dir.display()))
+            .unwrap_or_else(|_| "默认目录".to_string())
+    } else {
+        current.download_dir.clone()
+    };
+    
+    rsx! {
+        if !error_message.read().is_empty() {
+            div {
+                style: "
+                    padding: 10px 14px;
+                    margin-bottom: 15px;
+                    background-color: #fff0f0;
+                    border: 1px solid #ffcccc;
+                    border-radius: 6px;
+                    color: #d33;
+                ",
+                "{error_message}"
+            }
+        }
+        if saved() {
+            div {
+                style: "
+                    padding: 10px 14px;
+                    margin-bottom: 15px;
+                    background-color: #f0fff4;
+                    border: 1px solid #b7ebc6;
+                    border-radius: 6px;
+                    color: #2f9e44;
+                ",
+                "设置已保存"
+            }
+        }
+        
+        SettingsCard {
+            title: "常规",
+            SettingRow {
+                label: "显示名称",
+                description: "对方添加你为联系人时看到的名称",
+                input {
+                    style: "{INPUT_STYLE}",
+                    r#type: "text",
+                    placeholder: "未设置",
+                    value: "{current.display_name}",
+                    onchange: move |e| {
+                        let mut s = settings();
+                        s.display_name = e.value().trim().to_string();
+                        save(s);
+                    }
+                }
+            }
+            SettingRow {
+                label: "启动时的接收模式",
+                description: "程序启动后自动进入的接收状态",
+                select {
+                    style: "{INPUT_STYLE}",
+                    value: current.startup_receive_mode.as_str(),
+                    onchange: move |e| {
+                        let mut s = settings();
+                        s.startup_receive_mode = ReceiveStatus::from_db_value(&e.value());
+                        save(s);
+                    },
+                    // 口令模式的口令每次都需要告知对方，不适合作为启动模式
+                    for mode in ReceiveStatus::ALL.into_iter().filter(|mode| *mode != ReceiveStatus::Code) {
+                        option {
+                            value: mode.as_str(),
+                            selected: mode == current.startup_receive_mode,
+                            "{mode.label()}"
+                        }
+                    }
+                }
+            }
+        }
+        
+        SettingsCard {
+            title: "接收",
+            SettingRow {
+                label: "下载目录",
+                description: "接收的文件保存在这里",
+                div {
+                    style: "display: flex; align-items: center; gap: 8px; max-width: 320px;",
+                    span {
+                        style: "
+                            flex: 1;
+                            min-width: 0;
+                            overflow: hidden;
+                            text-overflow: ellipsis;
+                            white-space: nowrap;
+                            font-size: 13px;
+                            color: #555;
+                        ",
+                        title: "{download_dir}",
+                        "{download_dir}"
+                    }
+                    button {
+                        style: "{SMALL_BUTTON_STYLE}",
+                        onclick: move |_| async move {
+                            let folder = rfd::AsyncFileDialog::new()
+                                .set_title("选择下载目录")
+                                .pick_folder()
+                                .await;
+                            if let Some(folder) = folder {
+                                let mut s = settings();
+                                s.download_dir = folder.path().to_string_lossy().to_string();
+                                save(s);
+                            }
+                        },
+                        "选择..."
+                    }
+                    if !current.download_dir.is_empty() {
+                        button {
+                            style: "{SMALL_BUTTON_STYLE}",
+                            onclick: move |_| {
+                                let mut s = settings();
+                                s.download_dir = String::new();
+                                save(s);
+                            },
+                            "恢复默认"
+                        }
+                    }
+                }
+            }
+            SettingRow {
+                label: "按发送方分类",
+                description: "在下载目录中为每个发送方建立子文件夹",
+                select {
+                    style: "{INPUT_STYLE}",
+                    value: current.routing.by_sender.as_str(),
+                    onchange: move |e| {
+                        let mut s = settings();
+                        s.routing.by_sender = SenderFolder::from_db_value(&e.value());
+                        save(s);
+                    },
+                    for folder in SenderFolder::ALL {
+                        option {
+                            value: folder.as_str(),
+                            selected: folder == current.routing.by_sender,
+                            "{folder.label()}"
+                        }
+                    }
+                }
+            }
+            SettingRow {
+                label: "按日期分类",
+                description: "按接收日期建立子文件夹，例如 2024-05-01",
+                input {
+                    r#type: "checkbox",
+                    checked: current.routing.by_date,
+                    onchange: move |e| {
+                        let mut s = settings();
+                        s.routing.by_date = e.checked();
+                        save(s);
+                    }
+                }
+            }
+            SettingRow {
+                label: "按扩展名分类",
+                description: "按文件扩展名建立子文件夹，例如 pdf、jpg",
+                input {
+                    r#type: "checkbox",
+                    checked: current.routing.by_extension,
+                    onchange: move |e| {
+                        let mut s = settings();
+                        s.routing.by_extension = e.checked();
+                        save(s);
+                    }
+                }
+            }
+            SettingRow {
+                label: "端口",
+                description: "接收时监听、发送时连接的端口，修改后接收服务器会重新启动",
+                NumberInput {
+                    value: current.port as u64,
+                    min: 1,
+                    max: u16::MAX as u64,
+                    on_change: move |v: u64| {
+                        let mut s = settings();
+                        s.port = v as u16;
+                        save(s);
+                    }
+                }
+            }
+            SettingRow {
+                label: "同名文件",
+                description: "下载目录中已有同名文件时的处理方式",
+                select {
+                    style: "{INPUT_STYLE}",
+                    value: current.conflict_policy.as_str(),
+                    onchange: move |e| {
+                        let mut s = settings();
+                        s.conflict_policy = ConflictPolicy::from_db_value(&e.value());
+                        save(s);
+                    },
+                    for policy in ConflictPolicy::ALL {
+                        option {
+                            value: policy.as_str(),
+                            selected: policy == current.conflict_policy,
+                            "{policy.label()}"
+                        }
+                    }
+                }
+            }
+            SettingRow {
+                label: "关闭等待时间（秒）",
+                description: "关闭接收或退出程序时，等待进行中的传输完成的最长时间",
+                NumberInput {
+                    value: current.drain_grace_secs,
+                    min: 0,
+                    max: 3600,
+                    on_change: move |v: u64| {
+                        let mut s = settings();
+                        s.drain_grace_secs = v;
+                        save(s);
+                    }
+                }
+            }
+        }
+        
+        SettingsCard {
+            title: "传输",
+            SettingRow {
+                label: "同时发送的文件数",
+                description: "发送多个文件时并发传输的数量",
+                NumberInput {
+                    value: current.concurrent_transfers as u64,
+                    min: 1,
+                    max: 64,
+                    on_change: move |v: u64| {
+                        let mut s = settings();
+                        s.concurrent_transfers = v as usize;
+                        save(s);
+                    }
+                }
+            }
+            SettingRow {
+                label: "最大接收连接数",
+                description: "同时接收的连接数，超出时对方会稍后自动重试",
+                NumberInput {
+                    value: current.max_connections as u64,
+                    min: 1,
+                    max: 256,
+                    on_change: move |v: u64| {
+                        let mut s = settings();
+                        s.max_connections = v as usize;
+                        save(s);
+                    }
+                }
+            }
+            SettingRow {
+                label: "每个地址的最大连接数（IPv6 按 /64 网段）",
+                description: "同一个来源地址同时接收的连接数",
+                NumberInput {
+                    value: current.max_connections_per_peer as u64,
+                    min: 1,
+                    max: 256,
+                    on_change: move |v: u64| {
+                        let mut s = settings();
+                        s.max_connections_per_peer = v as usize;
+                        save(s);
+                    }
+                }
+            }
+            SettingRow {
+                label: "握手超时（秒）",
+                description: "等待对方响应和完成加密握手的最长时间",
+                NumberInput {
+                    value: current.timeouts.handshake.as_secs(),
+                    min: 1,
+                    max: 600,
+                    on_change: move |v: u64| {
+                        let mut s = settings();
+                        s.timeouts.handshake = Duration::from_secs(v);
+                        save(s);
+                    }
+                }
+            }
+            SettingRow {
+                label: "文件信息超时（秒）",
+                description: "接收身份码、文件名和文件大小的最长时间",
+                NumberInput {
+                    value: current.timeouts.metadata.as_secs(),
+                    min: 1,
+                    max: 600,
+                    on_change: move |v: u64| {
+                        let mut s = settings();
+                        s.timeouts.metadata = Duration::from_secs(v);
+                        save(s);
+                    }
+                }
+            }
+            SettingRow {
+                label: "空闲超时（秒）",
+                description: "传输过程中两次收到数据之间的最长间隔",
+                NumberInput {
+                    value: current.timeouts.idle.as_secs(),
+                    min: 1,
+                    max: 3600,
+                    on_change: move |v: u64| {
+                        let mut s = settings();
+                        s.timeouts.idle = Duration::from_secs(v);
+                        save(s);
+                    }
+                }
+            }
+        }
+        
+        SettingsCard {
+            title: "连接频率限制",
+            SettingRow {
+                label: "统计时间窗口（秒）",
+                description: "统计同一地址连接次数的时间范围",
+                NumberInput {
+                    value: current.peer_guard.window.as_secs(),
+                    min: 1,
+                    max: 3600,
+                    on_change: move |v: u64| {
+                        let mut s = settings();
+                        s.peer_guard.window = Duration::from_secs(v);
+                        save(s);
+                    }
+                }
+            }
+            SettingRow {
+                label: "窗口内最大连接次数",
+                description: "超出后拒绝该地址的新连接",
+                NumberInput {
+                    value: current.peer_guard.max_attempts as u64,
+                    min: 1,
+                    max: 10000,
+                    on_change: move |v: u64| {
+                        let mut s = settings();
+                        s.peer_guard.max_attempts = v as usize;
+                        save(s);
+                    }
+                }
+            }
+            SettingRow {
+                label: "连续失败封禁次数",
+                description: "握手失败或被拒绝达到该次数后临时封禁该地址",
+                NumberInput {
+                    value: current.peer_guard.max_failures as u64,
+                    min: 1,
+                    max: 1000,
+                    on_change: move |v: u64| {
+                        let mut s = settings();
+                        s.peer_guard.max_failures = v as u32;
+                        save(s);
+                    }
+                }
+            }
+            SettingRow {
+                label: "封禁时长（秒）",
+                description: "临时封禁持续的时间",
+                NumberInput {
+                    value: current.peer_guard.ban_duration.as_secs(),
+                    min: 1,
+                    max: 86400,
+                    on_change: move |v: u64| {
+                        let mut s = settings();
+                        s.peer_guard.ban_duration = Duration::from_secs(v);
+                        save(s);
+                    }
+                }
+            }
+        }
+    }
+}
+
+const INPUT_STYLE: &str = "
+    width: 220px;
+    padding: 8px 10px;
+    border: 1px solid #ddd;
+    border-radius: 6px;
+    font-size: 14px;
+    box-sizing: border-box;
+";
+
+const SMALL_BUTTON_STYLE: &str = "
+    padding: 6px 12px;
+    background-color: #f5f5f5;
+    border: 1px solid #ddd;
+    border-radius: 6px;
+    cursor: pointer;
+    font-size: 13px;
+    white-space: nowrap;
+";
+
+// 一组设置项
+#[component]
+fn SettingsCard(title: &'static str, children: Element) -> Element {
+    rsx! {
+        div {
+            class: "settings-item",
+            style: "
+                padding: 20px;
+                margin-bottom: 15px;
+                background-color: white;
+                border-radius: 8px;
+                border: 1px solid #e0e0e0;
+            ",
+            
+            div {
+                style: "
+                    color: #333;
+                    font-weight: bold;
+                    font-size: 16px;
+                    margin-bottom: 10px;
+                ",
+                "{title}"
+            }
+            
+            {children}
+        }
+    }
+}
+
+// 单个设置项：左侧说明，右侧输入控件
+#[component]
+fn SettingRow(label: &'static str, description: &'static str, children: Element) -> Element {
+    rsx! {
+        div {
+            style: "
+                display: flex;
+                justify-content: space-between;
+                align-items: center;
+                gap: 20px;
+                padding: 10px 0;
+                border-top: 1px solid #f0f0f0;
+            ",
+            
+            div {
+                style: "flex: 1; min-width: 0;",
+                div {
+                    style: "color: #333; font-size: 14px;",
+                    "{label}"
+                }
+                div {
+                    style: "color: #999; font-size: 12px; margin-top: 4px;",
+                    "{description}"
+                }
+            }
+            
+            {children}
+        }
+    }
+}
+
+// 数字输入框，只有在输入合法且在范围内时才提交
+#[component]
+fn NumberInput(value: u64, min: u64, max: u64, on_change: EventHandler<u64>) -> Element {
+    rsx! {
+        input {
+            style: "{INPUT_STYLE}",
+            r#type: "number",
+            min: "{min}",
+            max: "{max}",
+            value: "{value}",
+            onchange: move |e| {
+                if let Ok(v) = e.value().trim().parse::<u64>() {
+                    if (min..=max).contains(&v) {
+                        on_change.call(v);
+                    }
+                }
+            }
+        }
+    }
+}
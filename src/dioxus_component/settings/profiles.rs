@@ -0,0 +1,163 @@
+// src/dioxus_component/settings/profiles.rs
+use dioxus::prelude::*;
+use crate::core::profile::Profiles;
+use crate::dioxus_component::app::AppState;
+use crate::dioxus_component::error_message::describe_error;
+
+const INPUT_STYLE: &str = "
+    flex: 1;
+    padding: 8px 10px;
+    border: 1px solid #ddd;
+    border-radius: 6px;
+    font-size: 14px;
+    box-sizing: border-box;
+";
+
+const BUTTON_STYLE: &str = "
+    padding: 6px 12px;
+    background-color: #f5f5f5;
+    border: 1px solid #ddd;
+    border-radius: 6px;
+    cursor: pointer;
+    font-size: 13px;
+    white-space: nowrap;
+";
+
+// 档案：每个档案有独立的身份码、通讯录、设置和默认下载目录，也可以用 --profile 参数启动
+#[component]
+pub fn ProfileSection() -> Element {
+    let app_state = use_context::<AppState>();
+    let profiles = use_signal(Profiles::list);
+    let mut new_name = use_signal(String::new);
+    let mut error_message = use_signal(String::new);
+    let mut switching = use_signal(|| false);
+    
+    let mut switch_to = move |name: String| {
+        if switching() {
+            return;
+        }
+        switching.set(true);
+        spawn(async move {
+            // 切换成功后所有页面重新加载，本组件随之卸载
+            if let Err(e) = app_state.switch_profile(name).await {
+                error_message.set(format!("切换档案失败: {}", describe_error(&e)));
+            }
+            switching.set(false);
+        });
+    };
+    
+    let create = move |_| {
+        let name = new_name.read().clone();
+        match Profiles::create(&name) {
+            Ok(name) => {
+                new_name.set(String::new());
+                switch_to(name);
+            }
+            Err(e) => error_message.set(describe_error(&e)),
+        }
+    };
+    
+    let active = Profiles::active();
+    let profile_list = profiles.read().clone();
+    
+    rsx! {
+        div {
+            class: "settings-item",
+            style: "
+                padding: 20px;
+                margin-bottom: 15px;
+                background-color: white;
+                border-radius: 8px;
+                border: 1px solid #e0e0e0;
+            ",
+            
+            div {
+                style: "
+                    display: flex;
+                    justify-content: space-between;
+                    align-items: center;
+                    margin-bottom: 10px;
+                ",
+                
+                span {
+                    style: "
+                        color: #333;
+                        font-weight: bold;
+                        font-size: 16px;
+                    ",
+                    "档案"
+                }
+                
+                span {
+                    style: "color: #999;",
+                    "当前: {active}"
+                }
+            }
+            
+            p {
+                style: "margin: 0 0 12px 0; font-size: 13px; color: #666;",
+                "每个档案有独立的身份码、通讯录、接收记录、设置和默认下载目录。启动时也可以用 --profile <名称> 选择档案。"
+            }
+            
+            for name in profile_list {
+                div {
+                    key: "{name}",
+                    style: "
+                        display: flex;
+                        align-items: center;
+                        gap: 10px;
+                        padding: 8px 0;
+                        border-top: 1px solid #f0f0f0;
+                    ",
+                    
+                    span {
+                        style: "flex: 1; font-size: 14px; color: #333;",
+                        "{name}"
+                    }
+                    
+                    if name == active {
+                        span {
+                            style: "font-size: 12px; color: #2e7d32;",
+                            "使用中"
+                        }
+                    } else {
+                        button {
+                            style: BUTTON_STYLE,
+                            disabled: switching(),
+                            onclick: move |_| switch_to(name.clone()),
+                            "切换"
+                        }
+                    }
+                }
+            }
+            
+            div {
+                style: "display: flex; gap: 10px; margin-top: 10px;",
+                input {
+                    style: INPUT_STYLE,
+                    placeholder: "新档案名称（英文字母、数字、- 和 _）",
+                    value: "{new_name}",
+                    oninput: move |e| new_name.set(e.value()),
+                }
+                button {
+                    style: BUTTON_STYLE,
+                    disabled: switching() || new_name.read().trim().is_empty(),
+                    onclick: create,
+                    "新建并切换"
+                }
+            }
+            
+            if switching() {
+                div {
+                    style: "color: #666; font-size: 13px; margin-top: 10px;",
+                    "正在切换档案，等待进行中的传输结束..."
+                }
+            } else if !error_message.read().is_empty() {
+                div {
+                    style: "color: #c62828; font-size: 13px; margin-top: 10px;",
+                    "{error_message}"
+                }
+            }
+        }
+    }
+}
@@ -1,6 +1,15 @@
 // src/dioxus_component/settings/setting.rs
 use dioxus::prelude::*;
 use crate::core::db::AddressBook;
+use crate::core::filereceiver::FileReceiver;
+use super::preferences::PreferenceSections;
+use super::contact_card::ContactCardSection;
+use super::identity_history::IdentityHistorySection;
+use super::key_protection::KeyProtectionSection;
+use super::backup::BackupSection;
+use super::profiles::ProfileSection;
+use crate::dioxus_component::error_message::describe_error;
+use log::error;
 
 #[component]
 pub fn Settings() -> Element {
@@ -8,7 +17,7 @@ pub fn Settings() -> Element {
     let error_message = use_signal(|| String::new());
     let success_message = use_signal(|| String::new());
     let copy_success = use_signal(|| false);
-    let reset_success = use_signal(|| false);
+    let rotate_success = use_signal(|| false);
     
     // 加载我的身份码
     use_effect(move || {
@@ -16,8 +25,8 @@ pub fn Settings() -> Element {
         let mut error_message = error_message.to_owned();
         
         spawn(async move {
-            if let Err(e) = AddressBook::init_db() {
-                error_message.set(format!("数据库初始化失败: {}", e));
+            if let Err(e) = AddressBook::call(AddressBook::init_db).await {
+                error_message.set(format!("数据库初始化失败: {}", describe_error(&e)));
                 return;
             }
             load_my_identity(my_identity, error_message).await;
@@ -67,33 +76,34 @@ pub fn Settings() -> Element {
         });
     };
     
-    // 重置身份码
-    let reset_identity = move |_| {
+    // 轮换身份码，旧身份码为新身份码签名，联系人下次收到文件时可确认更新
+    let rotate_identity = move |_| {
         let mut my_identity = my_identity.to_owned();
         let mut error_message = error_message.to_owned();
         let mut success_message = success_message.to_owned();
-        let mut reset_success = reset_success.to_owned();
+        let mut rotate_success = rotate_success.to_owned();
         
         spawn(async move {
-            match tokio::task::spawn_blocking(|| AddressBook::reset_my_identity()).await {
-                Ok(Ok(new_identity)) => {
-                    success_message.set("身份码重置成功".to_string());
+            match AddressBook::call(AddressBook::rotate_my_identity).await {
+                Ok(new_identity) => {
+                    success_message.set("身份码已轮换，联系人下次收到你的文件时会提示更新".to_string());
                     error_message.set(String::new());
                     my_identity.set(new_identity);
                     
-                    reset_success.set(true);
+                    // 握手的静态密钥来自身份私钥，重新启动接收服务器使用新密钥
+                    if let Err(e) = FileReceiver::restart_server().await {
+                        error!("重新启动接收服务器失败: {}", e);
+                    }
+                    
+                    rotate_success.set(true);
                     
                     spawn(async move {
                         tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
-                        reset_success.set(false);
+                        rotate_success.set(false);
                     });
                 }
-                Ok(Err(e)) => {
-                    error_message.set(format!("重置失败: {}", e));
-                    success_message.set(String::new());
-                }
                 Err(e) => {
-                    error_message.set(format!("任务执行失败: {}", e));
+                    error_message.set(format!("轮换失败: {}", describe_error(&e)));
                     success_message.set(String::new());
                 }
             }
@@ -134,10 +144,22 @@ pub fn Settings() -> Element {
                     error_message,
                     success_message,
                     copy_success,
-                    reset_success,
+                    rotate_success,
                     on_copy: copy_identity,
-                    on_reset: reset_identity,
+                    on_rotate: rotate_identity,
                 }
+                
+                ContactCardSection { my_identity }
+                
+                IdentityHistorySection { my_identity }
+                
+                KeyProtectionSection {}
+                
+                BackupSection { my_identity }
+                
+                ProfileSection {}
+                
+                PreferenceSections {}
             }
         }
     }
@@ -150,9 +172,9 @@ fn IdentitySection(
     error_message: Signal<String>,
     success_message: Signal<String>,
     copy_success: Signal<bool>,
-    reset_success: Signal<bool>,
+    rotate_success: Signal<bool>,
     on_copy: EventHandler,
-    on_reset: EventHandler,
+    on_rotate: EventHandler,
 ) -> Element {
     rsx! {
         div {
@@ -265,9 +287,9 @@ fn IdentitySection(
                     }
                 }
                 
-                // 重置按钮
+                // 轮换按钮
                 button {
-                    class: if *reset_success.read() {
+                    class: if *rotate_success.read() {
                         "settings-success-button"
                     } else {
                         "settings-danger-button"
@@ -286,25 +308,25 @@ fn IdentitySection(
                         justify-content: center;
                         gap: 8px;
                     ",
-                    onclick: move |_| on_reset.call(()),
+                    onclick: move |_| on_rotate.call(()),
                     
-                    if reset_success() {
+                    if rotate_success() {
                         div {
                             img {
                                 style: "width: 16px; height: 16px; position: relative; left: -2px; top: 2px;",
                                 src: asset!("assets/success-100.png"),
                                 alt: "  成功"
                             }
-                            span { "  已重置" }
+                            span { "  已轮换" }
                         }
                     } else {
                         div {
                             img {
                                 style: "width: 16px; height: 16px;",
                                 src: asset!("assets/reset-100.png"),
-                                alt: "  重置"
+                                alt: "  轮换"
                             }
-                            span { "  重置身份码" }
+                            span { "  轮换身份码" }
                         }
                     }
                 }
@@ -318,15 +340,12 @@ async fn load_my_identity(
     mut my_identity: Signal<String>,
     mut error_message: Signal<String>,
 ) {
-    match tokio::task::spawn_blocking(|| AddressBook::get_my_identity()).await {
-        Ok(Ok(identity)) => {
+    match AddressBook::call(AddressBook::get_my_identity).await {
+        Ok(identity) => {
             my_identity.set(identity);
         }
-        Ok(Err(e)) => {
-            error_message.set(format!("加载身份码失败: {}", e));
-        }
         Err(e) => {
-            error_message.set(format!("任务执行失败: {}", e));
+            error_message.set(format!("加载身份码失败: {}", describe_error(&e)));
         }
     }
 }
\ No newline at end of file
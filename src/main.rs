@@ -1,17 +1,35 @@
 use dioxus::prelude::*;
-use dioxus_desktop::{Config, WindowBuilder};
+use dioxus_desktop::{Config, WindowBuilder, WindowCloseBehaviour};
 use dioxus_component::App;
+use crate::core::profile::{Profiles, DEFAULT_PROFILE};
 
 mod dioxus_component;
 mod core;
 
 fn main() {
 	env_logger::init();
-	let cfg = Config::new().with_window(
-		WindowBuilder::new()
-			.with_title("Nearby")
-			.with_always_on_top(false)
-			.with_resizable(true),
-	);
+	
+	// 按 --profile 参数选择档案，指定的档案不存在时退出；没有指定且有多个档案时由启动界面选择
+	let profile = match Profiles::startup_profile(std::env::args().skip(1)) {
+		Ok(profile) => profile,
+		Err(e) => {
+			eprintln!("{}", e);
+			std::process::exit(2);
+		}
+	};
+	if let Some(profile) = &profile {
+		Profiles::open(profile);
+	}
+	
+	// 关闭窗口时先隐藏，仍有传输时 App 重新显示窗口显示排空进度，排空后退出程序
+	let title = Profiles::window_title(profile.as_deref().unwrap_or(DEFAULT_PROFILE));
+	let cfg = Config::new()
+		.with_window(
+			WindowBuilder::new()
+				.with_title(title)
+				.with_always_on_top(false)
+				.with_resizable(true),
+		)
+		.with_close_behaviour(WindowCloseBehaviour::LastWindowHides);
 	LaunchBuilder::new().with_cfg(cfg).launch(App);
 }
\ No newline at end of file